@@ -35,6 +35,7 @@ pub const ERROR_LINK_BROKEN: RESULT = 25; //链路中断
 pub const ERROR_BUFFER_TOO_SMALL: RESULT = 26; //缓冲区太小
 pub const ERROR_INIT_FAILED: RESULT = 27; //初始化失败
 pub const ERROR_NO_DATA: RESULT = 28; //没有数据
+pub const ERROR_WOULD_BLOCK: RESULT = 29; //非阻塞操作暂不能完成，需等待
 
 pub const ERROR_INVALID_IPADDR: RESULT = 50; //Invalid Ip Addr
 pub const ERROR_INVALID_MAC_ADDR: RESULT = 51;
@@ -104,6 +105,7 @@ fn init_error_map() {
     (ERROR_NO_OP,"No Operation"), 
 
     (ERROR_LINK_BROKEN,"Link Broken"), 
+    (ERROR_WOULD_BLOCK,"Operation Would Block"),
     (ERROR_OPEN_FILE,"Failed to Open File"), 
     (ERROR_FILE_NOT_FOUND,"File Not Found"), 
     (ERROR_DISK_FULL,"Disk is Full"),
@@ -0,0 +1,101 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+//! log-linear histogram, similar to HDR histogram, each power of 2 range is divided into 8 sub buckets,
+//! so the recorded value has a relative error less than 12.5%. the value larger than 2^40 is recorded as 2^40
+
+const SUB_BUCKET_BITS:u32 = 3;
+const SUB_BUCKET_COUNT:usize = 1<<SUB_BUCKET_BITS;
+const MAX_VALUE_BITS:u32 = 40;
+const MAX_VALUE:u64 = 1<<MAX_VALUE_BITS;
+const BUCKET_COUNT:usize = (MAX_VALUE_BITS as usize-SUB_BUCKET_BITS as usize+2)*SUB_BUCKET_COUNT;
+
+#[derive(Clone,Default)]
+pub struct histogram_t {
+    buckets:Vec<u64>,
+    count:u64,
+    sum:u64,
+    min:u64,
+    max:u64,
+}
+
+fn get_bucket_index(v:u64)->usize {
+    if v<SUB_BUCKET_COUNT as u64 {
+        return v as usize
+    }
+    let exp = 63-v.leading_zeros();
+    let shift = exp-SUB_BUCKET_BITS;
+    let sub = ((v>>shift) as usize) & (SUB_BUCKET_COUNT-1);
+    return (exp-SUB_BUCKET_BITS+1) as usize*SUB_BUCKET_COUNT+sub
+}
+
+//the highest value which falls into the bucket
+fn get_bucket_upper(idx:usize)->u64 {
+    if idx<SUB_BUCKET_COUNT {
+        return idx as u64
+    }
+    let shift = (idx/SUB_BUCKET_COUNT-1) as u32;
+    let sub = (idx % SUB_BUCKET_COUNT) as u64;
+    return ((SUB_BUCKET_COUNT as u64+sub)<<shift) + (1<<shift)-1
+}
+
+impl histogram_t {
+    pub fn new()->Self {
+        return Self::default()
+    }
+
+    pub fn record(&mut self,value:u64) {
+        let v = std::cmp::min(value,MAX_VALUE);
+        if self.buckets.is_empty() {
+            self.buckets = vec![0;BUCKET_COUNT];
+        }
+        self.buckets[get_bucket_index(v)]+=1;
+        if self.count==0 || v<self.min {
+            self.min = v;
+        }
+        if v>self.max {
+            self.max = v;
+        }
+        self.count+=1;
+        self.sum+=v;
+    }
+
+    pub fn count(&self)->u64 {
+        self.count
+    }
+    pub fn min(&self)->u64 {
+        self.min
+    }
+    pub fn max(&self)->u64 {
+        self.max
+    }
+    pub fn mean(&self)->u64 {
+        if self.count==0 {
+            return 0
+        }
+        self.sum/self.count
+    }
+
+    ///get the value at the percentile, percentile is in 0.0~100.0
+    pub fn percentile(&self,percentile:f64)->u64 {
+        if self.count==0 {
+            return 0
+        }
+        let p = percentile.max(0.0).min(100.0);
+        let target = std::cmp::max(((p/100.0)*self.count as f64).ceil() as u64,1);
+        let mut total = 0;
+        for (i,c) in self.buckets.iter().enumerate() {
+            total+=*c;
+            if total>=target {
+                return std::cmp::min(get_bucket_upper(i),self.max)
+            }
+        }
+        return self.max
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -34,6 +34,9 @@ pub use ringbuf::ts_ring_buffer_t;
 pub mod uuid;
 pub use uuid::uuid_t;
 
+pub mod histogram;
+pub use histogram::histogram_t;
+
 pub type rsm_time_t = time::OffsetDateTime;
 
 pub const UNIX_EPOCH_STRING:&str = "1970-1-1 00:00:00.000";
@@ -78,7 +78,8 @@ impl ring_buffer_t {
     ///从指定的偏移量处复制长度为copy_len的数据, the caller must checked the buffer and length is valid
     fn copy_data_uncheck(&self,offset:usize,copy_len:usize,buf:&mut [u8]) {
         let index=(self.head+offset) % self.size;
-        let first_slice_len = if index>self.tail {
+        //index==tail only if the buffer is full, the data is wrapped as well
+        let first_slice_len = if index>=self.tail {
             cmp::min(copy_len,self.size-index)
         } else {
             copy_len
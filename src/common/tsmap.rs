@@ -51,6 +51,31 @@ where
         self.lock.unlock();
         return v
     }
+    ///get a copy of the value under the lock, the copy is not affected by the later insert or remove
+    pub fn get_cloned(&self, k: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.lock.lock();
+        let v= self.inner.get(k).cloned();
+        self.lock.unlock();
+        return v
+    }
+    ///get the value of the key, or insert the value created by f if the key doesn't exist and the map has less than max_len items,
+    /// the lookup and the insert are done under one lock
+    pub fn get_or_insert_with<F>(&mut self, k: K, max_len: usize, f: F) -> Option<&mut V>
+    where
+        F: FnOnce() -> V,
+    {
+        self.lock.lock();
+        let v= if self.inner.len()<max_len || self.inner.contains_key(&k) {
+            Some(self.inner.entry(k).or_insert_with(f))
+        } else {
+            None
+        };
+        self.lock.unlock();
+        return v
+    }
     pub fn contains_key(&self,k:&K)->bool {
         return self.is_exist(k)
     }
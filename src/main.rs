@@ -43,7 +43,7 @@ impl rsm::Runnable for sample_app_t {
         static mut data:u64=2048;
         let msg= rsm::rsm_message_t::new::<u64>(10015,unsafe {&data}).unwrap();
         if cid.get_inst_id()==1 {
-            let dst=rsm::rsm_component_t::new(cid.get_cid(),1,2);
+            let dst=rsm::rsm_component_t::new(cid.get_cid(),cid.get_node_id(),2);
             let ret = rsm::send_asyn_msg(&dst, msg);
             if ret!=errcode::RESULT_SUCCESS {
                 println!("Send message failed,ret={}",ret);
@@ -93,7 +93,7 @@ fn main() {
     let mut msg=rsm::rsm_message_t::new::<u64>(10014, &content).unwrap();
     thread::sleep(Duration::from_millis(500));
     loop {      
-        let dst = rsm::rsm_component_t::new(1,1,1);        
+        let dst = rsm::rsm_component_t::new(1,rsm::get_local_node_id(),1);        
        
         rsm::send_asyn_msg(&dst, msg);
         content+=1;
@@ -172,6 +172,34 @@ pub mod ServicePorts {
 pub fn default_socket_addr() -> SocketAddr {
     return SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 0);
 }
+
+///Unix domain socket地址，文件路径或者抽象命名空间(仅Linux)中的名字
+#[derive(Clone,Debug,PartialEq,Eq,Hash)]
+pub enum UnixSocketAddr {
+    Unnamed,
+    Path(std::path::PathBuf),
+    Abstract(Vec<u8>),
+}
+
+impl UnixSocketAddr {
+    pub fn from_path(path:&str)->Self {
+        return Self::Path(std::path::PathBuf::from(path))
+    }
+
+    pub fn from_abstract_name(name:&[u8])->Self {
+        return Self::Abstract(name.to_vec())
+    }
+}
+
+impl std::fmt::Display for UnixSocketAddr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unnamed=>write!(f,"(unnamed)"),
+            Self::Path(p)=>write!(f,"{}",p.display()),
+            Self::Abstract(n)=>write!(f,"@{}",String::from_utf8_lossy(n)),
+        }
+    }
+}
 //将一个IP地址转化为一个16字节数组
 pub fn ipaddr_to_array(ip:&IpAddr)->(usize,[u8;IPV6_ADDR_LEN]) {
     let mut ip_addr:[u8;IPV6_ADDR_LEN]=[0;IPV6_ADDR_LEN];
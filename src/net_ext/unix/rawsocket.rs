@@ -87,6 +87,169 @@ pub unsafe fn to_socket_addr(
     }
 }
 
+///Unix domain socket地址转换为sockaddr_un
+fn to_sockaddr_un(addr:&UnixSocketAddr)->Result<(libc::sockaddr_un,libc::socklen_t),errcode::RESULT> {
+    use std::os::unix::ffi::OsStrExt;
+    let mut sa = unsafe { mem::zeroed::<libc::sockaddr_un>() };
+    sa.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    let path_offset = mem::size_of::<libc::sa_family_t>();
+    //路径以0结尾，抽象地址以0开头
+    let (name,name_len) = match addr {
+        UnixSocketAddr::Unnamed=>return Ok((sa,path_offset as libc::socklen_t)),
+        UnixSocketAddr::Path(p)=> {
+            let b = p.as_os_str().as_bytes();
+            (b.to_vec(),b.len()+1)
+        },
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        UnixSocketAddr::Abstract(n)=> {
+            let mut b = vec![0u8];
+            b.extend_from_slice(n);
+            let l = b.len();
+            (b,l)
+        },
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        UnixSocketAddr::Abstract(_)=>return Err(errcode::ERROR_NOT_SUPPORT),
+    };
+    if name_len>sa.sun_path.len() {
+        return Err(errcode::ERROR_MSG_TOO_LONG)
+    }
+    let len = path_offset+name_len;
+    for (i,c) in name.iter().enumerate() {
+        sa.sun_path[i] = *c as libc::c_char;
+    }
+    return Ok((sa,len as libc::socklen_t))
+}
+
+///sockaddr_un转换为Unix domain socket地址
+fn from_sockaddr_un(sa:&libc::sockaddr_un,len:libc::socklen_t)->UnixSocketAddr {
+    let path_offset = mem::size_of::<libc::sa_family_t>();
+    let len = (len as usize).min(mem::size_of::<libc::sockaddr_un>());
+    if len<=path_offset {
+        return UnixSocketAddr::Unnamed
+    }
+    let name:Vec<u8> = sa.sun_path[..len-path_offset].iter().map(|c| *c as u8).collect();
+    if name[0]==0 {
+        return UnixSocketAddr::Abstract(name[1..].to_vec())
+    }
+    let end = name.iter().position(|c| *c==0).unwrap_or(name.len());
+    if end==0 {
+        return UnixSocketAddr::Unnamed
+    }
+    return UnixSocketAddr::Path(std::path::PathBuf::from(String::from_utf8_lossy(&name[..end]).to_string()))
+}
+
+///绑定Unix domain socket地址，地址已被占用返回ERROR_ALREADY_EXIST，目录不存在返回ERROR_NOT_FOUND
+pub fn bind_unix(fd:RawFdType,addr:&UnixSocketAddr)->errcode::RESULT {
+    let (sa,len) = match to_sockaddr_un(addr) {
+        Ok(a)=>a,
+        Err(e)=>return e,
+    };
+    let res = unsafe { libc::bind(fd,std::ptr::addr_of!(sa) as *const libc::sockaddr, len) };
+    if res!=0 {
+        return match Error::last_os_error().raw_os_error() {
+            Some(libc::EADDRINUSE)=>errcode::ERROR_ALREADY_EXIST,
+            Some(libc::ENOENT)=>errcode::ERROR_NOT_FOUND,
+            Some(libc::EACCES)=>errcode::ERROR_NO_PERMISSION,
+            _=>errcode::ERROR_BIND_SOCKET,
+        }
+    }
+    return errcode::RESULT_SUCCESS
+}
+
+///连接Unix domain socket，本地连接立即完成，对端队列满时返回ERROR_WOULD_BLOCK
+pub fn connect_unix(fd:RawFdType,dst:&UnixSocketAddr)->errcode::RESULT {
+    let (sa,len) = match to_sockaddr_un(dst) {
+        Ok(a)=>a,
+        Err(e)=>return e,
+    };
+    let ret = unsafe { libc::connect(fd, std::ptr::addr_of!(sa) as *const libc::sockaddr, len) };
+    if ret==0 {
+        return errcode::RESULT_SUCCESS
+    }
+    return match Error::last_os_error().raw_os_error() {
+        Some(libc::EAGAIN) | Some(libc::EINPROGRESS)=>errcode::ERROR_WOULD_BLOCK,
+        Some(libc::ENOENT)=>errcode::ERROR_NOT_FOUND,
+        _=>errcode::ERROR_CONNECTION,
+    }
+}
+
+///接受Unix domain socket连接，返回新的socket和对端地址
+pub fn accept_unix(fd:RawFdType)->Result<(RawFdType,UnixSocketAddr),errcode::RESULT> {
+    let mut sa = unsafe { mem::zeroed::<libc::sockaddr_un>() };
+    let mut len = mem::size_of::<libc::sockaddr_un>() as libc::socklen_t;
+    let res = unsafe { libc::accept(fd,std::ptr::addr_of_mut!(sa) as *mut libc::sockaddr,&mut len as *mut libc::socklen_t) };
+    if res<0 {
+        if Error::last_os_error().kind()==io::ErrorKind::WouldBlock {
+            return Err(errcode::ERROR_WOULD_BLOCK)
+        }
+        return Err(errcode::ERROR_OS_CALL_FAILED)
+    }
+    return Ok((res as RawFdType,from_sockaddr_un(&sa, len)))
+}
+
+///向Unix domain socket地址发送报文
+pub fn send_to_unix(fd:RawFdType,buf:&[u8],dst:&UnixSocketAddr)->Result<usize,errcode::RESULT> {
+    let (sa,len) = match to_sockaddr_un(dst) {
+        Ok(a)=>a,
+        Err(e)=>return Err(e),
+    };
+    let rv = unsafe {
+        libc::sendto(fd, buf.as_ptr() as *const libc::c_void, buf.len(), 0,
+        std::ptr::addr_of!(sa) as *const libc::sockaddr, len)
+    };
+    if rv < 0 {
+        if Error::last_os_error().kind()==io::ErrorKind::WouldBlock {
+            return Err(errcode::ERROR_WOULD_BLOCK);
+        }
+        return Err(errcode::ERROR_SEND_MSG);
+    }
+    Ok(rv as usize)
+}
+
+///从Unix domain socket接收报文，返回大小和对端地址
+pub fn recv_from_unix(fd:RawFdType,buf:&mut [u8])->Result<(usize,UnixSocketAddr),errcode::RESULT> {
+    let mut sa = unsafe { mem::zeroed::<libc::sockaddr_un>() };
+    let mut len = mem::size_of::<libc::sockaddr_un>() as libc::socklen_t;
+    let rv = unsafe {
+        libc::recvfrom(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0,
+        std::ptr::addr_of_mut!(sa) as *mut libc::sockaddr, &mut len as *mut libc::socklen_t)
+    };
+    if rv < 0 {
+        if Error::last_os_error().kind()==io::ErrorKind::WouldBlock {
+            return Err(errcode::ERROR_WOULD_BLOCK);
+        }
+        return Err(errcode::ERROR_RECV_MSG);
+    }
+    Ok((rv as usize,from_sockaddr_un(&sa, len)))
+}
+
+///获取Unix domain socket对端进程的凭据，返回(pid,uid,gid)
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn get_peer_cred(fd:RawFdType)->Result<(i32,u32,u32),errcode::RESULT> {
+    let mut cred = unsafe { mem::zeroed::<libc::ucred>() };
+    let mut len = mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(fd as c_int, libc::SOL_SOCKET, libc::SO_PEERCRED,
+        &mut cred as *mut _ as *mut c_void, &mut len as *mut libc::socklen_t)
+    };
+    if ret!=0 {
+        return Err(errcode::ERROR_OS_CALL_FAILED)
+    }
+    Ok((cred.pid,cred.uid,cred.gid))
+}
+
+///获取Unix domain socket对端进程的凭据，返回(pid,uid,gid)，pid不可用时为0
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn get_peer_cred(fd:RawFdType)->Result<(i32,u32,u32),errcode::RESULT> {
+    let mut uid:libc::uid_t = 0;
+    let mut gid:libc::gid_t = 0;
+    let ret = unsafe { libc::getpeereid(fd as c_int, &mut uid, &mut gid) };
+    if ret!=0 {
+        return Err(errcode::ERROR_OS_CALL_FAILED)
+    }
+    Ok((0,uid as u32,gid as u32))
+}
+
 ///设置socket接收缓冲区大小，以字节计算
 pub fn set_socket_recvbuf(socket:RawFdType,buf_size:i32)->i32 {
     return  unsafe { 
@@ -237,6 +400,20 @@ pub fn accept(fd:RawFdType)->Result<(RawFdType,SocketAddr),errcode::RESULT> {
 
 }
 
+///获取socket绑定的本地地址
+pub fn get_local_addr(fd:RawFdType)->Result<SocketAddr,errcode::RESULT> {
+    let mut sock_addr = unsafe { mem::zeroed::<sockaddr_t>() };
+    let mut len=std::mem::size_of::<sockaddr_t>() as libc::socklen_t;
+    let res = unsafe { libc::getsockname(fd,std::ptr::addr_of_mut!(sock_addr) as *mut libc::sockaddr,&mut len as *mut libc::socklen_t) };
+    if res<0 {
+        return Err(errcode::ERROR_OS_CALL_FAILED)
+    }
+    return match unsafe { to_socket_addr(std::ptr::addr_of!(sock_addr)) } {
+        Err(_)=>Err(errcode::ERROR_INVALID_IPADDR),
+        Ok(a)=>Ok(a),
+    }
+}
+
 pub fn connect(fd:RawFdType,dst:&SocketAddr)->errcode::RESULT {
     let (addr,len)=sockaddr_t::from_socket_addr(dst);
     let ret = unsafe {
@@ -246,9 +423,31 @@ pub fn connect(fd:RawFdType,dst:&SocketAddr)->errcode::RESULT {
     if ret==0 {
         return errcode::RESULT_SUCCESS
     }
+    //非阻塞socket的连接正在进行
+    if Error::last_os_error().raw_os_error()==Some(libc::EINPROGRESS) {
+        return errcode::ERROR_WOULD_BLOCK
+    }
     return errcode::ERROR_OS_CALL_FAILED
 }
 
+///读取socket的待处理错误，用于获取非阻塞connect的结果
+pub fn get_socket_error(fd:RawFdType)->errcode::RESULT {
+    let mut err:c_int=0;
+    let mut len=std::mem::size_of::<c_int>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(fd as c_int, libc::SOL_SOCKET, libc::SO_ERROR,
+        &mut err as *mut _ as *mut c_void, &mut len as *mut libc::socklen_t)
+    };
+    if ret!=0 {
+        return errcode::ERROR_OS_CALL_FAILED
+    }
+    return match err {
+        0=>errcode::RESULT_SUCCESS,
+        libc::ETIMEDOUT=>errcode::ERROR_TIME_OUT,
+        _=>errcode::ERROR_CONNECTION,
+    }
+}
+
 ///设置网卡的混杂模式
 pub fn set_promisc_mode(fd:RawFdType,if_idx: i32, state: bool) ->errcode::RESULT {
     let packet_membership = if state {
@@ -277,6 +476,9 @@ pub fn set_promisc_mode(fd:RawFdType,if_idx: i32, state: bool) ->errcode::RESULT
 pub fn read_fd(fd: RawFd, buf: &mut [u8],flags:i32) -> Result<usize,errcode::RESULT> {
     let rv = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
     if rv < 0 {
+        if Error::last_os_error().kind()==io::ErrorKind::WouldBlock {
+            return Err(errcode::ERROR_WOULD_BLOCK);
+        }
         return Err(errcode::ERROR_RECV_MSG);
     }
 
@@ -288,7 +490,10 @@ pub fn write_fd(fd: RawFd, buf: &[u8],flags:i32) -> Result<usize,errcode::RESULT
     let rv = unsafe { libc::write(fd, buf.as_ptr() as *const libc::c_void, buf.len().try_into().unwrap()) };
     if rv < 0 {
         //println!("send packet error:{} ",Error::last_os_error());
-        return Err(errcode::ERROR_RECV_MSG);
+        if Error::last_os_error().kind()==io::ErrorKind::WouldBlock {
+            return Err(errcode::ERROR_WOULD_BLOCK);
+        }
+        return Err(errcode::ERROR_SEND_MSG);
     }
 
     Ok(rv as usize)
@@ -239,6 +239,22 @@ pub fn accept(fd:RawFdType)->Result<(RawFdType,SocketAddr),errcode::RESULT> {
 
 }
 
+///获取socket绑定的本地地址
+pub fn get_local_addr(fd:RawFdType)->Result<SocketAddr,errcode::RESULT> {
+    let mut sock_addr = unsafe { mem::zeroed::<sockaddr_t>() };
+    let mut len=std::mem::size_of_val(&sock_addr) as i32;
+    let res = unsafe {
+        WinSock::getsockname(fd as usize,std::ptr::addr_of_mut!(sock_addr) as *mut SOCKADDR,&mut len as *mut i32)
+    };
+    if res!=0 {
+        return Err(errcode::ERROR_OS_CALL_FAILED)
+    }
+    return match unsafe { to_socket_addr(std::ptr::addr_of!(sock_addr)) } {
+        Err(_)=>Err(errcode::ERROR_INVALID_IPADDR),
+        Ok(a)=>Ok(a),
+    }
+}
+
 pub fn connect(fd:RawFdType,dst:&SocketAddr)->errcode::RESULT {
     let (addr,len)=sockaddr_t::from_socket_addr(dst);
     let ret = unsafe {
@@ -248,9 +264,31 @@ pub fn connect(fd:RawFdType,dst:&SocketAddr)->errcode::RESULT {
     if ret==0 {
         return errcode::RESULT_SUCCESS
     }
+    //非阻塞socket的连接正在进行
+    if unsafe { WinSock::WSAGetLastError() }==WinSock::WSAEWOULDBLOCK {
+        return errcode::ERROR_WOULD_BLOCK
+    }
     return errcode::ERROR_OS_CALL_FAILED
 }
 
+///读取socket的待处理错误，用于获取非阻塞connect的结果
+pub fn get_socket_error(fd:RawFdType)->errcode::RESULT {
+    let mut err:i32=0;
+    let mut len:i32=4;
+    let ret = unsafe {
+        WinSock::getsockopt(fd as usize, WinSock::SOL_SOCKET as i32, WinSock::SO_ERROR as i32,
+        &mut err as *mut i32 as PSTR, &mut len as *mut i32)
+    };
+    if ret!=0 {
+        return errcode::ERROR_OS_CALL_FAILED
+    }
+    return match err {
+        0=>errcode::RESULT_SUCCESS,
+        WinSock::WSAETIMEDOUT=>errcode::ERROR_TIME_OUT,
+        _=>errcode::ERROR_CONNECTION,
+    }
+}
+
 #[cfg(windows)]
 pub fn set_promisc_mode(fd:RawFdType,if_idx: i32, state: bool) ->errcode::RESULT {
     errcode::RESULT_SUCCESS
@@ -263,6 +301,9 @@ pub fn read_fd(fd: RawSocket, buf: &mut [u8],flags:i32) -> Result<usize,errcode:
         WinSock::recv(fd as usize, buf.as_mut_ptr() as PSTR , buf.len() as i32, flags) 
     };
     if rv < 0 {
+        if unsafe { WinSock::WSAGetLastError() }==WinSock::WSAEWOULDBLOCK {
+            return Err(errcode::ERROR_WOULD_BLOCK);
+        }
         return Err(errcode::ERROR_RECV_MSG);
     }
 
@@ -276,6 +317,9 @@ pub fn write_fd(fd: RawSocket, buf: &[u8],flags:i32) -> Result<usize,errcode::RE
         WinSock::send(fd as usize, buf.as_ptr() as PCSTR, buf.len() as i32, flags)
     };
     if rv < 0 {
+        if unsafe { WinSock::WSAGetLastError() }==WinSock::WSAEWOULDBLOCK {
+            return Err(errcode::ERROR_WOULD_BLOCK);
+        }
         return Err(errcode::ERROR_SEND_MSG);
     }
 
@@ -8,6 +8,29 @@ use std::net::{IpAddr,SocketAddr};
 
 pub const RSM_DEF_LOG_SERVER_PORT:u16=61000;
 pub const RSM_OAM_SERVER_PORT:u16=12000;
+pub const RSM_DEF_TIMER_TICK_MSEC:u64=10;
+
+///transport protocol used to forward messages between rsm nodes
+#[derive(Deserialize,Serialize,Clone,Copy,Debug,PartialEq,Eq)]
+pub enum E_RSM_TRANSPORT_PROTO {
+    ///one message per datagram, no connection
+    TRANSPORT_UDP = 0,
+    ///length prefixed messages over a tcp connection per peer, reconnected automatically
+    TRANSPORT_TCP = 1,
+}
+
+impl Default for E_RSM_TRANSPORT_PROTO {
+    fn default() -> Self {
+        E_RSM_TRANSPORT_PROTO::TRANSPORT_UDP
+    }
+}
+
+///address of a rsm node, the local node listens on its own address
+#[derive(Deserialize,Serialize,Clone,Debug)]
+pub struct rsm_node_cfg_t {
+    pub node_id:u32,
+    pub addr:SocketAddr,
+}
 
 #[derive(Deserialize,Serialize,Clone,Debug)]
 pub struct rsm_init_cfg_t {
@@ -15,7 +38,18 @@ pub struct rsm_init_cfg_t {
     pub max_component_num:usize,
     pub oam_server_addr:SocketAddr, 
     pub log_config:xlog::log_service_config_t,
-     
+    ///node table, including the local node, the message sent to a component of another node is forwarded by the transport
+    #[serde(default)]
+    pub nodes:Vec<rsm_node_cfg_t>,
+    #[serde(default)]
+    pub transport:E_RSM_TRANSPORT_PROTO,
+    ///resolution of the rsm timer, 1ms for the high resolution timers, the default is 10ms
+    #[serde(default="default_timer_tick")]
+    pub timer_tick_msec:u64,
+}
+
+fn default_timer_tick()->u64 {
+    RSM_DEF_TIMER_TICK_MSEC
 }
 
 impl rsm_init_cfg_t {
@@ -31,6 +65,9 @@ impl rsm_init_cfg_t {
             max_component_num:MAX_COMPONENT_NUM,
             log_config:xlog::log_service_config_t::new_default(),
             oam_server_addr:oam_addr,
+            nodes:Vec::new(),
+            transport:E_RSM_TRANSPORT_PROTO::TRANSPORT_UDP,
+            timer_tick_msec:RSM_DEF_TIMER_TICK_MSEC,
         };
         if let Some(log_addr) = log_server_addr {
             cfg.log_config.self_addr=log_addr;
@@ -43,6 +80,14 @@ impl rsm_init_cfg_t {
         
         return cfg
     }
+
+    ///add a node to the node table, the address of an existing node is replaced
+    pub fn add_node(&mut self,node_id:u32,addr:&SocketAddr) {
+        match self.nodes.iter_mut().find(|n| n.node_id==node_id) {
+            None=>self.nodes.push(rsm_node_cfg_t { node_id, addr:addr.clone() }),
+            Some(n)=>n.addr=addr.clone(),
+        }
+    }
 }
 
 #[derive(Debug)]
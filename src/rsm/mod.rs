@@ -53,8 +53,25 @@
 //!
 //! *pub fn registry_component(cid:u32,attrs:&component_attrs_t,callback:rsm_new_task)->errcode::RESULT*
 //!
+//! *registry_component_with_policy* additionally takes a *component_policy_t*, which describes the dependency, power on, restart and watchdog policy below
+//!
 //! After the component registration is finished, the *start_rsm()* function should be called to running the system.
 //!
+//! Components are powered on in the order of registration, unless a component declares the components it *depends_on*.
+//! Each task instance receive a power on message and call *on_init*, the component with need_init_ack=true must call *power_on_ack()*
+//! when it is ready, otherwise after *init_timeout_msec* the scheduler either aborts the startup or skips the component according to *init_fail_policy*
+//!
+//! if a task instance panics, the scheduler logs the panic and restarts the instance by invoking the *rsm_new_task* callback again,
+//! according to the *restart_policy* of the component. the restart relies on unwinding, it has no effect if the application is built with panic="abort".
+//! the instances created by *rsm_new_boxed_task* and registered by *registry_boxed_component* are owned by rsm, the old instance is dropped when restarted
+//!
+//! a watchdog checks the tasks periodically, an alarm is raised if a message handler exceeds the component's *deadline_usec*,
+//! or the message queue is not draining for *queue_stall_msec*. alarms are logged by xlog, listed by the OAM */alarm* url and delivered to the callback registered by *register_alarm_callback*
+//!
+//! a component can also be registered after *start_rsm()*, it is powered on immediately, and *scale_component(cid,inst_num)* add or remove instances at runtime
+//!
+//! *stop_component(cid)* stops a single component, and *stop_rsm()* shutdown all the components in the reverse order of registration
+//!
 //!Runtime
 //!---
 //!every running task can be identified uniquely by **rsm_component_t**
@@ -66,11 +83,31 @@
 //!
 //! for the receiver side, the application use msg.decode::<T>(v) to restore the message to application defined type
 //!
+//! message body is encoded in json by default, application can choose a compact binary codec by *rsm_message_t::new_with_codec*,
+//! or move a raw buffer into the message by *rsm_message_t::new_raw* / *rsm_message_t::new_pkt_buf* to avoid encoding and copy
+//!
+//! components can also communicate by publish/subscribe, the message published to a topic is delivered to all the subscribers
+//! *pub fn subscribe_topic(topic:&str)->errcode::RESULT*
+//! *pub fn publish<'de,T>(topic:&str,msg_id:rsm_message_id_t,body:&T)->errcode::RESULT*
+//!
+//! a component can also send a request and wait for the response asynchronously, the response or the timeout event is delivered by *Runnable::on_response*
+//! *pub fn send_request<'de,T>(dst:&rsm_component_t,msg_id:rsm_message_id_t,body:&T,timeout_msec:u64)->Result<rsm_request_id_t,errcode::RESULT>*
+//!
+//! the receiver check msg.is_request(), then reply the request with *reply_to*
+//! *pub fn reply_to<'de,T>(req:&rsm_message_t,msg_id:rsm_message_id_t,body:&T)->errcode::RESULT*
+//!
+//! a system can be split into several processes, every process is a rsm node identified by *node_id*, the nodes are listed in the node table of *rsm_init_cfg_t*.
+//! the message sent to a component of another node is forwarded by udp or tcp, per-peer statistics are shown by the OAM */node* url
+//!
 //! RSM also provides a timer service, application can set timer simply by calling **set_timer** function, once the timer is set and expired, rsm task will receive a on_timer event, which is defined in the Runnable trait.
 //!
 //! *pub fn set_timer(dur_msec:u64,loop_count:u64,timer_data:usize)->Option<rsm_timer_id_t>*
 //! *pub fn kill_timer_by_id(timer_id:rsm_timer_id_t)->errcode::RESULT*
 //!
+//! timers run on a monotonic clock, *set_timer_at* arms a timer at an absolute deadline of *get_monotonic_msec*, and *modify_timer* re-arms a timer with a new period
+//! *set_timer_with* sets a timer carrying a typed payload, which is delivered by *on_message* and restored by msg.decode()
+//! *set_timer_for* sets a timer for another task from any thread, *set_callback_timer* sets a timer invoking a callback for the threads not managed by rsm, a timer can be killed by its owner or the creator only
+//!
 //! Diagnostic
 //! ===
 //! Developer and user can use rest api get running status and statistics
@@ -80,6 +117,7 @@
 //! help,*curl http://127.0.0.1:12000/rsm/help*
 //! get task running status, *curl http://127.0.0.1:12000/rsm/task?1:2*
 //! get component configuration,*curl http://127.0.0.1:12000/rsm/component?1*
+//! list the active timers of a task, *curl http://127.0.0.1:12000/rsm/timer?1024:1*
 //! 
 //! Application defined OAM API
 //! ---
@@ -113,7 +151,10 @@
 use crate::common::{self,errcode};
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr,SocketAddr};
+use std::sync::Arc;
 use serde_json;
+use bincode;
+use crate::net_ext::pktbuf::pkt_buf_handle_t;
 
 pub mod task;
 pub mod rsm_sched;
@@ -123,6 +164,10 @@ pub mod socket;
 pub mod config;
 pub mod xlog;
 pub mod oam;
+pub mod rsm_rpc;
+pub mod watchdog;
+pub mod rsm_transport;
+pub use watchdog::{rsm_alarm_t,rsm_alarm_callback,E_RSM_ALARM_TYPE};
 
 const MAX_COMPONENT_NUM:usize = 256;
 pub const RSM_MODULE_NAME: &str = "rust_rsm";
@@ -133,6 +178,8 @@ pub type rsm_node_id_t = u32;
 pub type rsm_timer_id_t = i32;
 
 pub type rsm_message_id_t = u32;
+///request correlation id, used to match a response with the request
+pub type rsm_request_id_t = u64;
 
 ///system & user cid scope definition
 pub const RSM_INVALID_CID:u32 = 0;
@@ -141,6 +188,8 @@ pub const RSM_SYSTEM_CID_START:u32 = 1;
 ///end of the CID reserved for system use
 pub const RSM_SYSTEM_CID_END:u32 = 1023;
 pub const RSM_USER_CID_START:u32 = 1024;
+///system component which forwards the messages to the other rsm nodes
+pub const RSM_TRANSPORT_CID:u32 = 2;
 ///maximum instance number per cid
 pub const RSM_MAX_INST_PER_CID:usize=16;
 ///allowed max message queue len
@@ -149,6 +198,7 @@ pub const RSM_MAX_QUEUE_LEN:usize = 16384;
 pub const RSM_MAX_MESSAGE_LEN:usize = 64000;
 
 pub const RSM_INVALID_TIMER_ID:i32=-1;
+pub const RSM_INVALID_REQUEST_ID:rsm_request_id_t=0;
 /// describe the task schedule priority, the REALTIME Priority is mapped to Linux/Windows Realtime priority
 #[derive(Copy,Clone,PartialEq,Debug,Eq,Serialize)]
 pub enum E_RSM_TASK_PRIORITY {
@@ -163,7 +213,7 @@ pub enum E_RSM_TASK_PRIORITY {
 /// identifier for a software module running instance, include the software module unique id and an instance id
 /// in RSM, every software module running instance(component instance or task) is a Finite State Machine(FSM),
 ///  which mapped to an OS native thread, process message event loop
-#[derive(Eq,PartialEq,Hash,Clone,Debug,Copy)]
+#[derive(Eq,PartialEq,Hash,Clone,Debug,Copy,Serialize,Deserialize)]
 pub struct rsm_component_t {
     cid:rsm_component_id_t,
     node_id:rsm_node_id_t,
@@ -187,6 +237,9 @@ impl rsm_component_t {
     pub fn get_inst_id(&self)->usize {
         self.inst_id
     }
+    pub fn get_node_id(&self)->rsm_node_id_t {
+        self.node_id
+    }
 }
 
 impl std::fmt::Display for rsm_component_t {
@@ -207,16 +260,24 @@ pub const SOCK_EVENT_NEW:SOCKET_EVENT= 1<<2;
 pub const SOCK_EVENT_ERR:SOCKET_EVENT= 1<<3;
 ///socket has been closed by remote peer
 pub const SOCK_EVENT_CLOSE:SOCKET_EVENT= 1<<4;
+///an asynchronous tcp connect is completed, the result is RESULT_SUCCESS, ERROR_CONNECTION(refused or unreachable) or ERROR_TIME_OUT
+pub const SOCK_EVENT_CONNECT:SOCKET_EVENT= 1<<5;
+///the TLS handshake of a connection is finished, the result is RESULT_SUCCESS, ERROR_AUTH_FAILED or ERROR_CONNECTION
+pub const SOCK_EVENT_TLS_HANDSHAKE:SOCKET_EVENT= 1<<6;
 
 #[derive(Clone,Debug,Serialize,Deserialize)]
 pub struct rsm_socket_event_t {
     pub socket_id:i32,
     pub sock_type:socket::SOCKET_TYPE,
     pub event:SOCKET_EVENT,
-    
+    ///result of SOCK_EVENT_CONNECT and SOCK_EVENT_TLS_HANDSHAKE, ERROR_LINK_BROKEN for a SOCK_EVENT_CLOSE if the socket is closed by OAM,
+    /// RESULT_SUCCESS for other events
+    pub result:errcode::RESULT,
 }
 ///Task create callback function, which must return a valid object reference implement **Runnale** trait
 pub type rsm_new_task=fn(cid:&rsm_component_t)->&'static mut dyn Runnable;
+///create a task instance owned by rsm, the instance is dropped when the task is restarted or stopped
+pub type rsm_new_boxed_task=fn(cid:&rsm_component_t)->Box<dyn Runnable>;
 ///Component must implement the Runnable Trait
 pub trait Runnable {
     ///task init, called first when the task instance is created
@@ -234,8 +295,34 @@ pub trait Runnable {
     fn is_inited(&self)->bool;
     ///task has been destroyed, reserved for future use
     fn on_close(&mut self,cid:&rsm_component_t);
+    ///response of a request sent by *send_request*, result is RESULT_SUCCESS and msg is the response message,
+    /// or result is ERROR_TIME_OUT and msg is None if no response is received in time
+    fn on_response(&mut self,_cid:&rsm_component_t,_req_id:rsm_request_id_t,_result:errcode::RESULT,_msg:Option<&rsm_message_t>) {
+    }
+}
+
+///the action taken by the scheduler if a component failed to acknowledge the power on in time
+#[derive(Copy,Clone,PartialEq,Debug,Eq,Serialize)]
+pub enum E_RSM_INIT_FAIL_POLICY {
+    ///stop the components already started, the remaining components are not started
+    INIT_FAIL_ABORT = 0,
+    ///power off the failed component and its dependants, continue to start the other components
+    INIT_FAIL_SKIP = 1,
 }
 
+///restart policy of a component when a task instance panics
+#[derive(Copy,Clone,PartialEq,Debug,Eq,Serialize)]
+pub enum E_RSM_RESTART_POLICY {
+    ///the task instance exits, the message sent to it is rejected
+    RESTART_NEVER = 0,
+    RESTART_ALWAYS = 1,
+    ///restart at most max_restart times in restart_window_sec, otherwise the task instance exits
+    RESTART_LIMITED = 2,
+}
+
+///default time to wait for the power on ack of a component
+pub const RSM_DEF_INIT_TIMEOUT_MSEC:u64 = 30000;
+
 /// describe the component attribute while register to the RSM
 #[derive(Eq,PartialEq,Clone,Serialize)]
 pub struct component_attrs_t {
@@ -258,7 +345,64 @@ impl component_attrs_t {
             need_init_ack:need_init_ack,        
         }
     }
-    
+}
+
+/// describe the startup, supervision and watchdog policy of a component, *registry_component* uses the default policy
+#[derive(Eq,PartialEq,Clone,Debug,Serialize)]
+pub struct component_policy_t {
+    ///the components must be powered on before this component
+    pub depends_on:Vec<rsm_component_id_t>,
+    ///max time to wait for the power on ack, 0 means wait forever
+    pub init_timeout_msec:u64,
+    pub init_fail_policy:E_RSM_INIT_FAIL_POLICY,
+    pub restart_policy:E_RSM_RESTART_POLICY,
+    pub max_restart:u32,
+    pub restart_window_sec:u64,
+    ///max run time of a message handler, the watchdog raise an alarm if exceeded, 0 means no deadline
+    pub deadline_usec:u64,
+    ///the watchdog raise an alarm if the queue is not empty but no message is dispatched in this time, 0 means disabled
+    pub queue_stall_msec:u64,
+}
+
+impl Default for component_policy_t {
+    fn default()->Self {
+        return Self {
+            depends_on:Vec::new(),
+            init_timeout_msec:RSM_DEF_INIT_TIMEOUT_MSEC,
+            init_fail_policy:E_RSM_INIT_FAIL_POLICY::INIT_FAIL_ABORT,
+            restart_policy:E_RSM_RESTART_POLICY::RESTART_NEVER,
+            max_restart:0,
+            restart_window_sec:0,
+            deadline_usec:0,
+            queue_stall_msec:0,
+        }
+    }
+}
+
+impl component_policy_t {
+    ///set the components which must be powered on before this component
+    pub fn set_dependency(&mut self,depends_on:&[rsm_component_id_t]) {
+        self.depends_on = depends_on.to_vec();
+    }
+
+    ///set the power on timeout and the failure policy
+    pub fn set_init_policy(&mut self,init_timeout_msec:u64,policy:E_RSM_INIT_FAIL_POLICY) {
+        self.init_timeout_msec = init_timeout_msec;
+        self.init_fail_policy = policy;
+    }
+
+    ///set the deadline budget of the message handler and the queue stall threshold checked by the watchdog
+    pub fn set_deadline(&mut self,deadline_usec:u64,queue_stall_msec:u64) {
+        self.deadline_usec = deadline_usec;
+        self.queue_stall_msec = queue_stall_msec;
+    }
+
+    ///set the restart policy, max_restart and restart_window_sec only take effect for RESTART_LIMITED
+    pub fn set_restart_policy(&mut self,policy:E_RSM_RESTART_POLICY,max_restart:u32,restart_window_sec:u64) {
+        self.restart_policy = policy;
+        self.max_restart = max_restart;
+        self.restart_window_sec = restart_window_sec;
+    }
 }
 
 ///begin of the rsm message id using by system
@@ -275,7 +419,30 @@ pub const RSM_MSG_ID_SLAVE_POWER_ON:u32 = 2;
 pub const RSM_MSG_ID_POWER_ON_ACK:u32 = 3;
 pub const RSM_MSG_ID_POWER_OFF:u32 = 4;
 pub const RSM_MSG_ID_TIMER:u32 = 10;
+pub const RSM_MSG_ID_REQ_TIMEOUT:u32 = 11;
 pub const RSM_MSG_ID_SOCKET:u32 = 12;
+pub const RSM_MSG_ID_TRANSPORT:u32 = 13;
+
+///message body codec, can be selected for each message
+#[derive(Copy,Clone,PartialEq,Debug,Eq,Serialize,Deserialize)]
+pub enum E_RSM_MSG_CODEC {
+    ///body is encoded to json string, the default codec
+    MSG_CODEC_JSON = 0,
+    ///body is encoded to compact binary format
+    MSG_CODEC_BINARY = 1,
+    ///body is a raw byte buffer or packet buffer, which is moved into the message without encoding
+    MSG_CODEC_RAW = 2,
+}
+
+#[derive(Clone,Debug)]
+enum rsm_msg_body_t {
+    Empty,
+    Json(String),
+    Binary(Vec<u8>),
+    Raw(Vec<u8>),
+    //shared by the clones of the message, e.g. published to several subscribers, the buffer is not copied
+    PktBuf(Option<Arc<pkt_buf_handle_t>>),
+}
 
 ///message object
 #[derive(Clone,Debug)]
@@ -284,49 +451,148 @@ pub struct rsm_message_t {
     timer_id:rsm_timer_id_t,
     timer_data:usize,
     sender:rsm_component_t,
-    msg_body:String,
+    req_id:rsm_request_id_t,
+    is_response:bool,
+    //the time in usec when the message is put into the receiver's queue
+    sent_at:u64,
+    msg_body:rsm_msg_body_t,
 }
 impl rsm_message_t {
-    pub fn new<'de,T>(msg_id:rsm_message_id_t,body:&T)->Option<rsm_message_t> 
-    where T:Sized+Serialize+Deserialize<'de> {
-        let msg_body = match serde_json::to_string(body) {
-            Ok(s)=>s,
-            Err(_)=>return None,
-        };
+    fn new_with_body(msg_id:rsm_message_id_t,body:rsm_msg_body_t)->rsm_message_t {
         let sender = match get_self_cid() {
             None=>rsm_component_t::new_zero(),
             Some(c)=>c,
         };
-        let msg=Self {
+        return Self {
             msg_id:msg_id,
             timer_id:0,
             timer_data:0,
             sender:sender,
-            msg_body:msg_body,
-        };
-        return Some(msg);
+            req_id:RSM_INVALID_REQUEST_ID,
+            is_response:false,
+            sent_at:0,
+            msg_body:body,
+        }
     }
 
-    pub(crate) fn new_timer_msg(timer_id:rsm_timer_id_t,timer_data:usize)->Option<rsm_message_t> {
-        let sender = match get_self_cid() {
-            None=>rsm_component_t::new_zero(),
-            Some(c)=>c,
-        };
-        let msg=Self {
-            msg_id:RSM_MSG_ID_TIMER,
-            timer_id:timer_id,
-            timer_data:timer_data,
-            sender:sender,
-            msg_body:String::default(),
+    ///create a message, the body is encoded in json format
+    pub fn new<'de,T>(msg_id:rsm_message_id_t,body:&T)->Option<rsm_message_t> 
+    where T:Sized+Serialize+Deserialize<'de> {
+        return Self::new_with_codec(msg_id, body, E_RSM_MSG_CODEC::MSG_CODEC_JSON)
+    }
+
+    ///create a message with specific codec, MSG_CODEC_RAW is not allowed, use new_raw or new_pkt_buf instead
+    pub fn new_with_codec<'de,T>(msg_id:rsm_message_id_t,body:&T,codec:E_RSM_MSG_CODEC)->Option<rsm_message_t> 
+    where T:Sized+Serialize+Deserialize<'de> {
+        let msg_body = match codec {
+            E_RSM_MSG_CODEC::MSG_CODEC_JSON=>match serde_json::to_string(body) {
+                Ok(s)=>rsm_msg_body_t::Json(s),
+                Err(_)=>return None,
+            },
+            E_RSM_MSG_CODEC::MSG_CODEC_BINARY=>match bincode::serialize(body) {
+                Ok(v)=>rsm_msg_body_t::Binary(v),
+                Err(_)=>return None,
+            },
+            E_RSM_MSG_CODEC::MSG_CODEC_RAW=>return None,
         };
+        return Some(Self::new_with_body(msg_id, msg_body));
+    }
+
+    ///create a message carrying raw bytes, the buffer is moved into the message without copy
+    pub fn new_raw(msg_id:rsm_message_id_t,body:Vec<u8>)->rsm_message_t {
+        return Self::new_with_body(msg_id, rsm_msg_body_t::Raw(body))
+    }
+
+    ///create a message carrying a packet buffer, the buffer is moved into the message without copy,
+    /// the clones of the message share the same buffer
+    pub fn new_pkt_buf(msg_id:rsm_message_id_t,buf:pkt_buf_handle_t)->rsm_message_t {
+        return Self::new_with_body(msg_id, rsm_msg_body_t::PktBuf(Some(Arc::new(buf))))
+    }
+
+    ///create a rsm system message without body
+    pub(crate) fn new_sys_msg(msg_id:rsm_message_id_t)->rsm_message_t {
+        return Self::new_with_body(msg_id, rsm_msg_body_t::Empty)
+    }
+
+    pub(crate) fn new_timer_msg(msg_id:rsm_message_id_t,timer_id:rsm_timer_id_t,timer_data:usize)->Option<rsm_message_t> {
+        let mut msg = Self::new_sys_msg(msg_id);
+        msg.timer_id = timer_id;
+        msg.timer_data = timer_data;
         return Some(msg);
     }
-    /// on the receiving side, using decode to restore the original data format
+
+    ///timer id of the message sent by a timer with payload, 0 for the other messages
+    pub fn get_timer_id(&self)->rsm_timer_id_t {
+        self.timer_id
+    }
+
+    ///request id carried by the message, RSM_INVALID_REQUEST_ID if the message is not a request or response
+    pub fn get_request_id(&self)->rsm_request_id_t {
+        self.req_id
+    }
+    ///return true if the message is a request sent by *send_request*, which expect a reply
+    pub fn is_request(&self)->bool {
+        self.req_id!=RSM_INVALID_REQUEST_ID && !self.is_response
+    }
+    ///return true if the message is a response to a previous request
+    pub fn is_response(&self)->bool {
+        self.req_id!=RSM_INVALID_REQUEST_ID && self.is_response
+    }
+
+    ///get the codec of the message body
+    pub fn get_codec(&self)->E_RSM_MSG_CODEC {
+        match self.msg_body {
+            rsm_msg_body_t::Binary(_)=>E_RSM_MSG_CODEC::MSG_CODEC_BINARY,
+            rsm_msg_body_t::Raw(_) | rsm_msg_body_t::PktBuf(_)=>E_RSM_MSG_CODEC::MSG_CODEC_RAW,
+            _=>E_RSM_MSG_CODEC::MSG_CODEC_JSON,
+        }
+    }
+    /// on the receiving side, using decode to restore the original data format, either json or binary encoded
     pub fn decode<'a,T>(&'a self)->Option<T>
     where T:Deserialize<'a> {
-        match serde_json::from_slice::<T>(self.msg_body.as_bytes()) {
-            Ok(v)=>Some(v),
-            Err(_)=>None,
+        match &self.msg_body {
+            rsm_msg_body_t::Json(s)=>match serde_json::from_slice::<T>(s.as_bytes()) {
+                Ok(v)=>Some(v),
+                Err(_)=>None,
+            },
+            rsm_msg_body_t::Binary(b)=>match bincode::deserialize::<T>(b.as_slice()) {
+                Ok(v)=>Some(v),
+                Err(_)=>None,
+            },
+            _=>None,
+        }
+    }
+
+    ///get the raw bytes body, return None if the message is not created by new_raw
+    pub fn get_raw_body(&self)->Option<&[u8]> {
+        match &self.msg_body {
+            rsm_msg_body_t::Raw(b)=>Some(b.as_slice()),
+            _=>None,
+        }
+    }
+
+    ///get the packet buffer body, return None if the message is not created by new_pkt_buf or the buffer is taken
+    pub fn get_pkt_buf(&self)->Option<&pkt_buf_handle_t> {
+        match &self.msg_body {
+            rsm_msg_body_t::PktBuf(Some(b))=>Some(b.as_ref()),
+            _=>None,
+        }
+    }
+
+    ///take the packet buffer out of the message, the buffer can only be taken once,
+    /// return None if the buffer is still shared by a clone of the message
+    pub fn take_pkt_buf(&mut self)->Option<pkt_buf_handle_t> {
+        let b = match &mut self.msg_body {
+            rsm_msg_body_t::PktBuf(b)=>b,
+            _=>return None,
+        };
+        match b.take().map(Arc::try_unwrap) {
+            None=>None,
+            Some(Ok(buf))=>Some(buf),
+            Some(Err(shared))=> {
+                *b = Some(shared);
+                None
+            },
         }
     }
 }
@@ -341,26 +607,94 @@ pub fn rsm_init(conf:&config::rsm_init_cfg_t)->errcode::RESULT {
     }
     oam::init_oam(&conf.oam_server_addr, &conf.log_config.self_addr);
     rsm_sched::init_scheduler(conf.max_component_num);
-    rsm_timer::init_timer();
+    rsm_timer::init_timer(conf.timer_tick_msec);
+    rsm_rpc::init_rpc();
+    watchdog::init_watchdog();
     //let mut log_conf = xlog::log_service_config_t::new_default();
     
     xlog::xlog_server::InitLogService(&conf.log_config);
     socket::socketpool::init_socket_pool();
+    rsm_transport::init_transport(conf);
     errcode::RESULT_SUCCESS
 }
 
+///get the node id of the local rsm instance, the default node id is 1 before rsm is initialized
+pub fn get_local_node_id()->rsm_node_id_t {
+    match unsafe {&gRsmConfig} {
+        None=>1,
+        Some(c)=>c.node_id,
+    }
+}
+
+static mut gSchedThread:Option<std::thread::JoinHandle<()>>=None;
 ///after application initialize RSM and register all their running component, then invoke start_rsm
 pub fn start_rsm() {
     println!("Start RSM, current={}",common::format_datetime(&std::time::SystemTime::now()));
-    std::thread::spawn(|| rsm_timer::start_timer_thread());
-    std::thread::spawn(|| rsm_sched::run());
-    
+    rsm_timer::start_timer_thread();
+    watchdog::start_watchdog();
+    let h = std::thread::spawn(|| rsm_sched::run());
+    unsafe {
+        gSchedThread = Some(h);
+    }
+}
+
+///stop the whole rsm runtime, all the components are stopped in the reverse order of registration,
+/// their timers are killed and sockets are closed, then the timer service is stopped and the log file is flushed.
+/// must not be called under a rsm task context
+pub fn stop_rsm()->errcode::RESULT {
+    if get_self_cid().is_some() {
+        return errcode::ERROR_INVALID_STATE
+    }
+    println!("Stop RSM, current={}",common::format_datetime(&std::time::SystemTime::now()));
+    //don't wait for the remaining components to power on
+    rsm_sched::abort_power_on();
+    if let Some(h) = unsafe { gSchedThread.take() } {
+        let _ = h.join();
+    }
+    watchdog::stop_watchdog();
+    rsm_sched::stop_all_components();
+    rsm_timer::stop_timer_thread();
+    xlog::xlog_server::StopLogService();
+    errcode::RESULT_SUCCESS
+}
+
+///clear the statistics and the message latency histograms of a task
+pub fn clear_task_stats(tid:&rsm_component_t)->errcode::RESULT {
+    return rsm_sched::clear_task_stats(tid);
+}
+
+///register a callback which is invoked by the watchdog when an alarm is raised or cleared
+pub fn register_alarm_callback(callback:rsm_alarm_callback)->errcode::RESULT {
+    return watchdog::register_alarm_callback(callback);
+}
+
+///change the instance number of a registered component, up to RSM_MAX_INST_PER_CID,
+/// the new instances are powered on immediately if rsm is running, scaling down stops the instances with the largest inst_id
+pub fn scale_component(cid:rsm_component_id_t,inst_num:usize)->errcode::RESULT {
+    return rsm_sched::scale_component(cid, inst_num);
+}
+
+///stop a component, all the task instances of the component receive a power off message and exit after *on_close* is called,
+/// the timers and sockets owned by the tasks are released. a task can't stop its own component
+pub fn stop_component(cid:rsm_component_id_t)->errcode::RESULT {
+    return rsm_sched::stop_component(cid);
 }
 
 ///Register a component to RSM, with the configuration is specified by attrs parameter
 /// callback is a TASK creation call back function, which is invoke by RSM before schedule the task instance
 pub fn registry_component(cid:u32,attrs:&component_attrs_t,callback:rsm_new_task)->errcode::RESULT {
-    return rsm_sched::registry_component(cid, attrs, callback)
+    return rsm_sched::registry_component(cid, attrs, &component_policy_t::default(), rsm_sched::task_factory_t::STATIC(callback))
+}
+
+///Register a component to RSM with the power on dependency, restart and watchdog policy
+pub fn registry_component_with_policy(cid:u32,attrs:&component_attrs_t,policy:&component_policy_t,callback:rsm_new_task)->errcode::RESULT {
+    return rsm_sched::registry_component(cid, attrs, policy, rsm_sched::task_factory_t::STATIC(callback))
+}
+
+///Register a component whose task instances are boxed and owned by RSM, an instance is dropped after the task
+/// is restarted or stopped, so the restarted instances are not leaked
+pub fn registry_boxed_component(cid:u32,attrs:&component_attrs_t,policy:&component_policy_t,callback:rsm_new_boxed_task)->errcode::RESULT {
+    return rsm_sched::registry_component(cid, attrs, policy, rsm_sched::task_factory_t::BOXED(callback))
 }
 
 /// get self component id
@@ -373,8 +707,9 @@ pub fn get_sender_cid()->Option<rsm_component_t>{
     return rsm_sched::get_sender_cid()
 }
 
-///power_on or init_ack, to keep task start order, not implement yet
-pub fn power_on_ack() {
+///acknowledge the power on, a component registered with need_init_ack=true must call it under its task context
+/// after initialization is finished, then the scheduler continue to power on the next component
+pub fn power_on_ack()->errcode::RESULT {
     return rsm_sched::power_on_ack();
 }
 ///send asyn message, normally put into the receiver's message queue
@@ -396,11 +731,80 @@ pub fn send_asyn_priority_msg(dst:&rsm_component_t,msg:rsm_message_t)->errcode::
     return rsm_sched::send_asyn_priority_msg(dst, msg);
 }
 
+///subscribe a topic, then the caller task receive all the messages published to the topic
+pub fn subscribe_topic(topic:&str)->errcode::RESULT {
+    return rsm_sched::subscribe_topic(topic);
+}
+
+///unsubscribe a topic subscribed by the caller task
+pub fn unsubscribe_topic(topic:&str)->errcode::RESULT {
+    return rsm_sched::unsubscribe_topic(topic);
+}
+
+///publish a message to a topic, the message is delivered to all the subscribers' message queue
+pub fn publish<'de,T>(topic:&str,msg_id:rsm_message_id_t,body:&T)->errcode::RESULT
+    where T:Sized+Serialize+Deserialize<'de> {
+    let msg=match rsm_message_t::new(msg_id, body) {
+        None=>return errcode::ERROR_ENCODE_MSG,
+        Some(m)=>m,
+    };
+    return rsm_sched::publish(topic, msg);
+}
+
+///send a request to dst component, return the request id which correlate the response.
+/// the response or a timeout is delivered to the caller by *Runnable::on_response*, timeout_msec=0 means never timeout
+pub fn send_request<'de,T>(dst:&rsm_component_t,msg_id:rsm_message_id_t,body:&T,timeout_msec:u64)->Result<rsm_request_id_t,errcode::RESULT>
+    where T:Sized+Serialize+Deserialize<'de> {
+    return rsm_rpc::send_request(dst, msg_id, body, timeout_msec);
+}
+
+///reply a request message, the response is sent to the sender of current message being processed
+pub fn reply_to<'de,T>(req:&rsm_message_t,msg_id:rsm_message_id_t,body:&T)->errcode::RESULT
+    where T:Sized+Serialize+Deserialize<'de> {
+    return rsm_rpc::reply_to(req, msg_id, body);
+}
+
 ///set a timer, loop for **loop_count** times every **dur_msec** milliseconds. if *loop_count* is 0, the timer will not stop util application kill the timer
 pub fn set_timer(dur_msec:u64,loop_count:u64,timer_data:usize)->Option<rsm_timer_id_t>{
     return rsm_timer::set_timer(dur_msec, loop_count, timer_data);
 }
 
+///set a one time timer which expires at an absolute deadline, on the monotonic clock returned by *get_monotonic_msec*
+pub fn set_timer_at(deadline_msec:u64,timer_data:usize)->Option<rsm_timer_id_t>{
+    return rsm_timer::set_timer_at(deadline_msec, timer_data);
+}
+
+///set a timer carrying a payload, on expiry the payload is delivered by *on_message* with msg_id, restore it by msg.decode()
+pub fn set_timer_with<'de,T>(dur_msec:u64,loop_count:u64,msg_id:rsm_message_id_t,payload:&T)->Option<rsm_timer_id_t>
+    where T:Sized+Serialize+Deserialize<'de> {
+    return rsm_timer::set_timer_with(dur_msec, loop_count, msg_id, payload);
+}
+
+///set a timer for the task dst which receives the *on_timer* event, it can be called by any thread, including the threads not managed by rsm
+pub fn set_timer_for(dst:&rsm_component_t,dur_msec:u64,loop_count:u64,timer_data:usize)->Option<rsm_timer_id_t> {
+    return rsm_timer::set_timer_for(dst, dur_msec, loop_count, timer_data);
+}
+
+///set a timer which invokes the callback under the timer thread on expiry, for the threads not managed by rsm
+pub fn set_callback_timer(dur_msec:u64,loop_count:u64,timer_data:usize,callback:rsm_timer::rsm_timer_callback)->Option<rsm_timer_id_t> {
+    return rsm_timer::set_callback_timer(dur_msec, loop_count, timer_data, callback);
+}
+
+///give a timer owned by the caller a name, which is shown by the OAM */timer* url
+pub fn set_timer_name(timer_id:rsm_timer_id_t,name:&str)->errcode::RESULT {
+    return rsm_timer::set_timer_name(timer_id, name);
+}
+
+///re-arm a timer owned by the caller with a new period, without kill and set again
+pub fn modify_timer(timer_id:rsm_timer_id_t,dur_msec:u64)->errcode::RESULT {
+    return rsm_timer::modify_timer(timer_id, dur_msec);
+}
+
+///milliseconds on the monotonic clock used by the timer service, it is not affected by the change of system time
+pub fn get_monotonic_msec()->u64 {
+    return rsm_timer::get_monotonic_msec();
+}
+
 /// stop the timer, given the timer_id returned by *set_timer* function
 pub fn kill_timer_by_id(timer_id:rsm_timer_id_t)->errcode::RESULT {
     return rsm_timer::kill_timer_by_id(timer_id);
@@ -0,0 +1,215 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+//rsm request/response service, correlate the response with the request by request id
+//the caller use send_request to send a request, the receiver use reply_to to send back the response,
+//response or timeout event is delivered to the caller by Runnable::on_response
+use super::*;
+use crate::common::{errcode,tsmap::TsHashMap};
+use std::sync::atomic::{AtomicU64,Ordering};
+
+const MAX_PENDING_REQUEST:usize = 64*1024;
+
+#[derive(Clone)]
+struct pending_request_t {
+    req_id:rsm_request_id_t,
+    requester:rsm_component_t,
+    //the task expected to reply, the node id is resolved as the scheduler does
+    dst:rsm_component_t,
+    msg_id:rsm_message_id_t,
+    timer_id:rsm_timer_id_t,
+    sent_at:u64,
+}
+
+#[derive(Clone,Default,Serialize)]
+pub struct rpc_stats_t {
+    pub pending:usize,
+    pub sent_request:u64,
+    pub recv_response:u64,
+    pub timeout:u64,
+    ///the responses not matching a pending request of the receiver, e.g. received after the timeout
+    pub late_response:u64,
+}
+
+static gReqIdSeq:AtomicU64=AtomicU64::new(1);
+static mut gPendingRequest:Option<TsHashMap<rsm_request_id_t,pending_request_t>>=None;
+//counters updated by the requester tasks concurrently
+struct rpc_counters_t {
+    sent_request:AtomicU64,
+    recv_response:AtomicU64,
+    timeout:AtomicU64,
+    late_response:AtomicU64,
+}
+static gRpcStats:rpc_counters_t=rpc_counters_t{sent_request:AtomicU64::new(0),recv_response:AtomicU64::new(0),
+    timeout:AtomicU64::new(0),late_response:AtomicU64::new(0)};
+
+pub(crate) fn init_rpc() {
+    unsafe {
+        if gPendingRequest.is_some() {
+            return
+        }
+        gPendingRequest=Some(TsHashMap::new(MAX_PENDING_REQUEST));
+    }
+}
+
+fn alloc_request_id()->rsm_request_id_t {
+    return gReqIdSeq.fetch_add(1, Ordering::SeqCst)
+}
+
+fn remove_pending(req_id:rsm_request_id_t)->Option<pending_request_t> {
+    let pm = match unsafe {&mut gPendingRequest} {
+        None=>return None,
+        Some(m)=>m,
+    };
+    return pm.remove(&req_id)
+}
+
+///send a request message, the caller must be a rsm task
+pub(crate) fn send_request<'de,T>(dst:&rsm_component_t,msg_id:rsm_message_id_t,body:&T,timeout_msec:u64)->Result<rsm_request_id_t,errcode::RESULT>
+    where T:Sized+Serialize+Deserialize<'de> {
+    let pm = match unsafe {&mut gPendingRequest} {
+        None=>return Err(errcode::ERROR_NOT_INITIALIZED),
+        Some(m)=>m,
+    };
+    let caller = match rsm_sched::get_self_cid() {
+        None=>return Err(errcode::ERROR_INVALID_STATE),
+        Some(c)=>c,
+    };
+    if pm.len()>=MAX_PENDING_REQUEST {
+        return Err(errcode::ERROR_OUTOF_MEM)
+    }
+    //the task expected to reply, a peer node's task is kept as is
+    let expected_dst = match rsm_sched::get_local_dst(dst) {
+        Err(e)=>return Err(e),
+        Ok(d)=>d.unwrap_or(dst.clone()),
+    };
+    let mut msg = match rsm_message_t::new(msg_id, body) {
+        None=>return Err(errcode::ERROR_ENCODE_MSG),
+        Some(m)=>m,
+    };
+    let req_id = alloc_request_id();
+    msg.req_id = req_id;
+
+    let timer_id = if timeout_msec>0 {
+        match rsm_timer::set_timer_ext(timeout_msec, 1, req_id as usize, RSM_MSG_ID_REQ_TIMEOUT) {
+            None=>return Err(errcode::ERROR_OUTOF_MEM),
+            Some(id)=>id,
+        }
+    } else {
+        RSM_INVALID_TIMER_ID
+    };
+
+    let req = pending_request_t {
+        req_id:req_id,
+        requester:caller,
+        dst:expected_dst,
+        msg_id:msg_id,
+        timer_id:timer_id,
+        sent_at:common::get_now_usec64(),
+    };
+    pm.insert(req_id, req);
+
+    let ret = rsm_sched::send_asyn_msg(dst, msg);
+    if ret!=errcode::RESULT_SUCCESS {
+        remove_pending(req_id);
+        if timer_id!=RSM_INVALID_TIMER_ID {
+            rsm_timer::kill_timer_by_id(timer_id);
+        }
+        return Err(ret)
+    }
+    gRpcStats.sent_request.fetch_add(1, Ordering::Relaxed);
+    return Ok(req_id)
+}
+
+///send the response of the request message to the request sender
+pub(crate) fn reply_to<'de,T>(req:&rsm_message_t,msg_id:rsm_message_id_t,body:&T)->errcode::RESULT
+    where T:Sized+Serialize+Deserialize<'de> {
+    if !req.is_request() {
+        return errcode::ERROR_INVALID_MSG
+    }
+    let dst = match rsm_sched::get_sender_cid() {
+        None=>return errcode::ERROR_INVALID_STATE,
+        Some(c)=>c,
+    };
+    let mut msg = match rsm_message_t::new(msg_id, body) {
+        None=>return errcode::ERROR_ENCODE_MSG,
+        Some(m)=>m,
+    };
+    msg.req_id = req.req_id;
+    msg.is_response = true;
+
+    return rsm_sched::send_asyn_msg(&dst, msg)
+}
+
+///called by the requester task upon receiving a response, return false if the request is no longer pending,
+/// or the response is not sent by the destination of the request to the requester
+pub(crate) fn on_response_received(receiver:&rsm_component_t,msg:&rsm_message_t)->bool {
+    let pm = match unsafe {&gPendingRequest} {
+        None=>return false,
+        Some(m)=>m,
+    };
+    let req = match pm.get_cloned(&msg.req_id) {
+        None=> {
+            gRpcStats.late_response.fetch_add(1, Ordering::Relaxed);
+            return false
+        },
+        Some(r)=>r,
+    };
+    //a misrouted or forged response doesn't consume the request of another task
+    if req.requester!=*receiver || req.dst!=msg.sender {
+        gRpcStats.late_response.fetch_add(1, Ordering::Relaxed);
+        rsm_sched::sched_log(xlog::LOG_LEVEL_WARNING,errcode::ERROR_INVALID_MSG,
+            &format!("response of request {}(msg_id={},sent {}us ago) dropped, from {} to {}, expected from {} to {}",
+                req.req_id,req.msg_id,common::get_now_usec64().saturating_sub(req.sent_at),msg.sender,receiver,req.dst,req.requester));
+        return false
+    }
+    remove_pending(req.req_id);
+    if req.timer_id!=RSM_INVALID_TIMER_ID {
+        rsm_timer::kill_timer_by_id(req.timer_id);
+    }
+    gRpcStats.recv_response.fetch_add(1, Ordering::Relaxed);
+    return true
+}
+
+///called by the requester task upon receiving the timeout timer, return false if the response has been received
+pub(crate) fn on_request_timeout(req_id:rsm_request_id_t)->bool {
+    if remove_pending(req_id).is_none() {
+        return false
+    }
+    gRpcStats.timeout.fetch_add(1, Ordering::Relaxed);
+    return true
+}
+
+///remove the pending requests of a task being stopped or restarted, their timeout timers are killed with the other timers of the task
+pub(crate) fn remove_requests_by_owner(cid:&rsm_component_t)->usize {
+    let pm = match unsafe {&mut gPendingRequest} {
+        None=>return 0,
+        Some(m)=>m,
+    };
+    let mut to_delete = Vec::new();
+    for (id,req) in pm.iter() {
+        if req.requester==*cid {
+            to_delete.push(*id);
+        }
+    }
+    pm.end_iter();
+    for id in to_delete.iter() {
+        pm.remove(id);
+    }
+    return to_delete.len()
+}
+
+pub fn get_rpc_stats()->rpc_stats_t {
+    return rpc_stats_t {
+        pending:match unsafe {&gPendingRequest} {
+            None=>0,
+            Some(m)=>m.len(),
+        },
+        sent_request:gRpcStats.sent_request.load(Ordering::Relaxed),
+        recv_response:gRpcStats.recv_response.load(Ordering::Relaxed),
+        timeout:gRpcStats.timeout.load(Ordering::Relaxed),
+        late_response:gRpcStats.late_response.load(Ordering::Relaxed),
+    }
+}
@@ -5,28 +5,132 @@
 
 //rsm scheduler, manager task control block, schedule task according to the message
 use super::{*, task::task_stats_t, oam::E_RSM_OAM_OP};
-use common::{tsmap::TsHashMap,errcode};
-use task::task_t;
+use common::{tsmap::TsHashMap,errcode,spin_lock::spin_lock_t,atomicqueue::AtomicDequeue};
+use task::{task_t,task_obj_t};
 #[cfg(windows)]
 use windows_sys::Win32::System::Threading;
 #[cfg(unix)]
 use libc;
 use common::sched;
-use std::{thread,time::Duration};
+use std::{thread,time::Duration,panic};
+use std::sync::{Arc,Mutex,atomic::{AtomicBool,Ordering}};
+use std::cell::UnsafeCell;
+use std::ops::{Deref,DerefMut};
 
+#[derive(Clone)]
 pub(crate) struct component_registry_t {
     pub(crate) cattr:component_attrs_t,
-    new_task:rsm_new_task,
+    pub(crate) policy:component_policy_t,
+    new_task:task_factory_t,
+    ///serialize the changes of the instance number, shared by the copies of the registry
+    scale_lock:Arc<Mutex<()>>,
+}
+
+///the task instance factory of a component
+#[derive(Clone,Copy)]
+pub(crate) enum task_factory_t {
+    STATIC(rsm_new_task),
+    BOXED(rsm_new_boxed_task),
+}
+
+impl task_factory_t {
+    fn new_task_obj(&self,tid:&rsm_component_t)->task_obj_t {
+        match self {
+            Self::STATIC(f)=>task_obj_t::STATIC(f(tid)),
+            Self::BOXED(f)=>task_obj_t::BOXED(f(tid)),
+        }
+    }
 }
 
 const RSM_SCHED_TASK_URL:&str="/task";
 const RSM_SCHED_COMPONENT_URL:&str="/component";
+const RSM_SCHED_POWER_ON_URL:&str="/poweron";
+
+static mut gComponentRegistry:Option<TsHashMap<u32,Arc<component_registry_t>>>=None;
 
-static mut gComponentRegistry:Option<TsHashMap<u32,component_registry_t>>=None;
+///shared reference of a task control block, held by the task thread and the callers looking it up,
+/// the task control block is released after it is removed from the registry and the last reference is dropped
+#[derive(Clone)]
+pub(crate) struct task_ref_t(Arc<UnsafeCell<task_t>>);
+unsafe impl Send for task_ref_t {}
+unsafe impl Sync for task_ref_t {}
 
-static mut gTaskRegistry:Option<TsHashMap<rsm_component_t,task_t>>=None;
+impl Deref for task_ref_t {
+    type Target = task_t;
+    fn deref(&self)->&task_t {
+        unsafe { &*self.0.get() }
+    }
+}
+impl DerefMut for task_ref_t {
+    fn deref_mut(&mut self)->&mut task_t {
+        unsafe { &mut *self.0.get() }
+    }
+}
+
+static mut gTaskRegistry:Option<TsHashMap<rsm_component_t,task_ref_t>>=None;
 static mut gTaskIdMap:Option<TsHashMap<sched::os_task_id_t,rsm_component_t>>=None;
-static mut gTaskList:Option<Vec<rsm_component_t>>=None;
+//task instances in the order of registration, read by the watchdog and OAM threads
+static gTaskList:Mutex<Vec<rsm_component_t>>=Mutex::new(Vec::new());
+static mut gTaskThreads:Option<TsHashMap<rsm_component_t,thread::JoinHandle<()>>>=None;
+
+const MAX_TOPIC_NUM:usize = 1024;
+struct topic_subscriber_t {
+    cid:rsm_component_t,
+    delivered:u64,
+    dropped:u64,
+}
+
+///publish/subscribe topic, the message published to the topic is delivered to all the subscribers
+struct topic_t {
+    name:String,
+    subscribers:Vec<topic_subscriber_t>,
+    published:u64,
+    lock:spin_lock_t,
+}
+
+///topic statistics for one subscriber
+#[derive(Clone,Serialize)]
+pub(crate) struct topic_sub_stats_t {
+    pub topic:String,
+    pub published:u64,
+    pub delivered:u64,
+    pub dropped:u64,
+}
+static mut gTopicRegistry:Option<TsHashMap<String,topic_t>>=None;
+
+///power on state of a component
+#[derive(Copy,Clone,PartialEq,Debug,Serialize)]
+pub(crate) enum E_POWER_ON_STATE {
+    POWER_ON_WAITING=0,
+    POWER_ON_IN_PROGRESS=1,
+    POWER_ON_COMPLETED=2,
+    POWER_ON_TIMEOUT=3,
+    //a depended component is not started
+    POWER_ON_SKIPPED=4,
+    //startup is aborted by another component
+    POWER_ON_ABORTED=5,
+}
+
+#[derive(Clone,Serialize)]
+pub(crate) struct power_on_record_t {
+    pub cid:rsm_component_id_t,
+    pub name:String,
+    pub order:usize,
+    pub state:E_POWER_ON_STATE,
+    pub start_at:u64,
+    pub elapsed_msec:u64,
+}
+
+const MAX_SCHED_QUEUE_LEN:usize = 4096;
+static mut gPowerOnRecords:Option<TsHashMap<rsm_component_id_t,power_on_record_t>>=None;
+//message queue of the scheduler, receive the power on ack
+static mut gSchedQueue:Option<AtomicDequeue<rsm_message_t>>=None;
+static mut gSchedLog:Option<xlog::xlogger_t>=None;
+//rsm is running, the component registered later is powered on immediately
+static mut gSchedRunning:bool=false;
+static mut gSchedLock:Option<spin_lock_t>=None;
+//stop_rsm is called, the power on in progress is aborted
+static gSchedAbort:AtomicBool=AtomicBool::new(false);
 
 ///initialize the scheduler
 pub fn init_scheduler(max_component:usize) {
@@ -34,99 +138,392 @@ pub fn init_scheduler(max_component:usize) {
         gComponentRegistry = Some(TsHashMap::new(max_component));
         gTaskRegistry = Some(TsHashMap::new(max_component*4));
         gTaskIdMap = Some(TsHashMap::new(max_component*4));
-        gTaskList=Some(Vec::new());
+        if let Ok(mut tl) = gTaskList.lock() {
+            tl.clear();
+        }
+        gTaskThreads = Some(TsHashMap::new(max_component*4));
+        gTopicRegistry = Some(TsHashMap::new(MAX_TOPIC_NUM));
+        gPowerOnRecords = Some(TsHashMap::new(max_component));
+        gSchedQueue = Some(AtomicDequeue::new(MAX_SCHED_QUEUE_LEN));
+        gSchedLock = Some(spin_lock_t::new());
     }
-    let urls = [RSM_SCHED_TASK_URL.to_string(),RSM_SCHED_COMPONENT_URL.to_string()];
+    gSchedAbort.store(false, Ordering::Release);
+    let urls = [RSM_SCHED_TASK_URL.to_string(),RSM_SCHED_COMPONENT_URL.to_string(),RSM_SCHED_POWER_ON_URL.to_string()];
     oam::RegisterOamModule(&urls, process_sched_oam);
 
 }
 
 fn put_into_tasklist(tid:rsm_component_t) {
-    unsafe {
-        if let Some(tl)=&mut gTaskList {
-            tl.push(tid);
-        }
+    if let Ok(mut tl) = gTaskList.lock() {
+        tl.push(tid);
     }
 }
-fn register_task(attrs:&component_attrs_t,callback:rsm_new_task)->errcode::RESULT {
+fn register_task(reg:&component_registry_t)->errcode::RESULT {
+    for i in 0..reg.cattr.inst_num {
+        register_task_inst(reg, i+1);
+    }
+    errcode::RESULT_SUCCESS
+}
+
+fn register_task_inst(reg:&component_registry_t,inst_id:usize)->Option<rsm_component_t> {
+    let attrs = &reg.cattr;
     let ptMap = match unsafe { &mut gTaskRegistry } {
-        None=>return errcode::ERROR_NOT_INITIALIZED,
+        None=>return None,
         Some(m)=>m,
     };
-
-    for i in 0..attrs.inst_num {
-        let tid = rsm_component_t::new(attrs.cid,1,i+1);
-        let cb_inst = (callback)(&tid);
-        let task = task_t::new(&tid, attrs.need_init_ack,attrs.qlen, attrs.priority,cb_inst);
-        if ptMap.insert(tid.clone(),task)==errcode::RESULT_SUCCESS {
-            put_into_tasklist(tid);
-        }
+    let tid = rsm_component_t::new(attrs.cid,get_local_node_id(),inst_id);
+    if ptMap.contains_key(&tid) {
+        return None
     }
-    errcode::RESULT_SUCCESS
+    let cb_inst = reg.new_task.new_task_obj(&tid);
+    let task = task_t::new(&tid, attrs.need_init_ack,attrs.qlen, attrs.priority,reg.policy.deadline_usec,cb_inst);
+    if ptMap.insert(tid.clone(),task_ref_t(Arc::new(UnsafeCell::new(task))))==errcode::RESULT_SUCCESS {
+        put_into_tasklist(tid.clone());
+        return Some(tid)
+    }
+    None
 }
 
 ///register one component to scheduler
-pub fn registry_component(cid:u32,attrs:&component_attrs_t,callback:rsm_new_task)->errcode::RESULT {
+pub(crate) fn registry_component(cid:u32,attrs:&component_attrs_t,policy:&component_policy_t,callback:task_factory_t)->errcode::RESULT {
     let pcMap = match unsafe { &mut gComponentRegistry } {
         None=>return errcode::ERROR_NOT_INITIALIZED,
         Some(m)=>m,
     };
     
-    let r = component_registry_t {
+    let r = Arc::new(component_registry_t {
         cattr:attrs.clone(),
+        policy:policy.clone(),
         new_task:callback,
-    };
+        scale_lock:Arc::new(Mutex::new(())),
+    });
 
-    let res = pcMap.insert(cid,r);
+    if attrs.inst_num==0 || attrs.inst_num>RSM_MAX_INST_PER_CID {
+        return errcode::ERROR_INVALID_PARAM
+    }
+    if pcMap.contains_key(&cid) {
+        return errcode::ERROR_ALREADY_EXIST
+    }
+    //the panic can't be caught if the application is built with panic="abort"
+    if cfg!(panic="abort") && policy.restart_policy!=E_RSM_RESTART_POLICY::RESTART_NEVER {
+        sched_log(xlog::LOG_LEVEL_WARNING,errcode::ERROR_NOT_SUPPORT,
+            &format!("component {}({}) restart policy has no effect, built with panic=\"abort\"",attrs.name,cid));
+    }
+    sched_lock();
+    let res = pcMap.insert(cid,r.clone());
     if res!=errcode::RESULT_SUCCESS {
+        sched_unlock();
         return res;
     }
-    register_task(attrs, callback);
-    errcode::RESULT_SUCCESS
+    register_task(&r);
+    let running = unsafe {gSchedRunning};
+    sched_unlock();
+    if !running {
+        return errcode::RESULT_SUCCESS
+    }
+    return power_on_dynamic_component(attrs,policy)
 }
 
+///power on a component registered after rsm is running, the component is removed if power on failed
+fn power_on_dynamic_component(attrs:&component_attrs_t,policy:&component_policy_t)->errcode::RESULT {
+    if let Some(d) = policy.depends_on.iter().find(|d| get_power_on_state(**d)!=Some(E_POWER_ON_STATE::POWER_ON_COMPLETED)) {
+        sched_log(xlog::LOG_LEVEL_ERROR,errcode::ERROR_INIT_FAILED,
+            &format!("component {}({}) is not powered on, depended component {} is not running",attrs.name,attrs.cid,d));
+        stop_component(attrs.cid);
+        return errcode::ERROR_INIT_FAILED
+    }
+    add_power_on_record(attrs.cid, &attrs.name);
+    let ret = power_on_component(attrs.cid,policy.init_timeout_msec);
+    if ret!=errcode::RESULT_SUCCESS {
+        sched_log(xlog::LOG_LEVEL_ERROR,ret,
+            &format!("component {}({}) power on timeout,timeout={}ms, component removed",attrs.name,attrs.cid,policy.init_timeout_msec));
+        stop_component(attrs.cid);
+    }
+    return ret
+}
 
 
-///run the scheduler
-pub fn run() {
-    let tEntries = match unsafe {&mut gTaskRegistry} {
+
+pub(crate) fn sched_log(level:xlog::LOG_LEVEL,err:errcode::RESULT,desc:&String) {
+    let log = match unsafe {&mut gSchedLog} {
         None=>return,
-        Some(e)=>e,
+        Some(l)=>l,
     };
-    let tl = match unsafe {&gTaskList} {
+    match level {
+        xlog::LOG_LEVEL_ERROR=>log.Errorf("[rsm_sched]", err, desc),
+        xlog::LOG_LEVEL_WARNING=>log.Warningf("[rsm_sched]", err, desc),
+        _=>log.Infof("[rsm_sched]", err, desc),
+    }
+}
+
+fn sched_lock() {
+    if let Some(l) = unsafe {&gSchedLock} {
+        l.lock();
+    }
+}
+
+fn sched_unlock() {
+    if let Some(l) = unsafe {&gSchedLock} {
+        l.unlock();
+    }
+}
+
+fn add_power_on_record(cid:rsm_component_id_t,name:&String) {
+    let recs = match unsafe {&mut gPowerOnRecords} {
         None=>return,
-        Some(l)=>l,
+        Some(r)=>r,
+    };
+    let rec = power_on_record_t {
+        cid:cid,
+        name:name.clone(),
+        order:recs.len()+1,
+        state:E_POWER_ON_STATE::POWER_ON_WAITING,
+        start_at:0,
+        elapsed_msec:0,
     };
+    recs.insert(cid, rec);
+}
 
-    let mut ts=Vec::new();
+fn set_power_on_state(cid:rsm_component_id_t,state:E_POWER_ON_STATE) {
+    let rec = match unsafe {&mut gPowerOnRecords} {
+        None=>return,
+        Some(r)=> match r.get_mut(&cid) {
+            None=>return,
+            Some(rec)=>rec,
+        },
+    };
+    let now = common::get_now_usec64();
+    if state==E_POWER_ON_STATE::POWER_ON_IN_PROGRESS {
+        rec.start_at = now;
+    } else if rec.state==E_POWER_ON_STATE::POWER_ON_IN_PROGRESS {
+        rec.elapsed_msec = (now-rec.start_at)/1000;
+    }
+    rec.state = state;
+}
 
-    for t in tl {
-        if let Some(task)=tEntries.get(t) {
-            ts.push((t,task.clone()));
+fn get_power_on_state(cid:rsm_component_id_t)->Option<E_POWER_ON_STATE> {
+    match unsafe {&gPowerOnRecords} {
+        None=>None,
+        Some(r)=> match r.get(&cid) {
+            None=>None,
+            Some(rec)=>Some(rec.state),
+        },
+    }
+}
+
+///get the component power on order, a component is started after all the components it depends on
+fn get_power_on_order()->Vec<rsm_component_id_t> {
+    let mut cids:Vec<rsm_component_id_t> = Vec::new();
+    for t in get_all_tasks().iter() {
+        if !cids.contains(&t.cid) {
+            cids.push(t.cid);
         }
     }
-    /*for (t,task) in tEntries.iter() {
-        //println!("Spawn a task,id={},inst={}",t.cid,t.inst_id);
-        ts.push((t,task.clone()));
+
+    let mut ordered:Vec<rsm_component_id_t>=Vec::new();
+    let mut remaining = cids.clone();
+    while !remaining.is_empty() {
+        let mut progress = false;
+        let mut i = 0;
+        while i<remaining.len() {
+            let ready = match get_component_registry(remaining[i]) {
+                None=>true,
+                Some(c)=>c.policy.depends_on.iter().all(|d| ordered.contains(d) || !cids.contains(d)),
+            };
+            if ready {
+                ordered.push(remaining.remove(i));
+                progress = true;
+            } else {
+                i+=1;
+            }
+        }
+        if !progress {
+            sched_log(xlog::LOG_LEVEL_ERROR,errcode::ERROR_COLLISION,
+                &format!("dependency loop detected among components {:?}, start them in registration order",remaining));
+            ordered.extend(remaining.drain(..));
+        }
+    }
+    return ordered
+}
+
+///get all the task instances in the order of registration
+pub(crate) fn get_all_tasks()->Vec<rsm_component_t> {
+    match gTaskList.lock() {
+        Err(_)=>Vec::new(),
+        Ok(tl)=>tl.clone(),
+    }
+}
+
+pub(crate) fn get_task_qlen(tid:&rsm_component_t)->usize {
+    match get_task_inst(tid) {
+        None=>0,
+        Some(t)=>t.get_qlen(),
+    }
+}
+
+fn get_component_tasks(cid:rsm_component_id_t)->Vec<rsm_component_t> {
+    match gTaskList.lock() {
+        Err(_)=>Vec::new(),
+        Ok(tl)=>tl.iter().filter(|t| t.cid==cid).cloned().collect(),
+    }
+}
+
+fn is_task_init_completed(tid:&rsm_component_t)->bool {
+    match get_task_inst(tid) {
+        None=>false,
+        Some(t)=>t.is_init_completed(),
     }
-    tEntries.end_iter(); */
-    for t in &ts {
-        std::thread::spawn(|| schedule_task(t.0.clone()));
-        while !t.1.is_init_completed() {
-            thread::sleep(Duration::from_millis(10));
+}
+
+///process the message sent to the scheduler
+fn process_sched_msg() {
+    let q = match unsafe {&mut gSchedQueue} {
+        None=>return,
+        Some(q)=>q,
+    };
+    while let Some(msg) = q.pop_front() {
+        if msg.msg_id!=RSM_MSG_ID_POWER_ON_ACK {
+            continue;
+        }
+        if let Some(mut t) = get_task_inst(&msg.sender) {
+            t.set_init_acked();
         }
     }
+}
 
+///spawn the tasks of a component, send the power on message and wait for the ack
+fn power_on_component(cid:rsm_component_id_t,timeout_msec:u64)->errcode::RESULT {
+    set_power_on_state(cid, E_POWER_ON_STATE::POWER_ON_IN_PROGRESS);
+    let ret = power_on_tasks(&get_component_tasks(cid), timeout_msec);
+    if ret==errcode::RESULT_SUCCESS {
+        set_power_on_state(cid, E_POWER_ON_STATE::POWER_ON_COMPLETED);
+    } else {
+        set_power_on_state(cid, E_POWER_ON_STATE::POWER_ON_TIMEOUT);
+    }
+    return ret
+}
 
+fn power_on_tasks(tasks:&[rsm_component_t],timeout_msec:u64)->errcode::RESULT {
+    for tid in tasks {
+        let id = tid.clone();
+        let h = std::thread::spawn(move || schedule_task(id));
+        if let Some(th) = unsafe {&mut gTaskThreads} {
+            th.insert(tid.clone(), h);
+        }
+        let msg_id = if tid.inst_id==1 { RSM_MSG_ID_MASTER_POWER_ON } else { RSM_MSG_ID_SLAVE_POWER_ON };
+        send_asyn_priority_msg(tid, rsm_message_t::new_sys_msg(msg_id));
+    }
+
+    let start = common::get_now_usec64();
+    loop {
+        process_sched_msg();
+        if tasks.iter().all(|t| is_task_init_completed(t)) {
+            return errcode::RESULT_SUCCESS
+        }
+        if timeout_msec>0 && common::get_now_usec64()-start>=timeout_msec*1000 {
+            return errcode::ERROR_TIME_OUT
+        }
+        if gSchedAbort.load(Ordering::Acquire) {
+            return errcode::ERROR_INVALID_STATE
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+///send power off to the tasks without waiting, used when the component failed to power on
+fn power_off_component(cid:rsm_component_id_t) {
+    for tid in get_component_tasks(cid) {
+        send_asyn_priority_msg(&tid, rsm_message_t::new_sys_msg(RSM_MSG_ID_POWER_OFF));
+    }
+}
+
+///run the scheduler, power on the components one by one
+pub fn run() {
+    if unsafe {gSchedLog.is_none()} {
+        unsafe { gSchedLog = Some(new_xlog("rsm_sched")); }
+    }
+    sched_lock();
+    unsafe { gSchedRunning = true; }
+    let order = get_power_on_order();
+    for cid in order.iter() {
+        let name = match get_component_registry(*cid) {
+            None=>String::default(),
+            Some(c)=>c.cattr.name.clone(),
+        };
+        add_power_on_record(*cid, &name);
+    }
+    sched_unlock();
+
+    let mut started:Vec<rsm_component_id_t>=Vec::new();
+    for (i,cid) in order.iter().enumerate() {
+        if is_power_on_aborted(&order[i..]) {
+            return
+        }
+        let (attrs,policy) = match get_component_registry(*cid) {
+            None=>continue,
+            Some(c)=>(c.cattr.clone(),c.policy.clone()),
+        };
+        //a depended component failed to power on
+        let failed_dep = policy.depends_on.iter().find(|d| !started.contains(d) && order.contains(d));
+        let ret = if let Some(d) = failed_dep {
+            sched_log(xlog::LOG_LEVEL_ERROR,errcode::ERROR_INIT_FAILED,
+                &format!("component {}({}) is not powered on, depended component {} is not started",attrs.name,cid,d));
+            set_power_on_state(*cid, E_POWER_ON_STATE::POWER_ON_SKIPPED);
+            errcode::ERROR_INIT_FAILED
+        } else {
+            let r = power_on_component(*cid,policy.init_timeout_msec);
+            if r!=errcode::RESULT_SUCCESS {
+                sched_log(xlog::LOG_LEVEL_ERROR,r,
+                    &format!("component {}({}) power on timeout,timeout={}ms,policy={:?}",attrs.name,cid,policy.init_timeout_msec,policy.init_fail_policy));
+                power_off_component(*cid);
+            }
+            r
+        };
+        if ret==errcode::RESULT_SUCCESS {
+            started.push(*cid);
+            continue;
+        }
+        if is_power_on_aborted(&order[i..]) {
+            return
+        }
+        if policy.init_fail_policy==E_RSM_INIT_FAIL_POLICY::INIT_FAIL_ABORT {
+            sched_log(xlog::LOG_LEVEL_ERROR,ret,&format!("rsm startup aborted by component {}({})",attrs.name,cid));
+            for c in started.iter().rev() {
+                stop_component(*c);
+                set_power_on_state(*c, E_POWER_ON_STATE::POWER_ON_ABORTED);
+            }
+            for c in &order[i+1..] {
+                set_power_on_state(*c, E_POWER_ON_STATE::POWER_ON_ABORTED);
+            }
+            return
+        }
+    }
+    sched_log(xlog::LOG_LEVEL_INFO,errcode::RESULT_SUCCESS,
+        &format!("rsm power on finished,started={},total={}",started.len(),order.len()));
+}
+
+///stop_rsm is called, mark the components not started as aborted, they are stopped by stop_rsm
+fn is_power_on_aborted(remaining:&[rsm_component_id_t])->bool {
+    if !gSchedAbort.load(Ordering::Acquire) {
+        return false
+    }
+    for c in remaining {
+        if get_power_on_state(*c)!=Some(E_POWER_ON_STATE::POWER_ON_COMPLETED) {
+            set_power_on_state(*c, E_POWER_ON_STATE::POWER_ON_ABORTED);
+        }
+    }
+    sched_log(xlog::LOG_LEVEL_WARNING,errcode::ERROR_INVALID_STATE,&format!("rsm power on aborted by stop_rsm"));
+    return true
+}
+
+///abort the power on in progress, the scheduler thread returns without waiting for the remaining components
+pub(crate) fn abort_power_on() {
+    gSchedAbort.store(true, Ordering::Release);
 }
 
 /// schedule task for each task
 fn schedule_task(task_id:rsm_component_t) {
-    let gTask = match unsafe {&mut gTaskRegistry} {
-        None=>return,
-        Some(e)=>e,
-    };
-    let t = match gTask.get_mut(&task_id) {
+    let mut t = match get_task_inst(&task_id) {
         None=> {
             println!("Running a task,id={},inst={} error, not found in task registry",task_id.cid,task_id.inst_id);
             return 
@@ -139,28 +536,77 @@ fn schedule_task(task_id:rsm_component_t) {
         tm.insert(os_tid,task_id.clone());
     }
 
-    t.run();
+    //supervise the task, restart it according to the restart policy if a panic occurs
+    loop {
+        let err = match panic::catch_unwind(panic::AssertUnwindSafe(|| t.run())) {
+            Ok(_)=>break,
+            Err(e)=>e,
+        };
+        let name = match get_component_registry(task_id.cid) {
+            None=>String::default(),
+            Some(c)=>c.cattr.name.clone(),
+        };
+        sched_log(xlog::LOG_LEVEL_ERROR,errcode::ERROR_COMMON,
+            &format!("task {}({}:{}) panic,cur_msg_id={},reason={}",name,task_id.cid,task_id.inst_id,t.cur_msg_id(),get_panic_reason(&err)));
+        t.release_resources();
+        if t.is_terminated() || !need_restart(&task_id, &mut t) {
+            t.set_failed();
+            sched_log(xlog::LOG_LEVEL_ERROR,errcode::ERROR_INVALID_STATE,
+                &format!("task {}({}:{}) exit, not restarted",name,task_id.cid,task_id.inst_id));
+            break;
+        }
+        let reg = match get_component_registry(task_id.cid) {
+            None=>break,
+            Some(r)=>r,
+        };
+        t.restart(reg.new_task.new_task_obj(&task_id));
+        sched_log(xlog::LOG_LEVEL_WARNING,errcode::RESULT_SUCCESS,
+            &format!("task {}({}:{}) restarted",name,task_id.cid,task_id.inst_id));
+    }
+}
+
+fn get_panic_reason(err:&Box<dyn std::any::Any + Send>)->String {
+    if let Some(s) = err.downcast_ref::<&str>() {
+        return s.to_string()
+    }
+    if let Some(s) = err.downcast_ref::<String>() {
+        return s.clone()
+    }
+    return String::from("unknown")
+}
+
+///check the restart policy of the component
+fn need_restart(tid:&rsm_component_t,t:&mut task_t)->bool {
+    let reg = match get_component_registry(tid.cid) {
+        None=>return false,
+        Some(c)=>c,
+    };
+    let policy = &reg.policy;
+    match policy.restart_policy {
+        E_RSM_RESTART_POLICY::RESTART_NEVER=>false,
+        E_RSM_RESTART_POLICY::RESTART_ALWAYS=>true,
+        E_RSM_RESTART_POLICY::RESTART_LIMITED=>{
+            t.record_restart(policy.restart_window_sec)<=policy.max_restart as usize
+        },
+    }
 }
 
 ///get self component id, get None if not under the rsm thread context
 pub(crate) fn get_self_cid()->Option<rsm_component_t> {
     let os_tid = sched::get_self_os_task_id();
 
-    if let Some(tm) = unsafe {&mut gTaskIdMap} {
-        return match tm.get(&os_tid) {
-            None=> None,
-            Some(tid)=>Some(tid.clone()),
-        };
+    if let Some(tm) = unsafe {&gTaskIdMap} {
+        return tm.get_cloned(&os_tid);
     }   
     None
 }
 
-fn get_task_inst(tid:&rsm_component_t)->Option<&mut task_t> {
-  let tm = match unsafe {&mut gTaskRegistry} {
+fn get_task_inst(tid:&rsm_component_t)->Option<task_ref_t> {
+  let tm = match unsafe {&gTaskRegistry} {
     None=>return None,
     Some(t)=>t,
   };
-  return tm.get_mut(&tid);
+  return tm.get_cloned(&tid);
 }
 
 pub(crate) fn get_sender_cid()->Option<rsm_component_t> {
@@ -178,14 +624,244 @@ pub(crate) fn get_sender_cid()->Option<rsm_component_t> {
 }
 
 
-pub(crate) fn power_on_ack() {
+const MAX_POWER_OFF_RETRY:u32 = 100;
+///stop all the task instances of a component, and wait for the task threads exit
+/// the component is removed from the scheduler after stopped
+pub(crate) fn stop_component(cid:rsm_component_id_t)->errcode::RESULT {
+    let reg = match get_component_registry(cid) {
+        None=>return errcode::ERROR_NOT_FOUND,
+        Some(r)=>r,
+    };
+    if let Some(self_cid) = get_self_cid() {
+        if self_cid.cid==cid {
+            return errcode::ERROR_INVALID_STATE
+        }
+    }
+    //wait for the scaling in progress, the instances it adds are stopped as well
+    let _scale = reg.scale_lock.lock();
+    if get_component_registry(cid).is_none() {
+        return errcode::ERROR_NOT_FOUND
+    }
+    let tasks = get_component_tasks(cid);
+    let stuck = stop_tasks(&tasks);
+    if !stuck.is_empty() {
+        sched_log(xlog::LOG_LEVEL_WARNING,errcode::ERROR_TIME_OUT,
+            &format!("component {} stopped, {} task(s) not exit",cid,stuck.len()));
+        return errcode::ERROR_TIME_OUT
+    }
+    if let Some(cm) = unsafe {&mut gComponentRegistry} {
+        cm.remove(&cid);
+    }
+    sched_log(xlog::LOG_LEVEL_INFO,errcode::RESULT_SUCCESS,
+        &format!("component {} stopped, task_num={}",cid,tasks.len()));
+    errcode::RESULT_SUCCESS
+}
+
+///power off the tasks and wait for the threads exit, then remove them from the scheduler,
+/// return the tasks which did not exit in time
+fn stop_tasks(tasks:&[rsm_component_t])->Vec<rsm_component_t> {
+    for tid in tasks {
+        let mut retry = 0;
+        while send_asyn_priority_msg(tid, rsm_message_t::new_sys_msg(RSM_MSG_ID_POWER_OFF))==errcode::ERROR_OUTOF_MEM
+            && retry<MAX_POWER_OFF_RETRY {
+            thread::sleep(Duration::from_millis(1));
+            retry+=1;
+        }
+    }
+    let mut stuck:Vec<rsm_component_t>=Vec::new();
+    for tid in tasks {
+        let h = match unsafe {&mut gTaskThreads} {
+            None=>None,
+            Some(th)=>th.remove(tid),
+        };
+        if let Some(handle) = h {
+            //the task may be blocked in on_init or a message handler, give it a short time to exit
+            let mut retry = 0;
+            while !handle.is_finished() && retry<MAX_POWER_OFF_RETRY {
+                thread::sleep(Duration::from_millis(1));
+                retry+=1;
+            }
+            if !handle.is_finished() {
+                stuck.push(tid.clone());
+                continue;
+            }
+            let _ = handle.join();
+        }
+    }
+    remove_topic_subscribers(tasks, &stuck);
+
+    //remove the joined tasks from the task list first, so they can't be looked up by the watchdog and OAM
+    if let Ok(mut tl) = gTaskList.lock() {
+        tl.retain(|t| !tasks.contains(t) || stuck.contains(t));
+    }
+    for tid in tasks {
+        //the task control block is still used by the thread
+        if stuck.contains(tid) {
+            continue;
+        }
+        let mut t = match get_task_inst(tid) {
+            None=>continue,
+            Some(t)=>t,
+        };
+        //reject the messages sent by the callers still holding a reference,
+        //the task control block is released when the last reference is dropped
+        t.set_failed();
+        if let Some(tm) = unsafe {&mut gTaskIdMap} {
+            tm.remove(&t.get_os_task_id());
+        }
+        if let Some(tr) = unsafe {&mut gTaskRegistry} {
+            tr.remove(tid);
+        }
+    }
+    return stuck
+}
+
+///remove the stopped tasks from the subscribers of all the topics
+fn remove_topic_subscribers(tasks:&[rsm_component_t],stuck:&[rsm_component_t]) {
+    let tm = match unsafe {&mut gTopicRegistry} {
+        None=>return,
+        Some(m)=>m,
+    };
+    for (_,t) in tm.iter_mut() {
+        t.lock.lock();
+        t.subscribers.retain(|s| !tasks.contains(&s.cid) || stuck.contains(&s.cid));
+        t.lock.unlock();
+    }
+    tm.end_iter();
+}
+
+///change the instance number of a component, the new instances are powered on immediately if rsm is running,
+/// the instances with the largest inst_id are stopped when scaling down
+pub(crate) fn scale_component(cid:rsm_component_id_t,inst_num:usize)->errcode::RESULT {
+    if inst_num==0 || inst_num>RSM_MAX_INST_PER_CID {
+        return errcode::ERROR_INVALID_PARAM
+    }
+    if unsafe {gComponentRegistry.is_none()} {
+        return errcode::ERROR_NOT_INITIALIZED
+    }
+    let reg = match get_component_registry(cid) {
+        None=>return errcode::ERROR_NOT_FOUND,
+        Some(r)=>r,
+    };
+    //hold the lock until the new instance number is set, the registry is read again in case it was changed by another caller
+    let _scale = reg.scale_lock.lock();
+    let reg = match get_component_registry(cid) {
+        None=>return errcode::ERROR_NOT_FOUND,
+        Some(r)=>r,
+    };
+    let cur = reg.cattr.inst_num;
+    if inst_num==cur {
+        return errcode::ERROR_NO_OP
+    }
+
+    if inst_num<cur {
+        if let Some(self_cid) = get_self_cid() {
+            if self_cid.cid==cid && self_cid.inst_id>inst_num {
+                return errcode::ERROR_INVALID_STATE
+            }
+        }
+        //stop dispatching to the removed instances first
+        set_component_inst_num(&reg, inst_num);
+        let tasks:Vec<rsm_component_t> = (inst_num+1..cur+1).map(|i| rsm_component_t::new(cid,get_local_node_id(),i)).collect();
+        let stuck = stop_tasks(&tasks);
+        sched_log(xlog::LOG_LEVEL_INFO,errcode::RESULT_SUCCESS,
+            &format!("component {}({}) scaled in, inst_num {}->{}, {} task(s) not exit",reg.cattr.name,cid,cur,inst_num,stuck.len()));
+        return errcode::RESULT_SUCCESS
+    }
+
+    let mut tasks=Vec::new();
+    for i in cur+1..inst_num+1 {
+        if let Some(tid) = register_task_inst(&reg, i) {
+            tasks.push(tid);
+        }
+    }
+    if unsafe {gSchedRunning} {
+        let ret = power_on_tasks(&tasks, reg.policy.init_timeout_msec);
+        if ret!=errcode::RESULT_SUCCESS {
+            sched_log(xlog::LOG_LEVEL_ERROR,ret,
+                &format!("component {}({}) scale out failed, new instances power on timeout",reg.cattr.name,cid));
+            stop_tasks(&tasks);
+            return ret
+        }
+    }
+    set_component_inst_num(&reg, inst_num);
+    sched_log(xlog::LOG_LEVEL_INFO,errcode::RESULT_SUCCESS,
+        &format!("component {}({}) scaled out, inst_num {}->{}",reg.cattr.name,cid,cur,inst_num));
+    errcode::RESULT_SUCCESS
+}
+
+///replace the registry with a copy holding the new instance number, the holders of the old copy are not affected
+fn set_component_inst_num(reg:&component_registry_t,inst_num:usize) {
+    let mut r = reg.clone();
+    r.cattr.inst_num = inst_num;
+    if let Some(cm) = unsafe {&mut gComponentRegistry} {
+        cm.insert(r.cattr.cid, Arc::new(r));
+    }
+}
+
+///stop all the components in the reverse order of registration
+pub(crate) fn stop_all_components()->errcode::RESULT {
+    if get_self_cid().is_some() {
+        return errcode::ERROR_INVALID_STATE
+    }
+    let mut cids:Vec<rsm_component_id_t> = Vec::new();
+    for t in get_all_tasks().iter().rev() {
+        if !cids.contains(&t.cid) {
+            cids.push(t.cid);
+        }
+    }
+    for cid in cids {
+        stop_component(cid);
+    }
+    errcode::RESULT_SUCCESS
+}
 
+///send the power on ack of a task to the scheduler
+pub(crate) fn send_power_on_ack(tid:&rsm_component_t)->errcode::RESULT {
+    let q = match unsafe {&mut gSchedQueue} {
+        None=>return errcode::ERROR_NOT_INITIALIZED,
+        Some(q)=>q,
+    };
+    let mut msg = rsm_message_t::new_sys_msg(RSM_MSG_ID_POWER_ON_ACK);
+    msg.sender = tid.clone();
+    return q.push_back(msg)
+}
+
+pub(crate) fn power_on_ack()->errcode::RESULT {
+    let cid = match get_self_cid() {
+        None=>return errcode::ERROR_INVALID_STATE,
+        Some(c)=>c,
+    };
+    return send_power_on_ack(&cid)
+}
+
+///node id used by the applications addressing the local node before the multi-node support
+const RSM_LEGACY_LOCAL_NODE_ID:rsm_node_id_t = 1;
+///get the local task id of the destination, return None if the message is forwarded to a peer node.
+/// node id 1 not in the node table is regarded as the local node, other unknown node ids are not found
+pub(crate) fn get_local_dst(dst:&rsm_component_t)->Result<Option<rsm_component_t>,errcode::RESULT> {
+    let local = get_local_node_id();
+    if dst.node_id==local {
+        return Ok(Some(dst.clone()))
+    }
+    if rsm_transport::is_peer_node(dst.node_id) {
+        return Ok(None)
+    }
+    if dst.node_id==RSM_LEGACY_LOCAL_NODE_ID {
+        return Ok(Some(rsm_component_t::new(dst.cid, local, dst.inst_id)))
+    }
+    return Err(errcode::ERROR_NOT_FOUND)
 }
 
 pub(crate) fn send_asyn_msg(dst:&rsm_component_t,msg:rsm_message_t)->errcode::RESULT {
-    let task = match unsafe {&mut gTaskRegistry} {
+    let dst = match get_local_dst(dst) {
+        Err(e)=>return e,
+        Ok(None)=>return rsm_transport::forward_msg(dst, msg, false),
+        Ok(Some(d))=>d,
+    };
+    let mut task = match unsafe {&gTaskRegistry} {
         None=>return errcode::ERROR_NOT_INITIALIZED,
-        Some(t)=> match t.get_mut(dst) {
+        Some(t)=> match t.get_cloned(&dst) {
             None=>return errcode::ERROR_NOT_FOUND,
             Some(tk)=>tk,
         },
@@ -196,9 +872,14 @@ pub(crate) fn send_asyn_msg(dst:&rsm_component_t,msg:rsm_message_t)->errcode::RE
 
 ///send one high priority message to specific component
 pub(crate) fn send_asyn_priority_msg(dst:&rsm_component_t,msg:rsm_message_t)->errcode::RESULT {
-    let task = match unsafe {&mut gTaskRegistry} {
+    let dst = match get_local_dst(dst) {
+        Err(e)=>return e,
+        Ok(None)=>return rsm_transport::forward_msg(dst, msg, true),
+        Ok(Some(d))=>d,
+    };
+    let mut task = match unsafe {&gTaskRegistry} {
         None=>return errcode::ERROR_NOT_INITIALIZED,
-        Some(t)=> match t.get_mut(dst) {
+        Some(t)=> match t.get_cloned(&dst) {
             None=>return errcode::ERROR_NOT_FOUND,
             Some(tk)=>tk,
         },
@@ -207,23 +888,137 @@ pub(crate) fn send_asyn_priority_msg(dst:&rsm_component_t,msg:rsm_message_t)->er
     return task.send_asyn_priority_msg(msg);
 }
 
-pub(crate) fn get_component_registry(cid:u32)->Option<&'static component_registry_t> {
+///subscribe a topic, the caller task will receive the messages published to the topic
+pub(crate) fn subscribe_topic(topic:&str)->errcode::RESULT {
+    let tm = match unsafe {&mut gTopicRegistry} {
+        None=>return errcode::ERROR_NOT_INITIALIZED,
+        Some(m)=>m,
+    };
+    let cid = match get_self_cid() {
+        None=>return errcode::ERROR_INVALID_STATE,
+        Some(c)=>c,
+    };
+    let name = topic.to_string();
+    //the topic is created by the first subscriber, the tasks may subscribe the same topic concurrently
+    let t = match tm.get_or_insert_with(name.clone(), MAX_TOPIC_NUM, || topic_t {
+            name:name,
+            subscribers:Vec::new(),
+            published:0,
+            lock:spin_lock_t::new(),
+        }) {
+        None=>return errcode::ERROR_OUTOF_MEM,
+        Some(t)=>t,
+    };
+    t.lock.lock();
+    let ret = if t.subscribers.iter().any(|s| s.cid==cid) {
+        errcode::ERROR_ALREADY_EXIST
+    } else {
+        t.subscribers.push(topic_subscriber_t{cid:cid,delivered:0,dropped:0});
+        errcode::RESULT_SUCCESS
+    };
+    t.lock.unlock();
+    return ret
+}
+
+///caller task unsubscribe the topic
+pub(crate) fn unsubscribe_topic(topic:&str)->errcode::RESULT {
+    let tm = match unsafe {&mut gTopicRegistry} {
+        None=>return errcode::ERROR_NOT_INITIALIZED,
+        Some(m)=>m,
+    };
+    let cid = match get_self_cid() {
+        None=>return errcode::ERROR_INVALID_STATE,
+        Some(c)=>c,
+    };
+    let t = match tm.get_mut(&topic.to_string()) {
+        None=>return errcode::ERROR_NOT_FOUND,
+        Some(t)=>t,
+    };
+    t.lock.lock();
+    let count = t.subscribers.len();
+    t.subscribers.retain(|s| s.cid!=cid);
+    let ret = if t.subscribers.len()<count { errcode::RESULT_SUCCESS } else { errcode::ERROR_NOT_FOUND };
+    t.lock.unlock();
+    return ret
+}
+
+///publish a message to all the subscribers of the topic, a message is dropped if the subscriber's queue is full
+pub(crate) fn publish(topic:&str,msg:rsm_message_t)->errcode::RESULT {
+    let tm = match unsafe {&mut gTopicRegistry} {
+        None=>return errcode::ERROR_NOT_INITIALIZED,
+        Some(m)=>m,
+    };
+    let t = match tm.get_mut(&topic.to_string()) {
+        None=>return errcode::ERROR_NOT_FOUND,
+        Some(t)=>t,
+    };
+    t.lock.lock();
+    t.published+=1;
+    let mut delivered = 0;
+    for sub in t.subscribers.iter_mut() {
+        if send_asyn_msg(&sub.cid, msg.clone())==errcode::RESULT_SUCCESS {
+            sub.delivered+=1;
+            delivered+=1;
+        } else {
+            sub.dropped+=1;
+        }
+    }
+    let count = t.subscribers.len();
+    t.lock.unlock();
+    if count==0 {
+        return errcode::ERROR_NO_OP
+    }
+    if delivered==0 {
+        return errcode::ERROR_SEND_MSG
+    }
+    errcode::RESULT_SUCCESS
+}
+
+///get the statistics of all the topics subscribed by the task
+pub(crate) fn get_task_topic_stats(tid:&rsm_component_t)->Vec<topic_sub_stats_t> {
+    let mut stats = Vec::new();
+    let tm = match unsafe {&gTopicRegistry} {
+        None=>return stats,
+        Some(m)=>m,
+    };
+    for (_,t) in tm.iter() {
+        t.lock.lock();
+        if let Some(sub) = t.subscribers.iter().find(|s| s.cid==*tid) {
+            stats.push(topic_sub_stats_t{
+                topic:t.name.clone(),
+                published:t.published,
+                delivered:sub.delivered,
+                dropped:sub.dropped,
+            });
+        }
+        t.lock.unlock();
+    }
+    tm.end_iter();
+    return stats
+}
+
+pub(crate) fn get_component_registry(cid:u32)->Option<Arc<component_registry_t>> {
     let cm = match unsafe {& gComponentRegistry} {
         None=>return None,
         Some(c)=>c,
     };
-    match cm.get(&cid) {
-        None=>return None,
-        Some(c)=>return Some(c),
+    return cm.get_cloned(&cid)
+}
+///clear the statistics and latency histograms of a task
+pub(crate) fn clear_task_stats(tid:&rsm_component_t)->errcode::RESULT {
+    match get_task_inst(tid) {
+        None=>errcode::ERROR_NOT_FOUND,
+        Some(mut t)=> {
+            t.clear_task_stats();
+            errcode::RESULT_SUCCESS
+        },
     }
 }
-fn get_task_stats(tid:&rsm_component_t)->Option<task_stats_t> {
-    let task = match unsafe {&mut gTaskRegistry} {
+
+pub(crate) fn get_task_stats(tid:&rsm_component_t)->Option<task_stats_t> {
+    let task = match get_task_inst(tid) {
         None=>return None,
-        Some(t)=> match t.get_mut(tid) {
-            None=>return None,
-            Some(tk)=>tk,
-        },
+        Some(tk)=>tk,
     };
     return Some(task.get_task_stats())
 }
@@ -270,6 +1065,9 @@ fn process_sched_oam(op:oam::E_RSM_OAM_OP,url:&String,param:&String)->oam::oam_c
         RSM_SCHED_COMPONENT_URL=>{
             proccess_component_oam(op,param,&mut tResp); 
         },
+        RSM_SCHED_POWER_ON_URL=>{
+            proccess_power_on_oam(op,&mut tResp);
+        },
         _=>(),
     }
 
@@ -277,7 +1075,7 @@ fn process_sched_oam(op:oam::E_RSM_OAM_OP,url:&String,param:&String)->oam::oam_c
 }
 
 fn proccess_task_oam(op:oam::E_RSM_OAM_OP,param:&String,tResp:&mut oam::oam_cmd_resp_t) {
-    if op!=E_RSM_OAM_OP::CLI_OP_SHOW {
+    if op!=E_RSM_OAM_OP::CLI_OP_SHOW && op!=E_RSM_OAM_OP::CLI_OP_DEL {
         tResp.RetCode = errcode::ERROR_NOT_SUPPORT;
         return;
     }
@@ -298,10 +1096,31 @@ fn proccess_task_oam(op:oam::E_RSM_OAM_OP,param:&String,tResp:&mut oam::oam_cmd_
         Ok(d)=>d,
         Err(_)=>return,
     };
-    let tid = &rsm_component_t::new(cid, 1, inst as usize);
+    let tid = &rsm_component_t::new(cid, get_local_node_id(), inst as usize);
+    if op==E_RSM_OAM_OP::CLI_OP_DEL {
+        tResp.RetCode = clear_task_stats(tid);
+        return;
+    }
     if let Some(stats) = get_task_stats(&tid) {
         tResp.RetCode=errcode::RESULT_SUCCESS;
         tResp.Description = serde_json::to_string_pretty::<task_stats_t>(&stats).unwrap();
+        let hdr = vec!["topic".to_string(),"published".to_string(),"delivered".to_string(),"dropped".to_string()];
+        tResp.set_row_hdr(hdr);
+        for t in get_task_topic_stats(tid) {
+            let row = vec![t.topic,t.published.to_string(),t.delivered.to_string(),t.dropped.to_string()];
+            tResp.add_row(&row);
+        }
+        let hdr = vec!["msg_id".to_string(),"count".to_string(),"wait_p50_usec".to_string(),"wait_p99_usec".to_string(),
+            "wait_max_usec".to_string(),"exec_p50_usec".to_string(),"exec_p99_usec".to_string(),"exec_max_usec".to_string()];
+        tResp.set_row_hdr(hdr);
+        if let Some(t) = get_task_inst(tid) {
+            for (msg_id,l) in t.get_latency_stats() {
+                let row = vec![msg_id.to_string(),l.exec.count().to_string(),l.wait.percentile(50.0).to_string(),
+                    l.wait.percentile(99.0).to_string(),l.wait.max().to_string(),l.exec.percentile(50.0).to_string(),
+                    l.exec.percentile(99.0).to_string(),l.exec.max().to_string()];
+                tResp.add_row(&row);
+            }
+        }
     }
 
 }
@@ -321,7 +1140,41 @@ fn proccess_component_oam(op:oam::E_RSM_OAM_OP,param:&String,tResp:&mut oam::oam
     };
     if let Some(c) = get_component_registry(cid) {
         tResp.RetCode=errcode::RESULT_SUCCESS;
-        tResp.Description = serde_json::to_string_pretty::<component_attrs_t>(&c.cattr).unwrap();
+        tResp.Description = format!("{}\n{}",serde_json::to_string_pretty::<component_attrs_t>(&c.cattr).unwrap(),
+            serde_json::to_string_pretty::<component_policy_t>(&c.policy).unwrap());
+        let hdr = vec!["inst_id".to_string(),"init_completed".to_string(),"qlen".to_string()];
+        tResp.set_row_hdr(hdr);
+        for tid in get_component_tasks(cid) {
+            if let Some(t) = get_task_inst(&tid) {
+                let row = vec![tid.inst_id.to_string(),t.is_init_completed().to_string(),t.get_qlen().to_string()];
+                tResp.add_row(&row);
+            }
+        }
     }
     
-}
\ No newline at end of file
+}
+
+fn proccess_power_on_oam(op:oam::E_RSM_OAM_OP,tResp:&mut oam::oam_cmd_resp_t) {
+    if op!=E_RSM_OAM_OP::CLI_OP_SHOW {
+        tResp.RetCode = errcode::ERROR_NOT_SUPPORT;
+        return;
+    }
+    let recs = match unsafe {&gPowerOnRecords} {
+        None=>return,
+        Some(r)=>r,
+    };
+    let mut vr:Vec<power_on_record_t> = Vec::new();
+    for (_,r) in recs.iter() {
+        vr.push(r.clone());
+    }
+    recs.end_iter();
+    vr.sort_by_key(|r| r.order);
+
+    tResp.RetCode=errcode::RESULT_SUCCESS;
+    let hdr = vec!["order".to_string(),"cid".to_string(),"name".to_string(),"state".to_string(),"elapsed_msec".to_string()];
+    tResp.set_row_hdr(hdr);
+    for r in vr {
+        let row = vec![r.order.to_string(),r.cid.to_string(),r.name,format!("{:?}",r.state),r.elapsed_msec.to_string()];
+        tResp.add_row(&row);
+    }
+}
@@ -8,25 +8,45 @@
 /// set_timer(dur_msec:u64,loop_count:u64,timer_data:usize)->Option<rsm_timer_id_t>
 /// later application can kill the timer by invoke kill_timer_by_id
 /// Once the timer is set, application can get a timer event on the Runnable Trait called by RSM
-/// 
+/// timers are kept in a hierarchical timing wheel driven by a 10ms(configurable) os timer, set and kill are O(1), and a timer fires within one tick of its duration.
+/// the timer service runs on a monotonic clock, a timer can also be armed at an absolute deadline by set_timer_at, or re-armed by modify_timer
 
 use super::*;
-use crate::common::{self,errcode,tsidallocator::TsIdAllocator,tsmap::TsHashMap};
+use super::oam::E_RSM_OAM_OP;
+use crate::common::{errcode,tsidallocator::TsIdAllocator,spin_lock::spin_lock_t};
 use os_timer;
+use std::sync::atomic::{AtomicBool,AtomicUsize,Ordering};
+use std::time::Instant;
 const MAX_TIMER_COUNT:usize = 192*1024;
-const MAX_TIMER_PER_CAT:usize = 64*1024;
 const INVALID_TIMER_ID:rsm_timer_id_t = TsIdAllocator::INVALID_ID;
 
 const TIMER_CAT_10MS:u8=1;
 const TIMER_CAT_100MS:u8=2;
 const TIMER_CAT_1S:u8=3;
 
-#[derive(Clone,Default)]
+//hierarchical timing wheel, 4 levels and 64 slots per level, level n slot covers 64^n ticks,
+//a timer is moved to the lower level when the upper level slot is reached, then fired from the ready list
+const WHEEL_BITS:u32 = 6;
+const WHEEL_SIZE:usize = 1<<WHEEL_BITS;
+const WHEEL_MASK:u64 = (WHEEL_SIZE-1) as u64;
+const WHEEL_LEVELS:usize = 4;
+const READY_LIST:usize = WHEEL_LEVELS*WHEEL_SIZE;
+const NO_LIST:usize = usize::MAX;
+///max timers fired in one tick, the remaining expired timers are fired in the next tick
+const MAX_FIRE_PER_TICK:usize = 8192;
+const RSM_TIMER_URL:&str = "/timer";
+const MAX_OAM_TIMER_ROWS:usize = 1000;
+
+#[derive(Clone,Default,Debug)]
 pub struct timer_stats_t {
-    total:usize,
-    timer_count_10ms:usize,
-    timer_count_100ms:usize,
-    timer_count_1s:usize,
+    pub total:usize,
+    pub timer_count_10ms:usize,
+    pub timer_count_100ms:usize,
+    pub timer_count_1s:usize,
+    ///total fired count
+    pub fired:u64,
+    ///expired timers deferred to the next tick because of the per-tick firing budget
+    pub deferred:u64,
 }
 
 struct timer_desc_t {
@@ -34,170 +54,578 @@ struct timer_desc_t {
     duration_msec:u64,
     loop_count:u64,
     timer_data:usize,
-    last_fired:u64,
     expired_count:u64,
+    //the owner which receives the timer message
     cid:rsm_component_t,
+    //the task set the timer, or the token of a non rsm thread, the creator can also kill the timer
+    creator:rsm_component_t,
+    msg_id:rsm_message_id_t,
+    name:String,
+    //callback timer is fired by invoking the callback under the timer thread, instead of sending message
+    callback:Option<rsm_timer_callback>,
+    //payload of the typed timer, delivered as the message body
+    body:rsm_msg_body_t,
+    expire_tick:u64,
+    //the wheel slot or the ready list the timer is linked in
+    list:usize,
+    prev:rsm_timer_id_t,
+    next:rsm_timer_id_t,
+}
+
+///callback of a timer set by a non rsm thread, called under the timer thread context, it must return quickly
+pub type rsm_timer_callback = fn(timer_id:rsm_timer_id_t,timer_data:usize);
+
+//a fired timer, the message or callback is sent after the wheel is unlocked
+struct timer_event_t {
+    cid:rsm_component_t,
+    msg_id:rsm_message_id_t,
+    id:rsm_timer_id_t,
+    timer_data:usize,
+    body:rsm_msg_body_t,
+    callback:Option<rsm_timer_callback>,
+}
+
+struct timer_wheel_t {
+    //indexed by timer id
+    timers:Vec<Option<timer_desc_t>>,
+    heads:Vec<rsm_timer_id_t>,
+    tails:Vec<rsm_timer_id_t>,
+    cur_tick:u64,
+    tick_msec:u64,
+    stats:timer_stats_t,
+    lock:spin_lock_t,
 }
 
-type timer_hash_map = TsHashMap<rsm_timer_id_t,timer_desc_t>;
 static mut gTimerIdAlloc:Option<TsIdAllocator>=None;
-static mut gTimerCatMap:[u8;MAX_TIMER_COUNT+1]=[0;MAX_TIMER_COUNT+1];
-static mut gTimer10ms:Option<timer_hash_map>=None;
-static mut gTimer100ms:Option<timer_hash_map>=None;
-static mut gTimer1s:Option<timer_hash_map>=None;
+static mut gTimerWheel:Option<timer_wheel_t>=None;
+//the origin of the monotonic clock, timers are not affected by the change of the wall clock
+static mut gTimerEpoch:Option<Instant>=None;
+//the token of the threads not managed by rsm, never reused
+static gThreadToken:AtomicUsize=AtomicUsize::new(1);
+
+thread_local! {
+    //a non rsm thread is identified by the zero cid and a unique instance id
+    static tThreadCid:rsm_component_t = rsm_component_t::new(0, 0, gThreadToken.fetch_add(1, Ordering::Relaxed));
+}
+
+//the caller of the timer operation, the task id or the token of a non rsm thread
+fn get_caller_cid()->rsm_component_t {
+    match rsm_sched::get_self_cid() {
+        Some(c)=>c,
+        None=>tThreadCid.with(|c| c.clone()),
+    }
+}
 
-///initialize timer
-pub(crate) fn init_timer() {
+///initialize timer, tick_msec is the resolution of the timer, 0 means the default 10ms tick
+pub(crate) fn init_timer(tick_msec:u64) {
     unsafe {
         if gTimerIdAlloc.is_some() {
             return
         }
+        let tick = if tick_msec==0 {config::RSM_DEF_TIMER_TICK_MSEC} else {tick_msec};
+        gTimerEpoch = Some(Instant::now());
         gTimerIdAlloc = Some(TsIdAllocator::new(1, MAX_TIMER_COUNT as i32));
-        gTimer10ms = Some(TsHashMap::new(MAX_TIMER_PER_CAT));
-        gTimer100ms = Some(TsHashMap::new(MAX_TIMER_PER_CAT));
-        gTimer1s = Some(TsHashMap::new(MAX_TIMER_PER_CAT));
+        gTimerWheel = Some(timer_wheel_t::new(tick));
     }
+    let urls = [RSM_TIMER_URL.to_string()];
+    oam::RegisterOamModule(&urls, process_timer_oam);
 
     println!("RSM Init Timer finished");
 }
 
-fn get_timer_cb_by_duration(dur_msec:u64)->Option<&'static mut timer_hash_map> {
+fn get_timer_cat(dur_msec:u64)->u8 {
     if dur_msec<100 {
-        return unsafe {(&mut gTimer10ms).as_mut()}
+        return TIMER_CAT_10MS
     } else if dur_msec<1000{
-        return unsafe {(&mut gTimer100ms).as_mut()}
+        return TIMER_CAT_100MS
     }
-    return unsafe {(&mut gTimer1s).as_mut()}
+    return TIMER_CAT_1S 
 }
 
-fn get_timer_cb_by_cat(timer_cat:u8)->Option<&'static mut timer_hash_map> {
-    match timer_cat {
-        TIMER_CAT_10MS=> return unsafe {(&mut gTimer10ms).as_mut()},
-        TIMER_CAT_100MS=> return unsafe {(&mut gTimer100ms).as_mut()},
-        TIMER_CAT_1S=> return unsafe {(&mut gTimer1s).as_mut()},
-        _=>None, 
-    }    
+///milliseconds elapsed since the timer service is initialized, on a monotonic clock which never goes backward
+pub fn get_monotonic_msec()->u64 {
+    match unsafe {&gTimerEpoch} {
+        None=>0,
+        Some(e)=>e.elapsed().as_millis() as u64,
+    }
 }
 
-fn get_timer_cat(dur_msec:u64)->u8 {
-    if dur_msec<100 {
-        return TIMER_CAT_10MS
-    } else if dur_msec<1000{
-        return TIMER_CAT_100MS
+//the tick of an absolute deadline, rounded up so the timer never fires before the deadline
+fn deadline_to_tick(deadline_msec:u64,tick_msec:u64)->u64 {
+    return (deadline_msec+tick_msec-1)/tick_msec
+}
+
+fn msec_to_ticks(dur_msec:u64,tick_msec:u64)->u64 {
+    let ticks = (dur_msec+tick_msec-1)/tick_msec;
+    if ticks==0 {
+        return 1
     }
-    return TIMER_CAT_1S 
+    return ticks
 }
 
-fn set_timer_cat_map(id:rsm_timer_id_t,cat:u8)->errcode::RESULT {
-    if cat<TIMER_CAT_10MS || cat>TIMER_CAT_1S || id as usize>MAX_TIMER_COUNT{
-        return errcode::ERROR_INVALID_PARAM
+impl timer_wheel_t {
+    fn new(tick_msec:u64)->Self {
+        return Self {
+            timers:Vec::new(),
+            heads:vec![INVALID_TIMER_ID;READY_LIST+1],
+            tails:vec![INVALID_TIMER_ID;READY_LIST+1],
+            cur_tick:0,
+            tick_msec,
+            stats:timer_stats_t::default(),
+            lock:spin_lock_t::new(),
+        }
     }
-    unsafe {
-        gTimerCatMap[id as usize] = cat;
+
+    //the tick of current time, the wheel may lag behind it until the next os timer callback
+    fn now_tick(&self)->u64 {
+        return std::cmp::max(get_monotonic_msec()/self.tick_msec, self.cur_tick)
+    }
+
+    fn get_timer(&mut self,id:rsm_timer_id_t)->Option<&mut timer_desc_t> {
+        if id<=0 || id as usize>=self.timers.len() {
+            return None
+        }
+        return self.timers[id as usize].as_mut()
+    }
+
+    fn link(&mut self,id:rsm_timer_id_t,list:usize) {
+        let tail = self.tails[list];
+        match self.get_timer(id) {
+            None=>return,
+            Some(t)=> {
+                t.list = list;
+                t.prev = tail;
+                t.next = INVALID_TIMER_ID;
+            },
+        }
+        match self.get_timer(tail) {
+            None=>self.heads[list] = id,
+            Some(p)=>p.next = id,
+        }
+        self.tails[list] = id;
+    }
+
+    fn unlink(&mut self,id:rsm_timer_id_t) {
+        let (list,prev,next) = match self.get_timer(id) {
+            None=>return,
+            Some(t)=> {
+                let r = (t.list,t.prev,t.next);
+                t.list = NO_LIST;
+                t.prev = INVALID_TIMER_ID;
+                t.next = INVALID_TIMER_ID;
+                r
+            },
+        };
+        if list==NO_LIST {
+            return
+        }
+        match self.get_timer(prev) {
+            None=>self.heads[list] = next,
+            Some(p)=>p.next = next,
+        }
+        match self.get_timer(next) {
+            None=>self.tails[list] = prev,
+            Some(n)=>n.prev = prev,
+        }
+    }
+
+    //put the timer into the slot according to its expire tick
+    fn schedule(&mut self,id:rsm_timer_id_t) {
+        let expire = match self.get_timer(id) {
+            None=>return,
+            Some(t)=>t.expire_tick,
+        };
+        let delta = expire.saturating_sub(self.cur_tick);
+        let list = if delta==0 {
+            READY_LIST
+        } else {
+            let mut level = 0;
+            while level<WHEEL_LEVELS-1 && delta>=(1u64<<(WHEEL_BITS*(level as u32+1))) {
+                level+=1;
+            }
+            //beyond the range of the top level, put into the farthest slot, it is rescheduled when the slot is reached
+            let tick = if delta>=(1u64<<(WHEEL_BITS*WHEEL_LEVELS as u32)) {
+                self.cur_tick+((WHEEL_MASK as u64)<<(WHEEL_BITS*(WHEEL_LEVELS as u32-1)))
+            } else {
+                expire
+            };
+            level*WHEEL_SIZE+((tick>>(WHEEL_BITS*level as u32)) & WHEEL_MASK) as usize
+        };
+        self.link(id, list);
+    }
+
+    fn take_list(&mut self,list:usize)->Vec<rsm_timer_id_t> {
+        let mut ids = Vec::new();
+        let mut id = self.heads[list];
+        while let Some(t) = self.get_timer(id) {
+            ids.push(id);
+            t.list = NO_LIST;
+            id = t.next;
+        }
+        self.heads[list] = INVALID_TIMER_ID;
+        self.tails[list] = INVALID_TIMER_ID;
+        return ids
+    }
+
+    fn add_timer(&mut self,timer:timer_desc_t)->errcode::RESULT {
+        let id = timer.id;
+        if id<=0 || id as usize>MAX_TIMER_COUNT {
+            return errcode::ERROR_INVALID_PARAM
+        }
+        if id as usize>=self.timers.len() {
+            self.timers.resize_with(id as usize+1, || None);
+        }
+        if self.timers[id as usize].is_some() {
+            return errcode::ERROR_ALREADY_EXIST
+        }
+        self.update_cat_count(timer.duration_msec, true);
+        self.timers[id as usize] = Some(timer);
+        self.schedule(id);
+        errcode::RESULT_SUCCESS
+    }
+
+    fn remove_timer(&mut self,id:rsm_timer_id_t)->Option<timer_desc_t> {
+        self.unlink(id);
+        if id<=0 || id as usize>=self.timers.len() {
+            return None
+        }
+        let t = self.timers[id as usize].take();
+        if let Some(timer) = &t {
+            self.update_cat_count(timer.duration_msec, false);
+        }
+        return t
+    }
+
+    fn update_cat_count(&mut self,dur_msec:u64,add:bool) {
+        let count = match get_timer_cat(dur_msec) {
+            TIMER_CAT_10MS=>&mut self.stats.timer_count_10ms,
+            TIMER_CAT_100MS=>&mut self.stats.timer_count_100ms,
+            _=>&mut self.stats.timer_count_1s,
+        };
+        if add {
+            *count+=1;
+        } else {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    //re-arm the timer to expire dur_msec after now_tick and then every dur_msec
+    fn rearm(&mut self,id:rsm_timer_id_t,dur_msec:u64,now_tick:u64) {
+        let expire_tick = now_tick+msec_to_ticks(dur_msec,self.tick_msec);
+        self.unlink(id);
+        let old_dur = match self.get_timer(id) {
+            None=>return,
+            Some(t)=> {
+                let d = t.duration_msec;
+                t.duration_msec = dur_msec;
+                t.expire_tick = expire_tick;
+                d
+            },
+        };
+        self.update_cat_count(old_dur, false);
+        self.update_cat_count(dur_msec, true);
+        self.schedule(id);
+    }
+
+    //advance one tick, cascade the upper level slots, then move the expired timers to the ready list
+    fn advance(&mut self) {
+        self.cur_tick+=1;
+        let mut level = 1;
+        while level<WHEEL_LEVELS && (self.cur_tick & ((1u64<<(WHEEL_BITS*level as u32))-1))==0 {
+            let slot = ((self.cur_tick>>(WHEEL_BITS*level as u32)) & WHEEL_MASK) as usize;
+            for id in self.take_list(level*WHEEL_SIZE+slot) {
+                self.schedule(id);
+            }
+            level+=1;
+        }
+        let slot = (self.cur_tick & WHEEL_MASK) as usize;
+        for id in self.take_list(slot) {
+            self.link(id, READY_LIST);
+        }
+    }
+
+    //fire the timers in the ready list, up to MAX_FIRE_PER_TICK, return the timer messages to be sent
+    fn fire(&mut self)->Vec<timer_event_t> {
+        let mut fired = Vec::new();
+        while fired.len()<MAX_FIRE_PER_TICK {
+            let id = self.heads[READY_LIST];
+            let cur_tick = self.cur_tick;
+            let tick_msec = self.tick_msec;
+            self.unlink(id);
+            let finished = match self.get_timer(id) {
+                None=>break,
+                Some(t)=> {
+                    fired.push(timer_event_t { cid:t.cid.clone(), msg_id:t.msg_id, id:t.id, timer_data:t.timer_data,
+                        body:t.body.clone(), callback:t.callback });
+                    t.expired_count+=1;
+                    t.expire_tick = std::cmp::max(t.expire_tick+msec_to_ticks(t.duration_msec,tick_msec), cur_tick+1);
+                    t.loop_count>0 && t.expired_count>=t.loop_count
+                },
+            };
+            if finished {
+                self.remove_timer(id);
+                release_timer_id(id);
+            } else {
+                self.schedule(id);
+            }
+        }
+        self.stats.fired+=fired.len() as u64;
+        let mut id = self.heads[READY_LIST];
+        while let Some(t) = self.get_timer(id) {
+            id = t.next;
+            self.stats.deferred+=1;
+        }
+        return fired
     }
+}
 
-    errcode::RESULT_SUCCESS
+fn release_timer_id(id:rsm_timer_id_t) {
+    if let Some(a) = unsafe { &mut gTimerIdAlloc} {
+        a.release_id(id);
+    }
 }
+
 ///set a timer, loop_count=1 indicate a one time timer, 0-loop forever
 pub fn set_timer(dur_msec:u64,loop_count:u64,timer_data:usize)->Option<rsm_timer_id_t> {
-    let ida = match unsafe { &mut gTimerIdAlloc} {
+    return set_timer_ext(dur_msec, loop_count, timer_data, RSM_MSG_ID_TIMER)
+}
+
+///set a timer which send msg_id instead of RSM_MSG_ID_TIMER to the caller on expiry, for rsm inner use
+pub(crate) fn set_timer_ext(dur_msec:u64,loop_count:u64,timer_data:usize,msg_id:rsm_message_id_t)->Option<rsm_timer_id_t> {
+    let caller = match rsm_sched::get_self_cid() {
         None=>return None,
-        Some(a)=> a,
+        Some(c)=>c,
     };
-    let id = ida.allocate_id();
-    if id==INVALID_TIMER_ID {
-        return None;
+    return add_timer(new_timer_desc(&caller, dur_msec, loop_count, timer_data, msg_id), None)
+}
+
+///set a timer carrying a payload, on expiry the owner receives a message with msg_id by *on_message* instead of *on_timer*,
+/// msg.decode() restores the payload and msg.get_timer_id() returns the timer id. msg_id must be an application message id
+pub fn set_timer_with<'de,T>(dur_msec:u64,loop_count:u64,msg_id:rsm_message_id_t,payload:&T)->Option<rsm_timer_id_t>
+    where T:Sized+Serialize+Deserialize<'de> {
+    if msg_id<RSM_USER_MESSAGE_ID_START {
+        return None
     }
     let caller = match rsm_sched::get_self_cid() {
         None=>return None,
         Some(c)=>c,
     };
-    let timer = timer_desc_t {
-        id:id,
+    let msg = match rsm_message_t::new(msg_id, payload) {
+        None=>return None,
+        Some(m)=>m,
+    };
+    let mut timer = new_timer_desc(&caller, dur_msec, loop_count, 0, msg_id);
+    timer.body = msg.msg_body;
+    return add_timer(timer, None)
+}
+
+///set a one time timer which expires at an absolute deadline, deadline_msec is on the clock of get_monotonic_msec,
+/// a deadline already passed fires on the next tick
+pub fn set_timer_at(deadline_msec:u64,timer_data:usize)->Option<rsm_timer_id_t> {
+    let caller = match rsm_sched::get_self_cid() {
+        None=>return None,
+        Some(c)=>c,
+    };
+    let dur_msec = deadline_msec.saturating_sub(get_monotonic_msec());
+    return add_timer(new_timer_desc(&caller, dur_msec, 1, timer_data, RSM_MSG_ID_TIMER), Some(deadline_msec))
+}
+
+///set a timer for the task dst, which receives the *on_timer* event, it can be called by any thread.
+/// the timer is owned by dst, and can be killed by either dst or the caller
+pub fn set_timer_for(dst:&rsm_component_t,dur_msec:u64,loop_count:u64,timer_data:usize)->Option<rsm_timer_id_t> {
+    if rsm_sched::get_task_stats(dst).is_none() {
+        return None
+    }
+    return add_timer(new_timer_desc(dst, dur_msec, loop_count, timer_data, RSM_MSG_ID_TIMER), None)
+}
+
+///set a timer which invokes the callback under the timer thread instead of sending a message, for the threads not managed by rsm
+pub fn set_callback_timer(dur_msec:u64,loop_count:u64,timer_data:usize,callback:rsm_timer_callback)->Option<rsm_timer_id_t> {
+    let caller = get_caller_cid();
+    let mut timer = new_timer_desc(&caller, dur_msec, loop_count, timer_data, RSM_MSG_ID_TIMER);
+    timer.callback = Some(callback);
+    return add_timer(timer, None)
+}
+
+fn new_timer_desc(owner:&rsm_component_t,dur_msec:u64,loop_count:u64,timer_data:usize,msg_id:rsm_message_id_t)->timer_desc_t {
+    return timer_desc_t {
+        id:INVALID_TIMER_ID,
         duration_msec:dur_msec,
         loop_count:loop_count,
         timer_data:timer_data,
-        last_fired:get_inner_time_stamp(),
-        cid:caller,
+        cid:owner.clone(),
+        creator:get_caller_cid(),
         expired_count:0,
-    };
+        msg_id:msg_id,
+        name:String::default(),
+        callback:None,
+        body:rsm_msg_body_t::Empty,
+        expire_tick:0,
+        list:NO_LIST,
+        prev:INVALID_TIMER_ID,
+        next:INVALID_TIMER_ID,
+    }
+}
 
-    let timer_cat = get_timer_cat(dur_msec);
-    let timer_map = match get_timer_cb_by_cat(timer_cat) {
+//allocate the timer id and put the timer into the wheel
+fn add_timer(mut timer:timer_desc_t,deadline_msec:Option<u64>)->Option<rsm_timer_id_t> {
+    let ida = match unsafe { &mut gTimerIdAlloc} {
         None=>return None,
-        Some(m)=>m,
+        Some(a)=> a,
+    };
+    let wheel = match unsafe { &mut gTimerWheel} {
+        None=>return None,
+        Some(w)=>w,
     };
-    if timer_map.insert(id,timer)!=errcode::RESULT_SUCCESS {
+    let id = ida.allocate_id();
+    if id==INVALID_TIMER_ID {
+        return None;
+    }
+    wheel.lock.lock();
+    timer.id = id;
+    timer.expire_tick = match deadline_msec {
+        None=>wheel.now_tick()+msec_to_ticks(timer.duration_msec,wheel.tick_msec),
+        Some(d)=>deadline_to_tick(d, wheel.tick_msec),
+    };
+    let ret = wheel.add_timer(timer);
+    wheel.lock.unlock();
+    if ret!=errcode::RESULT_SUCCESS {
         ida.release_id(id);
         return None        
     }
-    set_timer_cat_map(id, timer_cat);
     return Some(id)
 }
 
+//the timer can be operated by its owner or creator, a non rsm thread is identified by its own token, the wheel must be locked
+fn check_timer_owner(wheel:&mut timer_wheel_t,timer_id:rsm_timer_id_t,cid:&rsm_component_t)->errcode::RESULT {
+    match wheel.get_timer(timer_id) {
+        None=>errcode::ERROR_NOT_FOUND,
+        Some(t)=> if t.cid!=*cid && t.creator!=*cid {
+            errcode::ERROR_NO_PERMISSION
+        } else {
+            errcode::RESULT_SUCCESS
+        },
+    }
+}
+
 pub fn kill_timer_by_id(timer_id:rsm_timer_id_t)->errcode::RESULT {
-    if timer_id as usize> MAX_TIMER_COUNT {
+    return kill_timer(timer_id, Some(&get_caller_cid()))
+}
+
+///kill a timer without the owner check, for the rsm inner modules which kill the timer from another thread
+pub(crate) fn kill_timer_internal(timer_id:rsm_timer_id_t)->errcode::RESULT {
+    return kill_timer(timer_id, None)
+}
+
+fn kill_timer(timer_id:rsm_timer_id_t,caller:Option<&rsm_component_t>)->errcode::RESULT {
+    if timer_id<=0 || timer_id as usize> MAX_TIMER_COUNT {
         return errcode::ERROR_INVALID_PARAM
     }
 
-    let cid=match rsm_sched::get_self_cid() {
-        None=>return errcode::ERROR_INVALID_STATE,
-        Some(c)=>c,
+    let wheel = match unsafe { &mut gTimerWheel} {
+        None=>return errcode::ERROR_NOT_INITIALIZED,
+        Some(w)=>w,
     };
-    let cat = unsafe {gTimerCatMap[timer_id as usize]};
-    let pMap = match get_timer_cb_by_cat(cat) {
-        None=>return errcode::ERROR_NOT_FOUND,
-        Some(m)=>m
+    wheel.lock.lock();
+    let ret = match caller {
+        None if wheel.get_timer(timer_id).is_none()=>errcode::ERROR_NOT_FOUND,
+        None=>errcode::RESULT_SUCCESS,
+        Some(cid)=>check_timer_owner(wheel, timer_id, cid),
     };
-    
-    if let Some(timer) = pMap.get(&timer_id) {
-        if timer.cid !=cid {
-            return errcode::ERROR_INVALID_STATE;
+    if ret==errcode::RESULT_SUCCESS {
+        wheel.remove_timer(timer_id);
+        release_timer_id(timer_id);
+    }
+    wheel.lock.unlock();
+    return ret
+}
+
+///give the timer a name, which is shown in the OAM timer list
+pub fn set_timer_name(timer_id:rsm_timer_id_t,name:&str)->errcode::RESULT {
+    let cid = get_caller_cid();
+    let wheel = match unsafe { &mut gTimerWheel} {
+        None=>return errcode::ERROR_NOT_INITIALIZED,
+        Some(w)=>w,
+    };
+    wheel.lock.lock();
+    let ret = check_timer_owner(wheel, timer_id, &cid);
+    if let Some(t) = wheel.get_timer(timer_id) {
+        if ret==errcode::RESULT_SUCCESS {
+            t.name = name.to_string();
         }
-    } else {
-        return errcode::ERROR_NOT_FOUND
     }
-    pMap.remove(&timer_id);
+    wheel.lock.unlock();
+    return ret
+}
 
-    match unsafe { &mut gTimerIdAlloc} {
-        None=>(),
-        Some(a)=>{
-            a.release_id(timer_id);
-        },
+///re-arm a running timer with a new period, the timer expires dur_msec later and then every dur_msec,
+/// the loop count is kept. a one time timer already expired can't be modified
+pub fn modify_timer(timer_id:rsm_timer_id_t,dur_msec:u64)->errcode::RESULT {
+    if timer_id<=0 || timer_id as usize> MAX_TIMER_COUNT {
+        return errcode::ERROR_INVALID_PARAM
+    }
+    let cid = get_caller_cid();
+    let wheel = match unsafe { &mut gTimerWheel} {
+        None=>return errcode::ERROR_NOT_INITIALIZED,
+        Some(w)=>w,
+    };
+    wheel.lock.lock();
+    let ret = check_timer_owner(wheel, timer_id, &cid);
+    if ret==errcode::RESULT_SUCCESS {
+        let now_tick = wheel.now_tick();
+        wheel.rearm(timer_id, dur_msec, now_tick);
+    }
+    wheel.lock.unlock();
+    return ret
+}
+
+///kill all the timers owned by a task, called when the task is closed
+pub(crate) fn kill_timers_by_owner(cid:&rsm_component_t)->usize {
+    let wheel = match unsafe { &mut gTimerWheel} {
+        None=>return 0,
+        Some(w)=>w,
+    };
+    wheel.lock.lock();
+    let to_delete:Vec<rsm_timer_id_t> = wheel.timers.iter().flatten()
+        .filter(|t| t.cid==*cid).map(|t| t.id).collect();
+    for id in to_delete.iter() {
+        wheel.remove_timer(*id);
+        release_timer_id(*id);
     }
-    errcode::RESULT_SUCCESS
+    wheel.lock.unlock();
+    return to_delete.len()
 }
 
+static gTimerStopped:AtomicBool=AtomicBool::new(false);
+static mut gTimerThread:Option<std::thread::JoinHandle<()>>=None;
 ///run timer schedule task,scan the allocated timer map, send message to correspondant task
 pub(crate) fn start_timer_thread() {
 
-     init_timer();
-   
-    std::thread::spawn(move || {
+    init_timer(config::RSM_DEF_TIMER_TICK_MSEC);
+    gTimerStopped.store(false, Ordering::SeqCst);
+    let h = std::thread::spawn(move || {
         timer_loop()
     });
-}
-static mut cur_time_stamp:u64=0;
-fn incr_inner_time_stamp() {
-    //most os use ticks as min timer unit
     unsafe {
-        cur_time_stamp=common::get_now_usec64()/1000;
+        gTimerThread = Some(h);
     }
 }
 
-fn get_inner_time_stamp()->u64 {
-    unsafe {
-        return cur_time_stamp;
+///stop the timer thread and wait for it to exit, no more timer message is sent afterwards
+pub(crate) fn stop_timer_thread() {
+    gTimerStopped.store(true, Ordering::SeqCst);
+    if let Some(h) = unsafe { gTimerThread.take() } {
+        let _ = h.join();
     }
 }
-
-const TIMER_TASK_INNER:usize = 1;
-const TIMER_TASK_10MS:usize = 2;
-const TIMER_TASK_100MS:usize = 3;
-const TIMER_TASK_1S:usize = 4;
+const TIMER_TASK_TICK:usize = 2;
 fn timer_loop() {
+    let tick_msec = match unsafe { &gTimerWheel} {
+        None=>return,
+        Some(w)=>w.tick_msec,
+    };
     os_timer::init_os_timer();
-    let _tm = match os_timer::os_timer_t::new(10,TIMER_TASK_10MS,scan_timer_call_back) {
+    let _tm = match os_timer::os_timer_t::new(tick_msec,TIMER_TASK_TICK,scan_timer_call_back) {
         None=> {
             println!("set time failed");
             return
@@ -205,109 +633,302 @@ fn timer_loop() {
         Some(t)=>t,
     };
     
-    loop {
-       std::thread::sleep(std::time::Duration::from_millis(1000));       
+    while !gTimerStopped.load(Ordering::SeqCst) {
+       std::thread::sleep(std::time::Duration::from_millis(100));       
     }
 }
 
-///send timer message
-fn send_timer_msg(cid:&rsm_component_t,tid:rsm_timer_id_t,timer_data:usize) {
-    let timer_msg = match rsm_message_t::new_timer_msg(tid, timer_data) {
-        None=>return,
+//build the message of a fired timer, the payload of a typed timer is carried as the message body
+fn new_timer_event_msg(msg_id:rsm_message_id_t,tid:rsm_timer_id_t,timer_data:usize,body:rsm_msg_body_t)->Option<rsm_message_t> {
+    let mut timer_msg = match rsm_message_t::new_timer_msg(msg_id,tid, timer_data) {
+        None=>return None,
         Some(m)=>m,
     };
-    rsm_sched::send_asyn_priority_msg(cid, timer_msg);
+    timer_msg.msg_body = body;
+    return Some(timer_msg)
+}
+
+///send timer message
+fn send_timer_msg(cid:&rsm_component_t,msg_id:rsm_message_id_t,tid:rsm_timer_id_t,timer_data:usize,body:rsm_msg_body_t) {
+    if let Some(timer_msg) = new_timer_event_msg(msg_id, tid, timer_data, body) {
+        rsm_sched::send_asyn_priority_msg(cid, timer_msg);
+    }
 }
 
 ///os timer callback
 fn scan_timer_call_back(_timerId:i32,timer_data:usize) {
-    static mut count:u64=0;
-    match timer_data {
-        //TIMER_TASK_INNER=>incr_inner_time_stamp(),
-        TIMER_TASK_10MS=> {
-            unsafe { count+=1;};
-            incr_inner_time_stamp();
-            scan_timer_10ms();
-            if unsafe { count % 10==0} {
-                scan_timer_100ms();
-            }
-            if unsafe { count % 100==0 } {
-                scan_timer_1s();
-            }
-            
-        },
-        _=>(),
+    if gTimerStopped.load(Ordering::SeqCst) || timer_data!=TIMER_TASK_TICK {
+        return
     }
-}
-///scan 10ms timer, and send timer message to specific component
-fn scan_timer_10ms() {
-    let tMap = match unsafe { &mut gTimer10ms} {
+    let wheel = match unsafe { &mut gTimerWheel} {
         None=>return,
-        Some(m)=>m,
+        Some(w)=>w,
     };
-    scan_timer_proc(tMap);
+    wheel.lock.lock();
+    //catch up the ticks elapsed, the os timer callback may be delayed
+    let target = wheel.now_tick();
+    while wheel.cur_tick<target {
+        wheel.advance();
+    }
+    let fired = wheel.fire();
+    wheel.lock.unlock();
+    for ev in fired {
+        match ev.callback {
+            None=>send_timer_msg(&ev.cid, ev.msg_id, ev.id, ev.timer_data, ev.body),
+            Some(cb)=>cb(ev.id, ev.timer_data),
+        }
+    }
 }
 
-fn scan_timer_100ms() {
-    let tMap = match unsafe { &mut gTimer100ms} {
-        None=>return,
-        Some(m)=>m,
+pub fn get_timer_stats()->timer_stats_t {
+    let wheel = match unsafe { &gTimerWheel} {
+        None=>return timer_stats_t::default(),
+        Some(w)=>w,
     };
-    scan_timer_proc(tMap);   
+    wheel.lock.lock();
+    let mut stats = wheel.stats.clone();
+    wheel.lock.unlock();
+    stats.total =  match unsafe {&gTimerIdAlloc} {
+        None=>0,
+        Some(ids)=>ids.used_count() as usize,
+    };
+    return stats
 }
 
-fn scan_timer_1s() {
-    let tMap = match unsafe { &mut gTimer1s} {
-        None=>return,
-        Some(m)=>m,
+///list the timer statistics and the active timers, the param is an optional filter of owner, either "cid" or "cid:inst"
+fn process_timer_oam(op:E_RSM_OAM_OP,_url:&String,param:&String)->oam::oam_cmd_resp_t {
+    let mut tResp = oam::oam_cmd_resp_t::new(errcode::RESULT_SUCCESS,&String::default());
+    if op!=E_RSM_OAM_OP::CLI_OP_SHOW {
+        tResp.RetCode = errcode::ERROR_NOT_SUPPORT;
+        return tResp
+    }
+    let mut ids = param.split(':').map(|p| p.trim().parse::<usize>());
+    let cid = match ids.next() {
+        Some(Ok(c))=>Some(c as rsm_component_id_t),
+        _=>None,
     };
-    scan_timer_proc(tMap);   
-}
-
-fn scan_timer_proc(timer_map:&mut timer_hash_map) {
-    let cur = get_inner_time_stamp();//common::get_now_usec64();
-    let mut to_delete=Vec::new();
-    for (id,t) in timer_map.iter_mut() {
-        if cur>=t.last_fired+t.duration_msec {
-            send_timer_msg(&t.cid, *id, t.timer_data);
-            t.last_fired=cur;
-            t.expired_count+=1;
-            if t.loop_count>0 && t.expired_count>=t.loop_count {
-                to_delete.push(t.id);
-            }
-            
+    let inst = match ids.next() {
+        Some(Ok(i))=>Some(i),
+        _=>None,
+    };
+    let wheel = match unsafe { &mut gTimerWheel} {
+        None=> {
+            tResp.RetCode = errcode::ERROR_NOT_INITIALIZED;
+            return tResp
+        },
+        Some(w)=>w,
+    };
+    let stats = get_timer_stats();
+    tResp.set_row_hdr(vec!["total".to_string(),"10ms".to_string(),"100ms".to_string(),"1s".to_string(),
+        "fired".to_string(),"deferred".to_string(),"tick_msec".to_string()]);
+    tResp.add_row(&vec![stats.total.to_string(),stats.timer_count_10ms.to_string(),stats.timer_count_100ms.to_string(),
+        stats.timer_count_1s.to_string(),stats.fired.to_string(),stats.deferred.to_string(),wheel.tick_msec.to_string()]);
+
+    tResp.set_row_hdr(vec!["timer_id".to_string(),"owner".to_string(),"creator".to_string(),"name".to_string(),"msg_id".to_string(),"period_msec".to_string(),
+        "loop_count".to_string(),"expired_count".to_string(),"next_fire_msec".to_string(),"remain_msec".to_string()]);
+    let now = get_monotonic_msec();
+    let mut rows = Vec::new();
+    wheel.lock.lock();
+    for t in wheel.timers.iter().flatten() {
+        if cid.map_or(false, |c| c!=t.cid.cid) || inst.map_or(false, |i| i!=t.cid.inst_id) {
+            continue
+        }
+        let next_fire = t.expire_tick*wheel.tick_msec;
+        rows.push(vec![t.id.to_string(),format!("{}:{}",t.cid.cid,t.cid.inst_id),
+            if t.callback.is_some() {format!("{}:{},callback",t.creator.cid,t.creator.inst_id)} else {format!("{}:{}",t.creator.cid,t.creator.inst_id)},t.name.clone(),t.msg_id.to_string(),
+            t.duration_msec.to_string(),t.loop_count.to_string(),t.expired_count.to_string(),next_fire.to_string(),
+            next_fire.saturating_sub(now).to_string()]);
+        if rows.len()>=MAX_OAM_TIMER_ROWS {
+            break
         }
     }
-    timer_map.end_iter();
-    // if expiry count large than expected, remove the timer
-    for i in to_delete {
-        timer_map.remove(&i);
+    wheel.lock.unlock();
+    for r in rows.iter() {
+        tResp.add_row(r);
     }
+    return tResp
 }
 
-pub fn get_timer_stats()->timer_stats_t {
-    let mut stats = timer_stats_t::default();
-    unsafe {
-        stats.total =  match &gTimerIdAlloc {
-            None=>0,
-            Some(ids)=>ids.used_count() as usize,
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
 
-        stats.timer_count_10ms = match &gTimer10ms {
-            None=>0,
-            Some(tm)=>tm.len(),
-        };
+    fn test_timer_callback(_timer_id:rsm_timer_id_t,_timer_data:usize) {
+    }
 
-        stats.timer_count_100ms = match &gTimer100ms {
-            None=>0,
-            Some(tm)=>tm.len(),
-        };
-        stats.timer_count_1s = match &gTimer1s {
-            None=>0,
-            Some(tm)=>tm.len(),
-        };
+    //add a timer to a standalone wheel, the ids start from 1000 to keep away from the ids allocated by the other tests
+    fn add_test_timer(wheel:&mut timer_wheel_t,id:rsm_timer_id_t,dur_msec:u64,loop_count:u64,expire_tick:u64) {
+        let mut t = new_timer_desc(&rsm_component_t::new_zero(), dur_msec, loop_count, 0, RSM_MSG_ID_TIMER);
+        t.id = id;
+        t.expire_tick = expire_tick;
+        assert_eq!(wheel.add_timer(t), errcode::RESULT_SUCCESS);
     }
-    
 
-    return stats
+    //advance the wheel tick by tick, return the fired timers and the tick they are fired
+    fn run_to(wheel:&mut timer_wheel_t,tick:u64)->Vec<(rsm_timer_id_t,u64)> {
+        let mut fired = Vec::new();
+        while wheel.cur_tick<tick {
+            wheel.advance();
+            for ev in wheel.fire() {
+                fired.push((ev.id,wheel.cur_tick));
+            }
+        }
+        return fired
+    }
+
+    #[test]
+    fn test_wheel_schedule_cascade() {
+        let mut wheel = timer_wheel_t::new(10);
+        //the boundaries of each level, and a timer beyond the range of the top level
+        let ticks = [1u64,5,63,64,65,100,4095,4096,4097,262143,262144,262145,16777215,16777216+100];
+        for (i,t) in ticks.iter().enumerate() {
+            add_test_timer(&mut wheel, 1000+i as rsm_timer_id_t, t*10, 1, *t);
+        }
+        let fired = run_to(&mut wheel, 16777216+200);
+        let expected:Vec<(rsm_timer_id_t,u64)> = ticks.iter().enumerate().map(|(i,t)| (1000+i as rsm_timer_id_t,*t)).collect();
+        assert_eq!(fired, expected);
+        assert_eq!(wheel.stats.fired, ticks.len() as u64);
+        assert!(wheel.timers.iter().all(|t| t.is_none()));
+
+        //a periodic timer is rescheduled after each expiry, and removed after loop_count expiries
+        let start = wheel.cur_tick;
+        add_test_timer(&mut wheel, 1100, 30, 3, start+3);
+        assert_eq!(run_to(&mut wheel, start+20), vec![(1100,start+3),(1100,start+6),(1100,start+9)]);
+        assert!(wheel.get_timer(1100).is_none());
+        assert_eq!(wheel.stats.timer_count_10ms, 0);
+    }
+
+    #[test]
+    fn test_wheel_kill_and_past_deadline() {
+        let mut wheel = timer_wheel_t::new(10);
+        //the timers in the same slot are linked, kill the one in the middle
+        for id in 1200..1203 {
+            add_test_timer(&mut wheel, id, 100, 1, 10);
+        }
+        //a timer killed after it is cascaded to the lower level
+        add_test_timer(&mut wheel, 1203, 50000, 1, 5000);
+        assert!(wheel.remove_timer(1201).is_some());
+        assert!(wheel.remove_timer(1201).is_none());
+        assert_eq!(run_to(&mut wheel, 4200), vec![(1200,10),(1202,10)]);
+        assert_eq!(wheel.get_timer(1203).map(|t| t.list<WHEEL_SIZE), Some(false));
+        run_to(&mut wheel, 4995);
+        assert!(wheel.get_timer(1203).map_or(false, |t| t.list<WHEEL_SIZE));
+        assert!(wheel.remove_timer(1203).is_some());
+        assert_eq!(run_to(&mut wheel, 6000), vec![]);
+
+        //a deadline already passed is put into the ready list, and fired without advancing the wheel
+        let past = wheel.cur_tick-5;
+        add_test_timer(&mut wheel, 1204, 10, 1, past);
+        let fired:Vec<rsm_timer_id_t> = wheel.fire().iter().map(|e| e.id).collect();
+        assert_eq!(fired, vec![1204]);
+        assert!(wheel.timers.iter().all(|t| t.is_none()));
+        assert_eq!((wheel.stats.timer_count_10ms,wheel.stats.timer_count_100ms,wheel.stats.timer_count_1s), (0,0,0));
+    }
+
+    #[test]
+    fn test_wheel_deadline_and_rearm() {
+        //the deadline is rounded up to the tick, the timer never fires before the deadline
+        assert_eq!(deadline_to_tick(100, 10), 10);
+        assert_eq!(deadline_to_tick(105, 10), 11);
+        assert_eq!(deadline_to_tick(0, 10), 0);
+
+        let mut wheel = timer_wheel_t::new(10);
+        add_test_timer(&mut wheel, 1300, 0, 1, deadline_to_tick(105, 10));
+        add_test_timer(&mut wheel, 1301, 500, 0, 5);
+        assert_eq!(run_to(&mut wheel, 7), vec![(1301,5)]);
+
+        //re-armed at tick 7 with a 20ms period, the loop count is kept
+        wheel.rearm(1301, 20, 7);
+        assert_eq!(run_to(&mut wheel, 13), vec![(1301,9),(1300,11),(1301,11),(1301,13)]);
+        assert_eq!(wheel.get_timer(1301).map(|t| (t.duration_msec,t.loop_count,t.expired_count)), Some((20,0,4)));
+        assert_eq!((wheel.stats.timer_count_10ms,wheel.stats.timer_count_100ms), (1,0));
+
+        //the wheel lags behind the monotonic clock, the timer is re-armed from the current time
+        wheel.rearm(1301, 150, 20);
+        assert_eq!((wheel.stats.timer_count_10ms,wheel.stats.timer_count_100ms), (0,1));
+        assert_eq!(run_to(&mut wheel, 40), vec![(1301,35)]);
+        //re-arm a killed timer has no effect
+        assert!(wheel.remove_timer(1301).is_some());
+        wheel.rearm(1301, 20, 40);
+        assert_eq!(run_to(&mut wheel, 100), vec![]);
+        assert_eq!((wheel.stats.timer_count_10ms,wheel.stats.timer_count_100ms), (0,0));
+
+        //the monotonic clock never goes backward
+        let mut last = get_monotonic_msec();
+        for _ in 0..1000 {
+            let now = get_monotonic_msec();
+            assert!(now>=last);
+            last = now;
+        }
+    }
+
+    #[test]
+    fn test_timer_owner_of_threads() {
+        init_timer(0);
+        let id = set_callback_timer(60000, 1, 0, test_timer_callback).unwrap();
+        //another non rsm thread can't operate the timer
+        let (other_id,kill,modify,name) = thread::spawn(move || {
+            let other_id = set_callback_timer(60000, 1, 0, test_timer_callback).unwrap();
+            (other_id,kill_timer_by_id(id),modify_timer(id, 1000),set_timer_name(id, "other"))
+        }).join().unwrap();
+        assert_eq!(kill, errcode::ERROR_NO_PERMISSION);
+        assert_eq!(modify, errcode::ERROR_NO_PERMISSION);
+        assert_eq!(name, errcode::ERROR_NO_PERMISSION);
+        assert_eq!(kill_timer_by_id(other_id), errcode::ERROR_NO_PERMISSION);
+
+        assert_eq!(set_timer_name(id, "mine"), errcode::RESULT_SUCCESS);
+        assert_eq!(kill_timer_by_id(id), errcode::RESULT_SUCCESS);
+        assert_eq!(kill_timer_by_id(id), errcode::ERROR_NOT_FOUND);
+        //the rsm inner modules kill the timer regardless of the owner
+        assert_eq!(kill_timer_internal(other_id), errcode::RESULT_SUCCESS);
+        assert_eq!(kill_timer_internal(other_id), errcode::ERROR_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_wheel_typed_payload() {
+        let mut wheel = timer_wheel_t::new(10);
+        let msg_id = RSM_USER_MESSAGE_ID_START+10;
+        let payload = (7u32,String::from("payload"));
+        let mut t = new_timer_desc(&rsm_component_t::new_zero(), 20, 2, 0, msg_id);
+        t.id = 1400;
+        t.expire_tick = 2;
+        t.body = rsm_message_t::new(msg_id, &payload).unwrap().msg_body;
+        assert_eq!(wheel.add_timer(t), errcode::RESULT_SUCCESS);
+
+        //each expiry of the periodic timer carries the payload
+        let mut fired = Vec::new();
+        while wheel.cur_tick<10 {
+            wheel.advance();
+            for ev in wheel.fire() {
+                assert_eq!((ev.id,ev.msg_id), (1400,msg_id));
+                assert!(ev.callback.is_none());
+                let msg = new_timer_event_msg(ev.msg_id, ev.id, ev.timer_data, ev.body).unwrap();
+                assert_eq!(msg.get_timer_id(), 1400);
+                assert_eq!(msg.decode::<(u32,String)>(), Some(payload.clone()));
+                fired.push(wheel.cur_tick);
+            }
+        }
+        assert_eq!(fired, vec![2,4]);
+
+        //a plain timer message has no payload
+        let msg = new_timer_event_msg(RSM_MSG_ID_TIMER, 1401, 5, rsm_msg_body_t::Empty).unwrap();
+        assert_eq!(msg.decode::<u32>(), None);
+    }
+
+    #[test]
+    fn test_timer_name() {
+        init_timer(0);
+        let id = set_callback_timer(60000, 0, 0, test_timer_callback).unwrap();
+        assert_eq!(set_timer_name(id, "heartbeat"), errcode::RESULT_SUCCESS);
+        assert_eq!(set_timer_name(-1, "heartbeat"), errcode::ERROR_NOT_FOUND);
+        //the name is listed by OAM with the timer
+        let resp = process_timer_oam(E_RSM_OAM_OP::CLI_OP_SHOW, &String::default(), &String::default());
+        assert_eq!(resp.RetCode, errcode::RESULT_SUCCESS);
+        let rows = serde_json::to_string(&resp.RespRows).unwrap();
+        assert!(rows.contains("\"heartbeat\""));
+        //a typed timer requires an application message id
+        assert!(set_timer_with(100, 1, RSM_MSG_ID_TIMER, &0u32).is_none());
+        assert_eq!(kill_timer_by_id(id), errcode::RESULT_SUCCESS);
+    }
 }
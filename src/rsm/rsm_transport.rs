@@ -0,0 +1,724 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+//rsm transport, forward the messages addressed to the components of other rsm nodes.
+//a system component owns the udp socket or the tcp connections, messages to be forwarded are put into its queue,
+//then encoded and sent to the peer node, the received message is delivered to the local destination component
+use super::*;
+use super::oam::E_RSM_OAM_OP;
+use super::config::{E_RSM_TRANSPORT_PROTO,rsm_node_cfg_t};
+use super::socket::{UdpSocket,TcpSocket,TcpListener,SOCKET_LB_POLICY};
+use crate::common::{errcode,tsmap::TsHashMap};
+use std::collections::HashMap;
+use std::net::{IpAddr,SocketAddr};
+
+const RSM_TRANSPORT_URL:&str = "/node";
+const RSM_TRANSPORT_TICK_MSEC:u64 = 100;
+///interval to reconnect a peer, it is also the connect timeout of a tcp connection
+const RSM_RECONNECT_INTERVAL_USEC:u64 = 1000*1000;
+const MAX_PEER_NUM:usize = 256;
+const MAX_PEER_OUT_BUF:usize = 4*1024*1024;
+const MAX_FRAME_LEN:usize = RSM_MAX_MESSAGE_LEN+256;
+const RECV_BUF_SIZE:usize = 65536;
+const TCP_BACK_LOG:i32 = 64;
+//the forwarded message body is [dst node_id:u32][frame_len:u32][frame], the length prefixed frame is sent over tcp as is
+const FRAME_HDR_LEN:usize = 4;
+const FRAME_BODY_EMPTY:u8 = 3;
+
+///connection state of a peer node, a udp peer is always connected once the local socket is opened
+#[derive(Copy,Clone,PartialEq,Debug,Serialize)]
+pub enum E_PEER_STATE {
+    PEER_DISCONNECTED = 0,
+    PEER_CONNECTING = 1,
+    PEER_CONNECTED = 2,
+}
+
+#[derive(Clone,Debug,Serialize)]
+pub struct rsm_peer_stats_t {
+    pub node_id:rsm_node_id_t,
+    pub addr:SocketAddr,
+    pub state:E_PEER_STATE,
+    pub sent_msg:u64,
+    pub sent_bytes:u64,
+    pub recv_msg:u64,
+    pub recv_bytes:u64,
+    ///message dropped because the peer is not connected or the send buffer is full
+    pub drop_msg:u64,
+    pub connect_count:u64,
+    pub disconnect_count:u64,
+}
+
+struct peer_t {
+    stats:rsm_peer_stats_t,
+    //outgoing tcp connection, -1 if not connected
+    sock_id:i32,
+    connect_at:u64,
+    out_buf:Vec<u8>,
+}
+
+struct transport_t {
+    local:rsm_node_cfg_t,
+    proto:E_RSM_TRANSPORT_PROTO,
+    peers:TsHashMap<rsm_node_id_t,peer_t>,
+    //received frames can't be decoded or not addressed to the local node
+    invalid_frame:u64,
+}
+
+static mut gTransport:Option<transport_t>=None;
+
+#[derive(Serialize,Deserialize)]
+struct transport_frame_t {
+    src:rsm_component_t,
+    dst:rsm_component_t,
+    msg_id:rsm_message_id_t,
+    req_id:rsm_request_id_t,
+    is_response:bool,
+    priority:bool,
+    codec:u8,
+    body:Vec<u8>,
+}
+
+///initialize the transport according to the node table, the transport component is registered
+/// only if the local node is in the table and there is at least one peer node
+pub(crate) fn init_transport(conf:&config::rsm_init_cfg_t)->errcode::RESULT {
+    if unsafe {gTransport.is_some()} {
+        return errcode::ERROR_ALREADY_EXIST
+    }
+    let local = match conf.nodes.iter().find(|n| n.node_id==conf.node_id) {
+        None=>return errcode::ERROR_NO_OP,
+        Some(n)=>n.clone(),
+    };
+    let mut peers = TsHashMap::new(MAX_PEER_NUM);
+    for n in conf.nodes.iter() {
+        if n.node_id==conf.node_id || peers.contains_key(&n.node_id) {
+            continue
+        }
+        let state = if conf.transport==E_RSM_TRANSPORT_PROTO::TRANSPORT_UDP {
+            E_PEER_STATE::PEER_CONNECTED
+        } else {
+            E_PEER_STATE::PEER_DISCONNECTED
+        };
+        let p = peer_t {
+            stats:rsm_peer_stats_t { node_id:n.node_id, addr:n.addr, state, sent_msg:0, sent_bytes:0,
+                recv_msg:0, recv_bytes:0, drop_msg:0, connect_count:0, disconnect_count:0 },
+            sock_id:-1,
+            connect_at:0,
+            out_buf:Vec::new(),
+        };
+        peers.insert(n.node_id, p);
+    }
+    if peers.len()==0 {
+        return errcode::ERROR_NO_OP
+    }
+    unsafe {
+        gTransport = Some(transport_t {
+            local,
+            proto:conf.transport,
+            peers,
+            invalid_frame:0,
+        });
+    }
+    let urls = [RSM_TRANSPORT_URL.to_string()];
+    oam::RegisterOamModule(&urls, process_transport_oam);
+
+    let attrs = component_attrs_t::new(&RSM_TRANSPORT_CID, "rsm_transport", 1, RSM_MAX_QUEUE_LEN,
+        E_RSM_TASK_PRIORITY::THREAD_PRI_HIGH, false);
+    let mut policy = component_policy_t::default();
+    policy.set_init_policy(RSM_DEF_INIT_TIMEOUT_MSEC, E_RSM_INIT_FAIL_POLICY::INIT_FAIL_SKIP);
+    return rsm_sched::registry_component(RSM_TRANSPORT_CID, &attrs, &policy, rsm_sched::task_factory_t::BOXED(new_transport_task))
+}
+
+fn transport_task_id(node_id:rsm_node_id_t)->rsm_component_t {
+    return rsm_component_t::new(RSM_TRANSPORT_CID, node_id, 1)
+}
+
+///get the statistics of the peer nodes
+pub fn get_peer_stats()->Vec<rsm_peer_stats_t> {
+    let mut stats = Vec::new();
+    let tp = match unsafe {&gTransport} {
+        None=>return stats,
+        Some(t)=>t,
+    };
+    for (_,p) in tp.peers.iter() {
+        stats.push(p.stats.clone());
+    }
+    tp.peers.end_iter();
+    stats.sort_by_key(|s| s.node_id);
+    return stats
+}
+
+fn encode_frame(dst:&rsm_component_t,msg:&rsm_message_t,priority:bool)->Option<Vec<u8>> {
+    let (codec,body) = match &msg.msg_body {
+        rsm_msg_body_t::Empty=>(FRAME_BODY_EMPTY,Vec::new()),
+        rsm_msg_body_t::Json(s)=>(E_RSM_MSG_CODEC::MSG_CODEC_JSON as u8,s.as_bytes().to_vec()),
+        rsm_msg_body_t::Binary(b)=>(E_RSM_MSG_CODEC::MSG_CODEC_BINARY as u8,b.clone()),
+        rsm_msg_body_t::Raw(b)=>(E_RSM_MSG_CODEC::MSG_CODEC_RAW as u8,b.clone()),
+        //packet buffer is received as raw bytes on the remote node
+        rsm_msg_body_t::PktBuf(_)=>match msg.get_pkt_buf() {
+            None=>(E_RSM_MSG_CODEC::MSG_CODEC_RAW as u8,Vec::new()),
+            Some(b)=>(E_RSM_MSG_CODEC::MSG_CODEC_RAW as u8,b.as_slice().to_vec()),
+        },
+    };
+    let frame = transport_frame_t {
+        src:msg.sender.clone(),
+        dst:dst.clone(),
+        msg_id:msg.msg_id,
+        req_id:msg.req_id,
+        is_response:msg.is_response,
+        priority,
+        codec,
+        body,
+    };
+    let buf = match bincode::serialize(&frame) {
+        Err(_)=>return None,
+        Ok(b)=>b,
+    };
+    let mut wire = Vec::with_capacity(buf.len()+2*FRAME_HDR_LEN);
+    wire.extend_from_slice(&dst.node_id.to_be_bytes());
+    wire.extend_from_slice(&(buf.len() as u32).to_be_bytes());
+    wire.extend_from_slice(&buf);
+    return Some(wire)
+}
+
+fn decode_frame(buf:&[u8])->Option<(rsm_component_t,rsm_message_t,bool)> {
+    let frame = match bincode::deserialize::<transport_frame_t>(buf) {
+        Err(_)=>return None,
+        Ok(f)=>f,
+    };
+    let body = match frame.codec {
+        FRAME_BODY_EMPTY=>rsm_msg_body_t::Empty,
+        0=>match String::from_utf8(frame.body) {
+            Err(_)=>return None,
+            Ok(s)=>rsm_msg_body_t::Json(s),
+        },
+        1=>rsm_msg_body_t::Binary(frame.body),
+        2=>rsm_msg_body_t::Raw(frame.body),
+        _=>return None,
+    };
+    let mut msg = rsm_message_t::new_with_body(frame.msg_id, body);
+    msg.sender = frame.src;
+    msg.req_id = frame.req_id;
+    msg.is_response = frame.is_response;
+    return Some((frame.dst,msg,frame.priority))
+}
+
+///forward a message to the component of another node, the message is put into the queue of the transport component
+pub(crate) fn forward_msg(dst:&rsm_component_t,msg:rsm_message_t,priority:bool)->errcode::RESULT {
+    let tp = match unsafe {&gTransport} {
+        None=>return errcode::ERROR_NOT_FOUND,
+        Some(t)=>t,
+    };
+    if !tp.peers.contains_key(&dst.node_id) {
+        return errcode::ERROR_NOT_FOUND
+    }
+    let wire = match encode_frame(dst, &msg, priority) {
+        None=>return errcode::ERROR_ENCODE_MSG,
+        Some(w)=>w,
+    };
+    if wire.len()>MAX_FRAME_LEN {
+        return errcode::ERROR_MSG_TOO_LONG
+    }
+    let tmsg = rsm_message_t::new_raw(RSM_MSG_ID_TRANSPORT, wire);
+    let tid = transport_task_id(tp.local.node_id);
+    if priority {
+        return rsm_sched::send_asyn_priority_msg(&tid, tmsg)
+    }
+    return rsm_sched::send_asyn_msg(&tid, tmsg)
+}
+
+///the node is a peer in the node table, the message addressed to it is forwarded by the transport
+pub(crate) fn is_peer_node(node_id:rsm_node_id_t)->bool {
+    return match unsafe {&gTransport} {
+        None=>false,
+        Some(t)=>t.peers.contains_key(&node_id),
+    }
+}
+
+///find the peer node by the source address of a udp datagram
+fn get_peer_by_addr(addr:&SocketAddr)->Option<rsm_node_id_t> {
+    let tp = match unsafe {&gTransport} {
+        None=>return None,
+        Some(t)=>t,
+    };
+    let mut node_id = None;
+    for (id,p) in tp.peers.iter() {
+        if p.stats.addr==*addr {
+            node_id = Some(*id);
+            break
+        }
+    }
+    tp.peers.end_iter();
+    return node_id
+}
+
+fn get_peer(node_id:rsm_node_id_t)->Option<&'static mut peer_t> {
+    return match unsafe {&mut gTransport} {
+        None=>None,
+        Some(t)=>t.peers.get_mut(&node_id),
+    }
+}
+
+fn get_peer_ids()->Vec<rsm_node_id_t> {
+    let mut ids = Vec::new();
+    if let Some(tp) = unsafe {&gTransport} {
+        for (id,_) in tp.peers.iter() {
+            ids.push(*id);
+        }
+        tp.peers.end_iter();
+    }
+    return ids
+}
+
+fn add_invalid_frame() {
+    if let Some(tp) = unsafe {&mut gTransport} {
+        tp.invalid_frame+=1;
+    }
+}
+
+///the system task which owns the transport sockets
+struct transport_task_t {
+    proto:E_RSM_TRANSPORT_PROTO,
+    local:Option<rsm_node_cfg_t>,
+    //udp socket or tcp listener, -1 if not opened
+    sock_id:i32,
+    //outgoing tcp connection to the peer node
+    conns:HashMap<i32,rsm_node_id_t>,
+    //receive buffer of the incoming tcp connections
+    rx_bufs:HashMap<i32,Vec<u8>>,
+    //the peer node of an incoming tcp connection, bound by the hello frame
+    rx_nodes:HashMap<i32,rsm_node_id_t>,
+    recv_buf:Vec<u8>,
+    timer_id:rsm_timer_id_t,
+    inited:bool,
+    log:xlog::xlogger_t,
+}
+
+fn new_transport_task(_cid:&rsm_component_t)->Box<dyn Runnable> {
+    let (proto,local) = match unsafe {&gTransport} {
+        None=>(E_RSM_TRANSPORT_PROTO::TRANSPORT_UDP,None),
+        Some(t)=>(t.proto,Some(t.local.clone())),
+    };
+    return Box::new(transport_task_t {
+        proto,
+        local,
+        sock_id:-1,
+        conns:HashMap::new(),
+        rx_bufs:HashMap::new(),
+        rx_nodes:HashMap::new(),
+        recv_buf:vec![0u8;RECV_BUF_SIZE],
+        timer_id:RSM_INVALID_TIMER_ID,
+        inited:false,
+        log:new_xlog("rsm_transport"),
+    })
+}
+
+impl transport_task_t {
+    fn open_local_socket(&mut self)->errcode::RESULT {
+        let addr = match &self.local {
+            None=>return errcode::ERROR_NOT_INITIALIZED,
+            Some(l)=>l.addr,
+        };
+        let res = match self.proto {
+            E_RSM_TRANSPORT_PROTO::TRANSPORT_UDP=>UdpSocket::new(&addr).map(|s| s.get_socket_id()),
+            E_RSM_TRANSPORT_PROTO::TRANSPORT_TCP=>TcpListener::new(&addr, TCP_BACK_LOG,
+                SOCKET_LB_POLICY::SOCK_LB_CALLER_INSTANCE).map(|s| s.get_sock_id()),
+        };
+        match res {
+            Ok(id)=> {
+                self.sock_id = id;
+                errcode::RESULT_SUCCESS
+            },
+            Err(e)=> {
+                self.log.Errorf("[rsm_transport]", e, &format!("open socket failed,addr={}",addr));
+                e
+            },
+        }
+    }
+
+    fn connect_peer(&mut self,node_id:rsm_node_id_t) {
+        let local_ip = match &self.local {
+            None=>return,
+            Some(l)=>l.addr.ip(),
+        };
+        let peer = match get_peer(node_id) {
+            None=>return,
+            Some(p)=>p,
+        };
+        peer.connect_at = common::get_now_usec64();
+        //bind to the local node address, the peer validates the source address of the connection
+        let bind_ip = if !local_ip.is_unspecified() {
+            local_ip
+        } else if local_ip.is_ipv4() {IpAddr::from([0,0,0,0])} else {IpAddr::from([0u16;8])};
+        let mut sock = match TcpSocket::new(&SocketAddr::new(bind_ip, 0)) {
+            Err(_)=>return,
+            Ok(s)=>s,
+        };
+        peer.sock_id = sock.get_socket_id();
+        peer.stats.state = E_PEER_STATE::PEER_CONNECTING;
+        self.conns.insert(peer.sock_id, node_id);
+        //a hello frame is sent first, which confirms the connection
+        let hello = rsm_message_t::new_sys_msg(RSM_INVALID_MESSAGE_ID);
+        if let Some(w) = encode_frame(&transport_task_id(node_id), &hello, false) {
+            peer.out_buf.extend_from_slice(&w[FRAME_HDR_LEN..]);
+        }
+        //the connection is usually in progress for non-blocking socket, it is completed by SOCK_EVENT_CONNECT
+        sock.set_connect_timeout(RSM_RECONNECT_INTERVAL_USEC/1000);
+        match sock.connect(&peer.stats.addr) {
+            errcode::RESULT_SUCCESS=>Self::set_peer_connected(peer),
+            errcode::ERROR_WOULD_BLOCK=>(),
+            _=>self.disconnect_peer(node_id, true),
+        }
+    }
+
+    fn set_peer_connected(peer:&mut peer_t) {
+        peer.stats.state = E_PEER_STATE::PEER_CONNECTED;
+        peer.stats.connect_count+=1;
+        Self::flush_peer(peer);
+    }
+
+    fn disconnect_peer(&mut self,node_id:rsm_node_id_t,close:bool) {
+        let peer = match get_peer(node_id) {
+            None=>return,
+            Some(p)=>p,
+        };
+        if peer.sock_id>=0 {
+            self.conns.remove(&peer.sock_id);
+            if close {
+                TcpSocket::get_socket_by_id(peer.sock_id).close();
+            }
+        }
+        if peer.stats.state==E_PEER_STATE::PEER_CONNECTED {
+            peer.stats.disconnect_count+=1;
+        }
+        peer.sock_id = -1;
+        peer.stats.state = E_PEER_STATE::PEER_DISCONNECTED;
+        peer.out_buf.clear();
+    }
+
+    fn flush_peer(peer:&mut peer_t) {
+        if peer.sock_id<0 || peer.stats.state!=E_PEER_STATE::PEER_CONNECTED {
+            return
+        }
+        let mut sock = TcpSocket::get_socket_by_id(peer.sock_id);
+        while peer.out_buf.len()>0 {
+            let len = match sock.send(&peer.out_buf) {
+                Err(_)=>break,
+                Ok(l)=>l,
+            };
+            if len==0 {
+                break
+            }
+            peer.out_buf.drain(..len);
+            peer.stats.sent_bytes+=len as u64;
+        }
+    }
+
+    fn send_to_peer(&mut self,wire:&[u8]) {
+        if wire.len()<2*FRAME_HDR_LEN {
+            return
+        }
+        let node_id = u32::from_be_bytes([wire[0],wire[1],wire[2],wire[3]]);
+        let peer = match get_peer(node_id) {
+            None=>return,
+            Some(p)=>p,
+        };
+        match self.proto {
+            E_RSM_TRANSPORT_PROTO::TRANSPORT_UDP=> {
+                if self.sock_id<0 {
+                    peer.stats.drop_msg+=1;
+                    return
+                }
+                match UdpSocket::get_socket_by_id(self.sock_id).send_to(&peer.stats.addr, &wire[2*FRAME_HDR_LEN..]) {
+                    Ok(l)=> {
+                        peer.stats.sent_msg+=1;
+                        peer.stats.sent_bytes+=l as u64;
+                    },
+                    Err(_)=>peer.stats.drop_msg+=1,
+                }
+            },
+            E_RSM_TRANSPORT_PROTO::TRANSPORT_TCP=> {
+                if peer.stats.state==E_PEER_STATE::PEER_DISCONNECTED || peer.out_buf.len()+wire.len()>MAX_PEER_OUT_BUF {
+                    peer.stats.drop_msg+=1;
+                    return
+                }
+                peer.out_buf.extend_from_slice(&wire[FRAME_HDR_LEN..]);
+                peer.stats.sent_msg+=1;
+                Self::flush_peer(peer);
+            },
+        }
+    }
+
+    ///deliver a frame received from the peer node src_node, the frame with a sender of another node is dropped,
+    /// except the zero sender of a message sent out of the rsm task context
+    fn deliver_frame(&mut self,buf:&[u8],src_node:rsm_node_id_t) {
+        let local_node = match &self.local {
+            None=>return,
+            Some(l)=>l.node_id,
+        };
+        let (dst,msg,priority) = match decode_frame(buf) {
+            None=> {
+                add_invalid_frame();
+                return
+            },
+            Some(f)=>f,
+        };
+        if dst.node_id!=local_node || (msg.sender.node_id!=src_node && msg.sender!=rsm_component_t::new_zero()) {
+            add_invalid_frame();
+            return
+        }
+        if let Some(peer) = get_peer(src_node) {
+            peer.stats.recv_msg+=1;
+            peer.stats.recv_bytes+=buf.len() as u64;
+        }
+        //hello frame of a tcp connection
+        if msg.msg_id==RSM_INVALID_MESSAGE_ID {
+            return
+        }
+        if priority {
+            rsm_sched::send_asyn_priority_msg(&dst, msg);
+        } else {
+            rsm_sched::send_asyn_msg(&dst, msg);
+        }
+    }
+
+    fn recv_udp(&mut self) {
+        let mut sock = UdpSocket::get_socket_by_id(self.sock_id);
+        loop {
+            let (len,from) = match sock.recv_from(&mut self.recv_buf) {
+                Err(_)=>break,
+                Ok(r)=>r,
+            };
+            let src_node = match get_peer_by_addr(&from) {
+                None=> {
+                    add_invalid_frame();
+                    continue
+                },
+                Some(n)=>n,
+            };
+            let buf = self.recv_buf[0..len].to_vec();
+            self.deliver_frame(&buf,src_node);
+        }
+    }
+
+    //get the peer node of an incoming connection, the first frame binds the connection to its sender node,
+    //if the connection is from the address of that node
+    fn get_conn_node(&mut self,sock_id:i32,sock:&TcpSocket,frame:&[u8])->Option<rsm_node_id_t> {
+        if let Some(n) = self.rx_nodes.get(&sock_id) {
+            return Some(*n)
+        }
+        let sender = match decode_frame(frame) {
+            None=>return None,
+            Some((_,msg,_))=>msg.sender.node_id,
+        };
+        let peer_ip = match get_peer(sender) {
+            None=>return None,
+            Some(p)=>p.stats.addr.ip(),
+        };
+        match sock.get_peer_addr() {
+            Some(a) if a.ip()==peer_ip=>(),
+            _=>return None,
+        }
+        self.rx_nodes.insert(sock_id, sender);
+        return Some(sender)
+    }
+
+    //return false if the connection is closed by the peer or a invalid frame is received
+    fn recv_tcp(&mut self,sock_id:i32)->bool {
+        let mut sock = TcpSocket::get_socket_by_id(sock_id);
+        let mut rx = self.rx_bufs.remove(&sock_id).unwrap_or_default();
+        let mut alive = true;
+        loop {
+            match sock.recv(&mut self.recv_buf) {
+                Err(_)=>break,
+                Ok(0)=> {
+                    alive = false;
+                    break
+                },
+                Ok(l)=>rx.extend_from_slice(&self.recv_buf[0..l]),
+            }
+        }
+        let mut pos = 0;
+        while rx.len()-pos>=FRAME_HDR_LEN {
+            let flen = u32::from_be_bytes([rx[pos],rx[pos+1],rx[pos+2],rx[pos+3]]) as usize;
+            if flen>MAX_FRAME_LEN {
+                add_invalid_frame();
+                alive = false;
+                break
+            }
+            if rx.len()-pos<FRAME_HDR_LEN+flen {
+                break
+            }
+            let frame = rx[pos+FRAME_HDR_LEN..pos+FRAME_HDR_LEN+flen].to_vec();
+            pos+=FRAME_HDR_LEN+flen;
+            let src_node = match self.get_conn_node(sock_id, &sock, &frame) {
+                None=> {
+                    add_invalid_frame();
+                    alive = false;
+                    break
+                },
+                Some(n)=>n,
+            };
+            self.deliver_frame(&frame,src_node);
+        }
+        if !alive {
+            return false
+        }
+        rx.drain(..pos);
+        self.rx_bufs.insert(sock_id, rx);
+        return true
+    }
+}
+
+impl Runnable for transport_task_t {
+    fn on_init(&mut self,_cid:&rsm_component_t) {
+        self.open_local_socket();
+        if self.proto==E_RSM_TRANSPORT_PROTO::TRANSPORT_TCP {
+            for id in get_peer_ids() {
+                self.connect_peer(id);
+            }
+        }
+        if let Some(t) = rsm_timer::set_timer(RSM_TRANSPORT_TICK_MSEC, 0, 0) {
+            self.timer_id = t;
+        }
+        self.inited = true;
+    }
+
+    fn on_timer(&mut self,_cid:&rsm_component_t,_timer_id:rsm_timer_id_t,_timer_data:usize) {
+        if self.sock_id<0 {
+            self.open_local_socket();
+        }
+        if self.proto!=E_RSM_TRANSPORT_PROTO::TRANSPORT_TCP {
+            return
+        }
+        let now = common::get_now_usec64();
+        for id in get_peer_ids() {
+            let peer = match get_peer(id) {
+                None=>continue,
+                Some(p)=>p,
+            };
+            let expired = now.saturating_sub(peer.connect_at)>=RSM_RECONNECT_INTERVAL_USEC;
+            match peer.stats.state {
+                E_PEER_STATE::PEER_CONNECTED=>Self::flush_peer(peer),
+                E_PEER_STATE::PEER_CONNECTING=> {
+                    //the connect timeout is reported by SOCK_EVENT_CONNECT, this is only a fallback
+                    if now.saturating_sub(peer.connect_at)>=2*RSM_RECONNECT_INTERVAL_USEC {
+                        self.disconnect_peer(id, true);
+                        self.connect_peer(id);
+                    }
+                },
+                E_PEER_STATE::PEER_DISCONNECTED=> {
+                    if expired {
+                        self.connect_peer(id);
+                    }
+                },
+            }
+        }
+    }
+
+    fn on_socket_event(&mut self,_cid:&rsm_component_t,event:rsm_socket_event_t) {
+        let sid = event.socket_id;
+        //the socket has been closed by socket pool
+        if (event.event & (SOCK_EVENT_CLOSE | SOCK_EVENT_ERR))!=0 {
+            if sid==self.sock_id {
+                self.sock_id = -1;
+            } else if let Some(node_id) = self.conns.get(&sid).cloned() {
+                self.disconnect_peer(node_id, false);
+            } else {
+                self.rx_bufs.remove(&sid);
+                self.rx_nodes.remove(&sid);
+            }
+            return
+        }
+        if (event.event & SOCK_EVENT_NEW)!=0 {
+            self.rx_bufs.insert(sid, Vec::new());
+        }
+        if sid==self.sock_id {
+            if self.proto==E_RSM_TRANSPORT_PROTO::TRANSPORT_UDP {
+                self.recv_udp();
+            }
+            return
+        }
+        if let Some(node_id) = self.conns.get(&sid).cloned() {
+            if (event.event & SOCK_EVENT_CONNECT)!=0 {
+                if event.result!=errcode::RESULT_SUCCESS {
+                    self.disconnect_peer(node_id, true);
+                    return
+                }
+                if let Some(peer) = get_peer(node_id) {
+                    Self::set_peer_connected(peer);
+                }
+            }
+            //the peer never sends on the outgoing connection, except closing it
+            if (event.event & SOCK_EVENT_READ)!=0 {
+                let mut sock = TcpSocket::get_socket_by_id(sid);
+                if let Ok(0) = sock.recv(&mut self.recv_buf) {
+                    self.disconnect_peer(node_id, true);
+                    return
+                }
+            }
+            //the send buffer was full, continue to send the pending frames
+            if (event.event & SOCK_EVENT_WRITE)!=0 {
+                if let Some(peer) = get_peer(node_id) {
+                    Self::flush_peer(peer);
+                }
+            }
+            return
+        }
+        if !self.recv_tcp(sid) {
+            self.rx_nodes.remove(&sid);
+            TcpSocket::get_socket_by_id(sid).close();
+        }
+    }
+
+    fn on_message(&mut self,_cid:&rsm_component_t,msg_id:rsm_message_id_t,msg:&rsm_message_t) {
+        if msg_id!=RSM_MSG_ID_TRANSPORT {
+            return
+        }
+        if let Some(wire) = msg.get_raw_body() {
+            self.send_to_peer(wire);
+        }
+    }
+
+    fn is_inited(&self)->bool {
+        self.inited
+    }
+
+    fn on_close(&mut self,_cid:&rsm_component_t) {
+        for id in get_peer_ids() {
+            self.disconnect_peer(id, true);
+        }
+        self.sock_id = -1;
+        self.rx_bufs.clear();
+        self.rx_nodes.clear();
+    }
+}
+
+///list the peer nodes and their statistics
+fn process_transport_oam(op:E_RSM_OAM_OP,_url:&String,_param:&String)->oam::oam_cmd_resp_t {
+    let mut tResp = oam::oam_cmd_resp_t::new(errcode::RESULT_SUCCESS,&String::default());
+    if op!=E_RSM_OAM_OP::CLI_OP_SHOW {
+        tResp.RetCode = errcode::ERROR_NOT_SUPPORT;
+        return tResp
+    }
+    let tp = match unsafe {&gTransport} {
+        None=> {
+            tResp.RetCode = errcode::ERROR_NOT_INITIALIZED;
+            return tResp
+        },
+        Some(t)=>t,
+    };
+    let hdr = vec!["node_id".to_string(),"addr".to_string(),"state".to_string(),"sent_msg".to_string(),"sent_bytes".to_string(),
+        "recv_msg".to_string(),"recv_bytes".to_string(),"drop_msg".to_string(),"connect".to_string(),"disconnect".to_string()];
+    tResp.set_row_hdr(hdr);
+    let local = vec![tp.local.node_id.to_string(),tp.local.addr.to_string(),format!("local,{:?},invalid_frame={}",tp.proto,tp.invalid_frame),
+        String::default(),String::default(),String::default(),String::default(),String::default(),String::default(),String::default()];
+    tResp.add_row(&local);
+    for p in get_peer_stats() {
+        let row = vec![p.node_id.to_string(),p.addr.to_string(),format!("{:?}",p.state),p.sent_msg.to_string(),p.sent_bytes.to_string(),
+            p.recv_msg.to_string(),p.recv_bytes.to_string(),p.drop_msg.to_string(),p.connect_count.to_string(),p.disconnect_count.to_string()];
+        tResp.add_row(&row);
+    }
+    return tResp
+}
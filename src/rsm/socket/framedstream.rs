@@ -0,0 +1,270 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+use std::cmp;
+use crate::common::errcode;
+use crate::common::ringbuf::{ring_buffer_t,fn_check_msg_interity};
+use super::*;
+
+//the max length read from or written to the socket at a time
+const FRAME_IO_CHUNK_SIZE:usize=16384;
+
+///STREAM_FRAMER, how the frames are delimited in a byte stream
+#[derive(Clone,Debug)]
+pub enum STREAM_FRAMER {
+    ///a big endian length header of 1, 2 or 4 bytes followed by the payload, the length doesn't include the header
+    FRAMER_LENGTH_PREFIXED(usize),
+    ///frames are terminated by the delimiter, e.g. b"\r\n"
+    FRAMER_DELIMITER(Vec<u8>),
+    ///the callback returns the length of the complete frame at the head of the stream, or an error if it is not complete,
+    /// the frame is delivered and sent as is
+    FRAMER_CUSTOM(fn_check_msg_interity),
+}
+
+///FramedStream, message reassembly over a stream socket,
+/// the received data is buffered until a whole frame is available, the data which can't be sent at once is buffered and flushed later.
+/// the application keeps a FramedStream for each connection, a frame must fit in the receive buffer
+pub struct FramedStream {
+    sock:TcpSocket,
+    framer:STREAM_FRAMER,
+    recv_buf:ring_buffer_t,
+    send_buf:ring_buffer_t,
+    //the offset from which the delimiter is searched
+    scan_offset:usize,
+    //the stream is closed by the peer or broken, reported after the frames received before it
+    recv_error:errcode::RESULT,
+}
+
+impl FramedStream {
+    ///create a framed stream on a connected socket, e.g. the socket_id of SOCK_EVENT_NEW
+    pub fn new(sock_id:i32,framer:STREAM_FRAMER,recv_buf_size:usize,send_buf_size:usize)->Result<Self,errcode::RESULT> {
+        let valid = match &framer {
+            STREAM_FRAMER::FRAMER_LENGTH_PREFIXED(l)=>*l==1 || *l==2 || *l==4,
+            STREAM_FRAMER::FRAMER_DELIMITER(d)=>d.len()>0,
+            STREAM_FRAMER::FRAMER_CUSTOM(_)=>true,
+        };
+        if !valid || recv_buf_size==0 || send_buf_size==0 {
+            return Err(errcode::ERROR_INVALID_PARAM)
+        }
+        let recv_buf = match ring_buffer_t::new(recv_buf_size, None) {
+            None=>return Err(errcode::ERROR_OUTOF_MEM),
+            Some(r)=>r,
+        };
+        let send_buf = match ring_buffer_t::new(send_buf_size, None) {
+            None=>return Err(errcode::ERROR_OUTOF_MEM),
+            Some(r)=>r,
+        };
+
+        return Ok(Self {
+            sock:TcpSocket::get_socket_by_id(sock_id),
+            framer:framer,
+            recv_buf:recv_buf,
+            send_buf:send_buf,
+            scan_offset:0,
+            recv_error:errcode::RESULT_SUCCESS,
+        })
+    }
+
+    pub fn get_socket_id(&self)->i32 {
+        self.sock.get_socket_id()
+    }
+
+    ///read the socket on SOCK_EVENT_READ until no data is left, return the whole frames received,
+    /// the length header or the delimiter is removed. ERROR_MSG_TOO_LONG is returned if a frame exceeds the receive buffer,
+    /// ERROR_LINK_BROKEN if the stream is closed by the peer, or the error of recv. if the frames are received before the stream is closed,
+    /// the frames are returned first, and the error is returned by get_recv_error and the next call
+    pub fn recv_frames(&mut self)->Result<Vec<Vec<u8>>,errcode::RESULT> {
+        if self.recv_error!=errcode::RESULT_SUCCESS {
+            return Err(self.recv_error)
+        }
+        let mut frames = Vec::new();
+        let mut buf = [0u8;FRAME_IO_CHUNK_SIZE];
+        loop {
+            let ret = self.pull_frames(&mut frames);
+            if ret!=errcode::RESULT_SUCCESS {
+                return Err(ret)
+            }
+            let max_len = cmp::min(buf.len(), self.recv_buf.buffer_available());
+            if max_len==0 {
+                return Err(errcode::ERROR_MSG_TOO_LONG)
+            }
+            let len = match self.sock.recv(&mut buf[0..max_len]) {
+                Ok(0)=> {
+                    self.recv_error = errcode::ERROR_LINK_BROKEN;
+                    break;
+                },
+                Ok(l)=>l,
+                Err(errcode::ERROR_WOULD_BLOCK)=>break,
+                Err(e)=> {
+                    self.recv_error = e;
+                    break;
+                },
+            };
+            self.recv_buf.put_data(&buf[0..len]);
+        }
+        if frames.is_empty() && self.recv_error!=errcode::RESULT_SUCCESS {
+            return Err(self.recv_error)
+        }
+        return Ok(frames)
+    }
+
+    ///the error which stopped the receiving, RESULT_SUCCESS if the stream is still readable
+    pub fn get_recv_error(&self)->errcode::RESULT {
+        self.recv_error
+    }
+
+    //move the whole frames from the receive buffer
+    fn pull_frames(&mut self,frames:&mut Vec<Vec<u8>>)->errcode::RESULT {
+        loop {
+            let (hdr_len,payload_len,tail_len) = match self.next_frame() {
+                None=>return errcode::RESULT_SUCCESS,
+                Some(f)=>f,
+            };
+            let frame_len = hdr_len+payload_len+tail_len;
+            let mut frame = vec![0u8;frame_len];
+            if let Err(e) = self.recv_buf.pull_data(frame_len, &mut frame) {
+                return e
+            }
+            self.scan_offset = 0;
+            frame.truncate(hdr_len+payload_len);
+            frames.push(frame.split_off(hdr_len));
+        }
+    }
+
+    //return the header length, payload length and delimiter length of the first frame if it is complete
+    fn next_frame(&mut self)->Option<(usize,usize,usize)> {
+        let total = self.recv_buf.len();
+        match &self.framer {
+            STREAM_FRAMER::FRAMER_LENGTH_PREFIXED(hdr_len)=> {
+                let mut hdr = [0u8;4];
+                if total<*hdr_len || self.recv_buf.peek_data(0, *hdr_len, &mut hdr).is_err() {
+                    return None
+                }
+                let mut payload_len:usize = 0;
+                for b in &hdr[0..*hdr_len] {
+                    payload_len = (payload_len<<8) | (*b as usize);
+                }
+                if total<hdr_len+payload_len {
+                    return None
+                }
+                return Some((*hdr_len,payload_len,0))
+            },
+            STREAM_FRAMER::FRAMER_DELIMITER(delimiter)=> {
+                if total<delimiter.len() || total<=self.scan_offset {
+                    return None
+                }
+                let mut data = vec![0u8;total-self.scan_offset];
+                if self.recv_buf.peek_data(self.scan_offset, data.len(), &mut data).is_err() {
+                    return None
+                }
+                return match data.windows(delimiter.len()).position(|w| w==delimiter.as_slice()) {
+                    None=> {
+                        //the delimiter may be split, search the last bytes again
+                        self.scan_offset = total+1-delimiter.len();
+                        None
+                    },
+                    Some(pos)=>Some((0,self.scan_offset+pos,delimiter.len())),
+                }
+            },
+            STREAM_FRAMER::FRAMER_CUSTOM(fn_check)=> {
+                let mut hdr = [0u8;8];
+                let l = match self.recv_buf.peek_data(0, hdr.len(), &mut hdr) {
+                    Err(_)=>return None,
+                    Ok(l)=>l,
+                };
+                return match fn_check(&hdr[0..l], total, total) {
+                    Ok(len) if len>0 && len<=total=>Some((0,len,0)),
+                    _=>None,
+                }
+            },
+        }
+    }
+
+    ///send a frame, the length header or the delimiter is added by the framer,
+    /// the data which can't be sent at once is buffered, and sent by flush on SOCK_EVENT_WRITE.
+    /// ERROR_OUTOF_MEM is returned and nothing is sent if the send buffer can't hold the frame
+    pub fn send_frame(&mut self,payload:&[u8])->errcode::RESULT {
+        let frame = match self.encode_frame(payload) {
+            Err(e)=>return e,
+            Ok(f)=>f,
+        };
+        if frame.len()>self.send_buf.buffer_available() {
+            return errcode::ERROR_OUTOF_MEM
+        }
+        //keep the order of the buffered data
+        if self.send_buf.len()>0 {
+            self.send_buf.put_data(&frame);
+            return match self.flush() {
+                Err(e)=>e,
+                Ok(_)=>errcode::RESULT_SUCCESS,
+            }
+        }
+        let len = match self.sock.send(&frame) {
+            Err(e)=>return e,
+            Ok(l)=>l,
+        };
+        if len<frame.len() {
+            self.send_buf.put_data(&frame[len..]);
+        }
+        return errcode::RESULT_SUCCESS
+    }
+
+    fn encode_frame(&self,payload:&[u8])->Result<Vec<u8>,errcode::RESULT> {
+        match &self.framer {
+            STREAM_FRAMER::FRAMER_LENGTH_PREFIXED(hdr_len)=> {
+                if (*hdr_len<4 && payload.len()>=(1usize<<(hdr_len*8))) || payload.len()>u32::MAX as usize {
+                    return Err(errcode::ERROR_MSG_TOO_LONG)
+                }
+                let mut frame = Vec::with_capacity(hdr_len+payload.len());
+                frame.extend_from_slice(&(payload.len() as u32).to_be_bytes()[4-hdr_len..]);
+                frame.extend_from_slice(payload);
+                return Ok(frame)
+            },
+            STREAM_FRAMER::FRAMER_DELIMITER(delimiter)=> {
+                let mut frame = Vec::with_capacity(payload.len()+delimiter.len());
+                frame.extend_from_slice(payload);
+                frame.extend_from_slice(delimiter);
+                return Ok(frame)
+            },
+            STREAM_FRAMER::FRAMER_CUSTOM(_)=>return Ok(payload.to_vec()),
+        }
+    }
+
+    ///send the buffered data, return the length left in the send buffer,
+    /// the owner receives a SOCK_EVENT_WRITE again if the socket send buffer is full
+    pub fn flush(&mut self)->Result<usize,errcode::RESULT> {
+        let mut buf = [0u8;FRAME_IO_CHUNK_SIZE];
+        if self.send_buf.len()==0 {
+            //the encrypted data left in a TLS session
+            let _ = self.sock.send(&[]);
+        }
+        while self.send_buf.len()>0 {
+            let len = match self.send_buf.peek_data(0, buf.len(), &mut buf) {
+                Err(e)=>return Err(e),
+                Ok(l)=>l,
+            };
+            let sent = match self.sock.send(&buf[0..len]) {
+                Err(e)=>return Err(e),
+                Ok(l)=>l,
+            };
+            if sent>0 {
+                let _ = self.send_buf.pull_data(sent, &mut buf);
+            }
+            if sent<len {
+                break;
+            }
+        }
+        return Ok(self.send_buf.len())
+    }
+
+    ///the length of the data waiting to be sent
+    pub fn pending_len(&self)->usize {
+        self.send_buf.len()
+    }
+
+    pub fn close(&mut self)->errcode::RESULT {
+        self.sock.close()
+    }
+}
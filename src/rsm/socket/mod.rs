@@ -20,6 +20,16 @@ pub mod socketpool;
 pub mod udpsocket;
 pub mod tcplistener;
 pub mod tcpsocket;
+pub mod framedstream;
+#[cfg(unix)]
+pub mod unixsocket;
+#[cfg(feature="tls")]
+pub mod tlssocket;
+
+pub use crate::net_ext::UnixSocketAddr;
+pub use framedstream::{FramedStream,STREAM_FRAMER};
+#[cfg(feature="tls")]
+pub use tlssocket::{TlsServerConfig,TlsClientConfig};
 
 
 
@@ -30,6 +40,7 @@ pub enum SOCKET_ADDRESS_FAMILY {
     SOCKET_INET = 1,
     SOCKET_INET6 = 2,
     SOCKET_RAW_PACKET=4,
+    SOCKET_UNIX=8,
 }
 
 #[derive(Clone,Debug,PartialEq,Copy,Serialize,Deserialize)]
@@ -39,6 +50,9 @@ pub enum SOCKET_TYPE {
     PROTO_STREAM = 2,
 }
 
+///default timeout of an asynchronous tcp connect
+pub const SOCK_DEF_CONNECT_TIMEOUT_MSEC:u64=5000;
+
 #[derive(Clone,Debug,PartialEq,Copy)]
 pub enum SOCKET_STATE {
     SOCK_INIT = 0,
@@ -72,6 +86,47 @@ pub struct Socket {
     lb_policy:SOCKET_LB_POLICY,
     local_addr:SocketAddr,
     peer_addr:SocketAddr,
+    //addresses of unix domain socket
+    unix_local:Option<UnixSocketAddr>,
+    unix_peer:Option<UnixSocketAddr>,
+    stats:SocketStats,
+}
+
+///traffic counters of a socket, the errors don't include the operations which would block
+#[derive(Clone,Debug,Default,PartialEq,Serialize,Deserialize)]
+pub struct SocketStats {
+    pub tx_bytes:u64,
+    pub tx_packets:u64,
+    pub rx_bytes:u64,
+    pub rx_packets:u64,
+    pub tx_errors:u64,
+    pub rx_errors:u64,
+}
+
+impl SocketStats {
+    pub(crate) fn update_tx(&mut self,ret:&Result<usize,errcode::RESULT>) {
+        match ret {
+            Ok(0)=>(),
+            Ok(l)=> {
+                self.tx_bytes+=*l as u64;
+                self.tx_packets+=1;
+            },
+            Err(errcode::ERROR_WOULD_BLOCK)=>(),
+            Err(_)=>self.tx_errors+=1,
+        }
+    }
+
+    pub(crate) fn update_rx(&mut self,ret:Result<usize,errcode::RESULT>) {
+        match ret {
+            Ok(0)=>(),
+            Ok(l)=> {
+                self.rx_bytes+=l as u64;
+                self.rx_packets+=1;
+            },
+            Err(errcode::ERROR_WOULD_BLOCK)=>(),
+            Err(_)=>self.rx_errors+=1,
+        }
+    }
 }
 pub trait AsSocket {
     fn as_socket(&mut self)->Socket;
@@ -92,6 +147,33 @@ pub struct TcpSocket {
     sck_idx:i32,
 }
 
+///Unix domain socket listener, stream connections are accepted and dispatched like TcpListener
+#[cfg(unix)]
+#[derive(Clone,Debug)]
+pub struct UnixListener {
+    sck_idx:i32,
+}
+
+#[cfg(unix)]
+#[derive(Clone,Debug)]
+pub struct UnixStream {
+    sck_idx:i32,
+}
+
+#[cfg(unix)]
+#[derive(Clone,Debug)]
+pub struct UnixDatagram {
+    sck_idx:i32,
+}
+
+///credential of the peer process of a unix domain socket, pid is 0 if the os doesn't provide it
+#[derive(Clone,Debug,PartialEq)]
+pub struct UnixCredential {
+    pub pid:i32,
+    pub uid:u32,
+    pub gid:u32,
+}
+
 #[derive(Clone,Debug)]
 pub struct RawSocket {
     sck_idx:i32,
@@ -129,6 +211,7 @@ fn socket(sock_af:SOCKET_ADDRESS_FAMILY,sock_type:SOCKET_TYPE,proto:u8)->Result<
         SOCKET_ADDRESS_FAMILY::SOCKET_INET=>libc::AF_INET,
         SOCKET_ADDRESS_FAMILY::SOCKET_INET6=>libc::AF_INET6,
         SOCKET_ADDRESS_FAMILY::SOCKET_RAW_PACKET=>libc::AF_PACKET,
+        SOCKET_ADDRESS_FAMILY::SOCKET_UNIX=>libc::AF_UNIX,
     };
 
     let stype = match sock_type {
@@ -72,6 +72,14 @@ impl Poll {
     fn post_event(&self,_fd:RawFdType,_key:usize,_event:rsm::SOCKET_EVENT) {
     }
 
+    ///change the events of a registered socket, a newly added event is reported at once if the socket is already ready for it
+    pub fn modify(&mut self,fd:RawFdType,key:usize,event:rsm::SOCKET_EVENT)->errcode::RESULT {
+        self.lock.lock();
+        let ret = poll_mod_socket(self.poll_fd, fd, key, event);
+        self.lock.unlock();
+        return ret
+    }
+
     pub fn deregister(&mut self,fd:RawFdType)->errcode::RESULT {
         self.lock.lock();
         let ret = poll_del_socket(self.poll_fd, fd);
@@ -167,6 +175,25 @@ fn poll_add_socket(inst:RawFdType,fd:RawFdType,key:usize,events:u32)->errcode::R
     errcode::RESULT_SUCCESS
 }
 
+#[cfg(windows)]
+fn poll_mod_socket(inst:RawFdType,fd:RawFdType,key:usize,events:u32)->errcode::RESULT {
+    //enable an already registered socket again replaces the event filter
+    return poll_add_socket(inst, fd, key, events)
+}
+
+#[cfg(unix)]
+fn poll_mod_socket(inst:RawFdType,fd:RawFdType,key:usize,events:u32)->errcode::RESULT {
+    let mut ev = unsafe { std::mem::zeroed::<libc::epoll_event>() };
+    ev.events = rsmev_to_osev(events) |  libc::EPOLLET as u32;
+    ev.u64 = key as u64;
+    let ret = unsafe { libc::epoll_ctl(inst,libc::EPOLL_CTL_MOD, fd,
+        &mut ev as *mut libc::epoll_event) };
+    if ret!=0 {
+        return errcode::ERROR_OS_CALL_FAILED
+    }
+    errcode::RESULT_SUCCESS
+}
+
 #[cfg(windows)]
 fn poll_del_socket(_inst:RawFdType,_fd:RawFdType)->errcode::RESULT {
     errcode::RESULT_SUCCESS
@@ -222,6 +249,7 @@ fn poll_wait(inst:RawFdType,wait_msec:u32)->Option<Vec<socket_event_t>> {
             socket_id:ol.lpCompletionKey as i32,
             sock_type:SOCKET_TYPE::PROTO_DGRAM,
             event:osev_to_rsmev(os_ev), 
+            result:errcode::RESULT_SUCCESS,
         };
         evs.push(sock_ev);
     }
@@ -247,6 +275,7 @@ fn poll_wait(fd:RawFdType,wait_msec:u32)->Option<Vec<socket_event_t>> {
             socket_id:ev.u64 as i32,
             sock_type:SOCKET_TYPE::PROTO_DGRAM,
             event:osev_to_rsmev(ev.events as i32),
+            result:errcode::RESULT_SUCCESS,
         };
         evs.push(sock_ev);
     }
@@ -29,6 +29,9 @@ impl Socket {
             lb_policy:SOCKET_LB_POLICY::SOCK_LB_ALL_INSTANCE,
             local_addr:SocketAddr::new(IpAddr::from([0,0,0,0]), 0),
             peer_addr:SocketAddr::new(IpAddr::from([0,0,0,0]), 0),
+            unix_local:None,
+            unix_peer:None,
+            stats:SocketStats::default(),
         };
         sock.set_non_blocking();
 
@@ -50,7 +53,8 @@ impl Socket {
         }
         let ret = rawsocket::bind(self.get_raw_fd(), addr);
         if ret == errcode::RESULT_SUCCESS {
-            self.state=SOCKET_STATE::SOCK_BIND
+            self.state=SOCKET_STATE::SOCK_BIND;
+            self.local_addr = rawsocket::get_local_addr(self.os_fd).unwrap_or(*addr);
         }
         return ret
     }
@@ -74,53 +78,96 @@ impl Socket {
         if self.state!=SOCKET_STATE::SOCK_LISTENING {
             return Err(errcode::ERROR_INVALID_STATE)
         }
-        let (fd,peer) = match rawsocket::accept(self.get_raw_fd()) {
-            Ok((f,p))=>(f,p),
-            Err(e)=>return Err(e),
+        let (fd,peer,unix_peer) = if self.sock_af==SOCKET_ADDRESS_FAMILY::SOCKET_UNIX {
+            match self.accept_unix() {
+                Ok((f,p))=>(f,self.peer_addr,Some(p)),
+                Err(e)=>return Err(e),
+            }
+        } else {
+            match rawsocket::accept(self.get_raw_fd()) {
+                Ok((f,p))=>(f,p,None),
+                Err(e)=>return Err(e),
+            }
         };
         let mut new_sock = self.clone();
         new_sock.os_fd=fd;
         new_sock.sock_id=new_idx;
         new_sock.tcp_server=false;
         new_sock.peer_addr = peer;
+        new_sock.unix_peer = unix_peer;
+        if self.sock_af!=SOCKET_ADDRESS_FAMILY::SOCKET_UNIX {
+            new_sock.local_addr = rawsocket::get_local_addr(fd).unwrap_or(self.local_addr);
+        }
+        new_sock.stats = SocketStats::default();
         new_sock.state=SOCKET_STATE::SOCK_CONNECTED;
         new_sock.set_non_blocking();
        Ok(new_sock)
     }
 
+    #[cfg(windows)]
+    fn accept_unix(&self)->Result<(net_ext::RawFdType,UnixSocketAddr),errcode::RESULT> {
+        Err(errcode::ERROR_NOT_SUPPORT)
+    }
+
+    ///connect to the dst, return ERROR_WOULD_BLOCK and keep in SOCK_CONNECTING state if the connection is in progress
     pub fn connect(&mut self,dst:&SocketAddr)->errcode::RESULT {
         let state = self.state;
         self.state=SOCKET_STATE::SOCK_CONNECTING;
         let ret=rawsocket::connect(self.os_fd, dst);
         if ret==errcode::RESULT_SUCCESS {
             self.state=SOCKET_STATE::SOCK_CONNECTED;
-        } else {
+        } else if ret!=errcode::ERROR_WOULD_BLOCK {
             self.state = state;
         }
+        if ret==errcode::RESULT_SUCCESS || ret==errcode::ERROR_WOULD_BLOCK {
+            self.peer_addr = *dst;
+            if let Ok(a) = rawsocket::get_local_addr(self.os_fd) {
+                self.local_addr = a;
+            }
+        }
         return ret;
     }
+
+    ///get the pending error of the socket, which is the result of an asynchronous connect
+    pub fn get_socket_error(&self)->errcode::RESULT {
+        return rawsocket::get_socket_error(self.os_fd)
+    }
     pub fn send(&mut self,buf:&[u8])->Result<usize,errcode::RESULT> {
-        return rawsocket::write_fd(self.os_fd, buf, 0);
+        let ret = rawsocket::write_fd(self.os_fd, buf, 0);
+        self.stats.update_tx(&ret);
+        return ret
     }
 
     pub fn send_to(&mut self,dst:&SocketAddr,buf:&[u8])->Result<usize,errcode::RESULT> {
-        return rawsocket::send_to(self.os_fd, buf, 0, dst)
+        let ret = rawsocket::send_to(self.os_fd, buf, 0, dst);
+        self.stats.update_tx(&ret);
+        return ret
     }
 
     pub fn recv(&mut self,buf:&mut [u8])->Result<usize,errcode::RESULT> {
-
-       return rawsocket::read_fd(self.os_fd, buf, 0)
+        let ret = rawsocket::read_fd(self.os_fd, buf, 0);
+        self.stats.update_rx(ret);
+        return ret
     }
 
     pub fn recv_from(&mut self,buf:&mut [u8])->Result<(usize,SocketAddr),errcode::RESULT> {
         let (len,peer) = match rawsocket::recv_from(self.os_fd, buf) {
             Ok((l,a))=>(l,a),
-            Err(_)=>return Err(errcode::ERROR_RECV_MSG),
+            Err(e)=> {
+                let ret = if e.kind()==std::io::ErrorKind::WouldBlock {errcode::ERROR_WOULD_BLOCK} else {errcode::ERROR_RECV_MSG};
+                self.stats.update_rx(Err(ret));
+                return Err(ret)
+            },
         };
+        self.stats.update_rx(Ok(len));
 
         return Ok((len,peer))
     }
 
+    pub fn get_stats(&self)->SocketStats {
+        self.stats.clone()
+    }
+
     pub fn get_raw_fd(&self)->net_ext::RawFdType {
         self.os_fd
     }
@@ -150,6 +197,10 @@ impl Socket {
         self.state
     }
 
+    pub(crate) fn set_sock_state(&mut self,state:SOCKET_STATE) {
+        self.state = state
+    }
+
     pub fn is_tcp_server(&self)->bool {
         return self.tcp_server
     }
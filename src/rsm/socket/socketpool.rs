@@ -3,7 +3,7 @@
 #![allow(non_upper_case_globals)]
 #![allow(dead_code)]
 
-use crate::rsm::{SOCK_EVENT_READ, SOCK_EVENT_CLOSE, SOCK_EVENT_NEW};
+use crate::rsm::{SOCK_EVENT_READ, SOCK_EVENT_WRITE, SOCK_EVENT_CLOSE, SOCK_EVENT_NEW, SOCK_EVENT_CONNECT};
 use crate::{rsm,rsm::rsm_component_t};
 use crate::{net_ext::RawFdType};
 use crate::common::{tsidallocator::TsIdAllocator,spin_lock::spin_lock_t};
@@ -20,9 +20,25 @@ macro_rules! return_error {
 }
 
 const MAX_SOCKET_NUM:usize=131072;
+//events registered for a new socket, the write event is added on demand
+const DEF_SOCK_INTEREST:rsm::SOCKET_EVENT=SOCK_EVENT_READ | SOCK_EVENT_CLOSE;
 pub(crate) struct socket_info_t {
     pub(crate) s:Socket,
     pub(crate) owner:rsm_component_t,
+    //events registered to the poll instance
+    pub(crate) interest:rsm::SOCKET_EVENT,
+    //the write event is removed after it is delivered once, set when a send is not completed
+    pub(crate) write_oneshot:bool,
+    pub(crate) connect_timeout_msec:u64,
+    //timer of an asynchronous connect in progress
+    pub(crate) connect_timer:rsm::rsm_timer_id_t,
+    //closed by OAM, the socket is released by the owner task after the SOCK_EVENT_CLOSE is delivered
+    pub(crate) close_requested:bool,
+    #[cfg(feature="tls")]
+    pub(crate) tls:Option<Box<tlssocket::tls_session_t>>,
+    //TLS configuration of a listener, the accepted connections are wrapped by TLS
+    #[cfg(feature="tls")]
+    pub(crate) tls_server:Option<std::sync::Arc<rustls::ServerConfig>>,
 }
 
 pub(crate) struct SocketPool {
@@ -84,8 +100,19 @@ impl SocketPool {
         let sck_info = socket_info_t {
             s:sock,
             owner:caller,
+            interest:DEF_SOCK_INTEREST,
+            write_oneshot:false,
+            connect_timeout_msec:SOCK_DEF_CONNECT_TIMEOUT_MSEC,
+            connect_timer:rsm::RSM_INVALID_TIMER_ID,
+            close_requested:false,
+            #[cfg(feature="tls")]
+            tls:None,
+            #[cfg(feature="tls")]
+            tls_server:None,
         };
+        self.lock.lock();
         self.sockets[sid as usize]=Some(sck_info);
+        self.lock.unlock();
 
         
         return Ok(sid);
@@ -93,7 +120,279 @@ impl SocketPool {
 
     fn add_poll_registry(&mut self,sock:&Socket)->errcode::RESULT {
         return self.poll_instance.register(sock.get_raw_fd(), sock.get_socket_id() as usize, 
-            SOCK_EVENT_READ | SOCK_EVENT_CLOSE,false)
+            DEF_SOCK_INTEREST,false)
+    }
+
+    ///enable or disable the write event of a socket, a oneshot write event is removed after it is delivered,
+    /// it doesn't replace a write event enabled by the application.
+    /// the interest is updated by the owner task and the socket pool thread, so it is serialized by the pool lock
+    pub(crate) fn set_write_interest(&mut self,sock_idx:i32,enable:bool,oneshot:bool)->errcode::RESULT {
+        self.lock.lock();
+        let ret = self.set_write_interest_locked(sock_idx, enable, oneshot);
+        self.lock.unlock();
+        return ret
+    }
+
+    //remove the oneshot write event after it is delivered, the write event enabled by the application is kept
+    fn clear_oneshot_write(&mut self,sock_idx:i32) {
+        self.lock.lock();
+        if self.get_sock_binding_info(sock_idx).map_or(false, |info| info.write_oneshot) {
+            self.set_write_interest_locked(sock_idx, false, false);
+        }
+        self.lock.unlock();
+    }
+
+    //the pool lock must be held
+    fn set_write_interest_locked(&mut self,sock_idx:i32,enable:bool,oneshot:bool)->errcode::RESULT {
+        let (fd,interest) = match self.get_sock_binding_info(sock_idx) {
+            None=>return errcode::ERROR_NOT_FOUND,
+            Some(info)=> {
+                if enable && oneshot && (info.interest & SOCK_EVENT_WRITE)!=0 {
+                    return errcode::RESULT_SUCCESS
+                }
+                info.write_oneshot = enable && oneshot;
+                let interest = if enable { info.interest | SOCK_EVENT_WRITE } else { info.interest & !SOCK_EVENT_WRITE };
+                if interest==info.interest {
+                    return errcode::RESULT_SUCCESS
+                }
+                (info.s.get_raw_fd(),interest)
+            },
+        };
+        let ret = self.poll_instance.modify(fd, sock_idx as usize, interest);
+        if ret==errcode::RESULT_SUCCESS {
+            if let Some(info) = self.get_sock_binding_info(sock_idx) {
+                info.interest = interest;
+            }
+        }
+        return ret
+    }
+
+    ///start the timer of an asynchronous connect, the completion is reported by the write event,
+    /// if the connect can't be watched, the socket is left in SOCK_BIND state and the error is returned to the owner
+    pub(crate) fn start_connect(&mut self,sock_idx:i32)->errcode::RESULT {
+        //the timer is recorded before the timeout is handled by finish_connect, which is serialized by the lock
+        self.lock.lock();
+        let timeout = match self.get_sock_binding_info(sock_idx) {
+            None=> {
+                self.lock.unlock();
+                return errcode::ERROR_NOT_FOUND
+            },
+            Some(info)=>info.connect_timeout_msec,
+        };
+        let timer_id = match rsm::rsm_timer::set_callback_timer(timeout, 1, sock_idx as usize, on_connect_timeout) {
+            None=> {
+                self.abort_connect(sock_idx);
+                self.lock.unlock();
+                return errcode::ERROR_OUTOF_MEM
+            },
+            Some(id)=>id,
+        };
+        if let Some(info) = self.get_sock_binding_info(sock_idx) {
+            info.connect_timer = timer_id;
+        }
+        let ret = self.set_write_interest_locked(sock_idx, true, true);
+        if ret!=errcode::RESULT_SUCCESS {
+            rsm::rsm_timer::kill_timer_internal(timer_id);
+            self.abort_connect(sock_idx);
+        }
+        self.lock.unlock();
+        return ret
+    }
+
+    //the pool lock must be held
+    fn abort_connect(&mut self,sock_idx:i32) {
+        if let Some(info) = self.get_sock_binding_info(sock_idx) {
+            info.connect_timer = rsm::RSM_INVALID_TIMER_ID;
+            info.s.set_sock_state(SOCKET_STATE::SOCK_BIND);
+        }
+    }
+
+    ///finish an asynchronous connect, called by the socket pool thread on the socket event, or by the timer thread on timeout,
+    /// return the owner if the connect is in progress and not finished by the other thread
+    fn finish_connect(&mut self,sock_idx:i32,result:errcode::RESULT,timer_id:Option<rsm::rsm_timer_id_t>)->Option<rsm_component_t> {
+        self.lock.lock();
+        let (owner,connect_timer) = match self.get_sock_binding_info(sock_idx) {
+            None=>(None,rsm::RSM_INVALID_TIMER_ID),
+            Some(info)=> {
+                let matched = match timer_id {
+                    None=>true,
+                    Some(id)=>id==info.connect_timer,
+                };
+                if !matched || info.s.get_sock_state()!=SOCKET_STATE::SOCK_CONNECTING {
+                    (None,rsm::RSM_INVALID_TIMER_ID)
+                } else {
+                    let state = if result==errcode::RESULT_SUCCESS {SOCKET_STATE::SOCK_CONNECTED} else {SOCKET_STATE::SOCK_BIND};
+                    info.s.set_sock_state(state);
+                    let t = info.connect_timer;
+                    info.connect_timer = rsm::RSM_INVALID_TIMER_ID;
+                    (Some(info.owner.clone()),t)
+                }
+            },
+        };
+        self.lock.unlock();
+        if owner.is_none() {
+            return None
+        }
+        if timer_id.is_none() && connect_timer!=rsm::RSM_INVALID_TIMER_ID {
+            rsm::rsm_timer::kill_timer_internal(connect_timer);
+        }
+        if result==errcode::RESULT_SUCCESS {
+            self.set_write_interest(sock_idx, false, false);
+        } else {
+            self.stop_socket_events(sock_idx);
+        }
+        return owner
+    }
+
+    //a failed tcp socket can't be used again, no more events until it is closed by the owner
+    fn stop_socket_events(&mut self,sock_idx:i32) {
+        self.lock.lock();
+        self.stop_socket_events_locked(sock_idx);
+        self.lock.unlock();
+    }
+
+    //the pool lock must be held
+    fn stop_socket_events_locked(&mut self,sock_idx:i32) {
+        if let Some(info) = self.get_sock_binding_info(sock_idx) {
+            let fd = info.s.get_raw_fd();
+            info.interest = 0;
+            info.write_oneshot = false;
+            self.del_poll_registry(fd);
+        }
+    }
+
+    ///drive the TLS handshake of a socket on the socket event, the read and write events are consumed by the handshake,
+    /// return a SOCK_EVENT_TLS_HANDSHAKE event when the handshake is finished or failed
+    #[cfg(feature="tls")]
+    fn process_tls_event(&mut self,ev:&mut socket_event_t)->Option<socket_event_t> {
+        let info = match self.get_sock_binding_info(ev.socket_id) {
+            None=>return None,
+            Some(i)=>i,
+        };
+        let tls = match &mut info.tls {
+            None=>return None,
+            Some(t)=>t,
+        };
+        //a failed connect is reported by SOCK_EVENT_CONNECT
+        if !tls.is_handshaking() || ((ev.event & SOCK_EVENT_CONNECT)!=0 && ev.result!=errcode::RESULT_SUCCESS) {
+            return None
+        }
+        //the events left after the handshake timed out
+        if tls.handshake_timed_out {
+            ev.event &= !(SOCK_EVENT_READ | SOCK_EVENT_WRITE);
+            return None
+        }
+        if tls.handshake_timer==rsm::RSM_INVALID_TIMER_ID {
+            let ret = self.start_handshake_timer(ev.socket_id);
+            if ret!=errcode::RESULT_SUCCESS {
+                self.stop_socket_events(ev.socket_id);
+                return Some(socket_event_t {
+                    socket_id:ev.socket_id,
+                    sock_type:SOCKET_TYPE::PROTO_STREAM,
+                    event:rsm::SOCK_EVENT_TLS_HANDSHAKE,
+                    result:ret,
+                })
+            }
+        }
+        //a new connection waits for the client hello
+        if (ev.event & SOCK_EVENT_NEW)!=0 {
+            return None
+        }
+        let (s,tls) = match self.get_sock_binding_info(ev.socket_id) {
+            None=>return None,
+            Some(info)=>match &mut info.tls {
+                None=>return None,
+                Some(t)=>(&mut info.s,t),
+            },
+        };
+        let writable = (ev.event & SOCK_EVENT_WRITE)!=0;
+        let state = tlssocket::drive_handshake(s, tls);
+        ev.event &= !(SOCK_EVENT_READ | SOCK_EVENT_WRITE);
+        if writable {
+            self.clear_oneshot_write(ev.socket_id);
+        }
+        let (event,result) = match state {
+            tlssocket::TLS_HANDSHAKE_STATE::TLS_HANDSHAKE_IN_PROGRESS(want_write)=> {
+                if want_write {
+                    self.set_write_interest(ev.socket_id, true, true);
+                }
+                return None
+            },
+            //the data following the handshake may be left in the socket
+            tlssocket::TLS_HANDSHAKE_STATE::TLS_HANDSHAKE_FINISHED=>(rsm::SOCK_EVENT_TLS_HANDSHAKE | SOCK_EVENT_READ,errcode::RESULT_SUCCESS),
+            tlssocket::TLS_HANDSHAKE_STATE::TLS_HANDSHAKE_FAILED(e)=>(rsm::SOCK_EVENT_TLS_HANDSHAKE,e),
+        };
+        //the timeout has been reported by the timer thread
+        if !self.finish_handshake(ev.socket_id, None) {
+            return None
+        }
+        if result!=errcode::RESULT_SUCCESS {
+            self.stop_socket_events(ev.socket_id);
+        }
+        return Some(socket_event_t {
+            socket_id:ev.socket_id,
+            sock_type:SOCKET_TYPE::PROTO_STREAM,
+            event:event,
+            result:result,
+        })
+    }
+
+    //start the handshake timer of a TLS connection, the timeout is the connect timeout of the socket
+    #[cfg(feature="tls")]
+    fn start_handshake_timer(&mut self,sock_idx:i32)->errcode::RESULT {
+        self.lock.lock();
+        let timeout = match self.get_sock_binding_info(sock_idx) {
+            None=> {
+                self.lock.unlock();
+                return errcode::ERROR_NOT_FOUND
+            },
+            Some(info)=>info.connect_timeout_msec,
+        };
+        let timer_id = match rsm::rsm_timer::set_callback_timer(timeout, 1, sock_idx as usize, on_handshake_timeout) {
+            None=> {
+                self.lock.unlock();
+                return errcode::ERROR_OUTOF_MEM
+            },
+            Some(id)=>id,
+        };
+        if let Some(tls) = self.get_sock_binding_info(sock_idx).and_then(|info| info.tls.as_mut()) {
+            tls.handshake_timer = timer_id;
+        }
+        self.lock.unlock();
+        return errcode::RESULT_SUCCESS
+    }
+
+    ///finish the TLS handshake, called by the socket pool thread when the handshake is finished or failed, or by the timer thread on timeout,
+    /// return false if it has been finished by the other thread, a timed out socket receives no more events
+    #[cfg(feature="tls")]
+    fn finish_handshake(&mut self,sock_idx:i32,timer_id:Option<rsm::rsm_timer_id_t>)->bool {
+        self.lock.lock();
+        let handshake_timer = match self.get_sock_binding_info(sock_idx).and_then(|info| info.tls.as_mut()) {
+            None=>rsm::RSM_INVALID_TIMER_ID,
+            Some(tls)=> {
+                let matched = match timer_id {
+                    None=>true,
+                    Some(id)=>id==tls.handshake_timer && tls.is_handshaking(),
+                };
+                if matched {
+                    tls.handshake_timed_out = timer_id.is_some();
+                    std::mem::replace(&mut tls.handshake_timer, rsm::RSM_INVALID_TIMER_ID)
+                } else {
+                    rsm::RSM_INVALID_TIMER_ID
+                }
+            },
+        };
+        if timer_id.is_some() && handshake_timer!=rsm::RSM_INVALID_TIMER_ID {
+            self.stop_socket_events_locked(sock_idx);
+        }
+        self.lock.unlock();
+        if handshake_timer==rsm::RSM_INVALID_TIMER_ID {
+            return false
+        }
+        if timer_id.is_none() {
+            rsm::rsm_timer::kill_timer_internal(handshake_timer);
+        }
+        return true
     }
 
     fn del_poll_registry(&mut self,fd:RawFdType)->errcode::RESULT {
@@ -104,23 +403,96 @@ impl SocketPool {
         if !self.check_socket_caller(sock_idx) {
             return errcode::ERROR_NO_PERMISSION
         }
+        return self.release_socket(sock_idx)
+    }
+
+    ///close a socket regardless of the owner, the owner receives a SOCK_EVENT_CLOSE with the result ERROR_LINK_BROKEN,
+    /// the socket is released under the owner task context after the event is delivered, so it is not freed while the owner is using it
+    pub(crate) fn force_close_socket(&mut self,sock_idx:i32)->errcode::RESULT {
+        self.lock.lock();
+        let res = match self.get_sock_binding_info(sock_idx) {
+            None=>Err(errcode::ERROR_NOT_FOUND),
+            Some(info)=> if info.close_requested {
+                Err(errcode::ERROR_NO_OP)
+            } else {
+                info.close_requested = true;
+                Ok((info.owner.clone(),info.s.get_sock_type()))
+            },
+        };
+        self.lock.unlock();
+        let (owner,sock_type) = match res {
+            Err(e)=>return e,
+            Ok(r)=>r,
+        };
+        let ev = socket_event_t {
+            socket_id:sock_idx,
+            sock_type:sock_type,
+            event:SOCK_EVENT_CLOSE,
+            result:errcode::ERROR_LINK_BROKEN,
+        };
+        send_socket_event(&owner, &ev);
+        return errcode::RESULT_SUCCESS
+    }
+
+    ///release the socket closed by OAM if the owner didn't close it when handling the SOCK_EVENT_CLOSE
+    pub(crate) fn release_force_closed_socket(&mut self,sock_idx:i32)->errcode::RESULT {
+        let requested = match self.get_sock_binding_info(sock_idx) {
+            None=>return errcode::ERROR_NOT_FOUND,
+            Some(info)=>info.close_requested,
+        };
+        if !requested {
+            return errcode::ERROR_NO_OP
+        }
+        return self.close_socket(sock_idx)
+    }
+
+    fn release_socket(&mut self,sock_idx:i32)->errcode::RESULT {
         {
-            let fd = match self.get_socket_by_idx(sock_idx) {
+            let (fd,interest,connect_timer) = match self.get_sock_binding_info(sock_idx) {
                 None=>return errcode::ERROR_NOT_FOUND,
-                Some(s)=>s.get_raw_fd(),
+                Some(info)=>(info.s.get_raw_fd(),info.interest,info.connect_timer),
             };
-            self.del_poll_registry(fd);
-        }
-        let ret = self.sock_ids.release_id(sock_idx);
-        if ret !=errcode::RESULT_SUCCESS {
-            return ret
+            if interest!=0 {
+                self.del_poll_registry(fd);
+            }
+            if connect_timer!=rsm::RSM_INVALID_TIMER_ID {
+                rsm::rsm_timer::kill_timer_internal(connect_timer);
+            }
+            #[cfg(feature="tls")]
+            if let Some(tls) = self.get_sock_binding_info(sock_idx).and_then(|info| info.tls.as_ref()) {
+                if tls.handshake_timer!=rsm::RSM_INVALID_TIMER_ID {
+                    rsm::rsm_timer::kill_timer_internal(tls.handshake_timer);
+                }
+            }
         }
-        if self.sockets[sock_idx as usize].is_none() {
+        self.lock.lock();
+        let info = self.sockets[sock_idx as usize].take();
+        self.lock.unlock();
+        if info.is_none() {
             return errcode::ERROR_NOT_FOUND
         }
-       
-        self.sockets[sock_idx as usize]=None;
-        errcode::RESULT_SUCCESS
+        //close the underlying socket out of the lock, the id can be reused after the slot is cleared
+        drop(info);
+        return self.sock_ids.release_id(sock_idx)
+    }
+
+    ///close all the sockets owned by a task, the caller must be the owner task
+    pub(crate) fn close_sockets_by_owner(&mut self,owner:&rsm_component_t)->usize {
+        let mut socks=Vec::new();
+        for i in 1..self.capacity+1 {
+            if let Some(sinfo)=&self.sockets[i] {
+                if sinfo.owner==*owner {
+                    socks.push(i as i32);
+                }
+            }
+        }
+        let mut count=0;
+        for idx in socks {
+            if self.close_socket(idx)==errcode::RESULT_SUCCESS {
+                count+=1;
+            }
+        }
+        return count
     }
 
     fn check_socket_caller(&self,sock_idx:i32)->bool {
@@ -218,17 +590,38 @@ impl SocketPool {
                  return_error!(Err(e),self.sock_ids,sid);
             },
         };
+        #[cfg(feature="tls")]
+        let tls = match &server.tls_server {
+            None=>None,
+            Some(cfg)=>match tlssocket::tls_session_t::new_server(cfg) {
+                None=>return_error!(Err(errcode::ERROR_INIT_FAILED),self.sock_ids,sid),
+                Some(t)=>Some(Box::new(t)),
+            },
+        };
         add_poll_registry(&client,false);
         let dst= get_lb_task_id(&server.owner, sid,server.s.lb_policy);
+        //the TLS handshake timeout of the accepted connections is set on the listener
+        let connect_timeout_msec = server.connect_timeout_msec;
 
         println!("[socketpool]New Tcp client connection,server_sock={},client_sock={},peer_addr={},raw_fd={},dst_tid={}",
             server_idx,sid,client.get_peer_addr(),client.get_raw_fd(),dst);
         let sck_info = socket_info_t {
             s:client,
             owner:dst,
+            interest:DEF_SOCK_INTEREST,
+            write_oneshot:false,
+            connect_timeout_msec:connect_timeout_msec,
+            connect_timer:rsm::RSM_INVALID_TIMER_ID,
+            close_requested:false,
+            #[cfg(feature="tls")]
+            tls:tls,
+            #[cfg(feature="tls")]
+            tls_server:None,
         };
 
+        self.lock.lock();
         self.sockets[sid as usize]=Some(sck_info);
+        self.lock.unlock();
         
         Ok(sid)
     }
@@ -298,13 +691,130 @@ pub(crate) fn close_socket(idx:i32)->errcode::RESULT {
     return pool.close_socket(idx);
 }
 
+///called by the owner task after a SOCK_EVENT_CLOSE is handled, release the socket if it is closed by OAM
+pub(crate) fn release_force_closed_socket(idx:i32)->errcode::RESULT {
+    let pool = match unsafe {&mut gSocketPool} {
+        None=> return errcode::ERROR_NOT_INITIALIZED,
+        Some(p)=>p,
+    };
+
+    return pool.release_force_closed_socket(idx);
+}
+
+pub(crate) fn close_sockets_by_owner(owner:&rsm_component_t)->usize {
+    let pool = match unsafe {&mut gSocketPool} {
+        None=> return 0,
+        Some(p)=>p,
+    };
+
+    return pool.close_sockets_by_owner(owner);
+}
+
 fn add_poll_registry(sock:&Socket,post_event:bool)->errcode::RESULT {
     let pool = match unsafe {&mut gSocketPool} {
         None=> return errcode::ERROR_NOT_INITIALIZED,
         Some(p)=>p,
     };    
     return pool.poll_instance.register(sock.get_raw_fd(), sock.get_socket_id() as usize, 
-        SOCK_EVENT_READ | SOCK_EVENT_CLOSE,post_event)
+        DEF_SOCK_INTEREST,post_event)
+}
+
+///enable or disable the SOCK_EVENT_WRITE of a socket, the caller must be the owner of the socket
+pub(crate) fn set_write_interest(sock_idx:i32,enable:bool)->errcode::RESULT {
+    let pool = match unsafe {&mut gSocketPool} {
+        None=> return errcode::ERROR_NOT_INITIALIZED,
+        Some(p)=>p,
+    };
+    if !pool.check_socket_caller(sock_idx) {
+        return errcode::ERROR_NO_PERMISSION
+    }
+    return pool.set_write_interest(sock_idx, enable, false)
+}
+
+///start an asynchronous connect, the owner receives a SOCK_EVENT_CONNECT when it is completed or timed out
+pub(crate) fn start_connect(sock_idx:i32)->errcode::RESULT {
+    let pool = match unsafe {&mut gSocketPool} {
+        None=> return errcode::ERROR_NOT_INITIALIZED,
+        Some(p)=>p,
+    };
+    return pool.start_connect(sock_idx)
+}
+
+///set the timeout of the asynchronous connect, it takes effect on the next connect
+pub(crate) fn set_connect_timeout(sock_idx:i32,timeout_msec:u64)->errcode::RESULT {
+    let pool = match unsafe {&mut gSocketPool} {
+        None=> return errcode::ERROR_NOT_INITIALIZED,
+        Some(p)=>p,
+    };
+    if timeout_msec==0 {
+        return errcode::ERROR_INVALID_PARAM
+    }
+    return match pool.get_sock_binding_info(sock_idx) {
+        None=>errcode::ERROR_NOT_FOUND,
+        Some(info)=> {
+            info.connect_timeout_msec = timeout_msec;
+            errcode::RESULT_SUCCESS
+        },
+    }
+}
+
+//the connect timer is fired under the timer thread, the socket may be connected or closed concurrently
+fn on_connect_timeout(timer_id:rsm::rsm_timer_id_t,timer_data:usize) {
+    let pool = match unsafe {&mut gSocketPool} {
+        None=> return,
+        Some(p)=>p,
+    };
+    let sock_idx = timer_data as i32;
+    let owner = match pool.finish_connect(sock_idx, errcode::ERROR_TIME_OUT, Some(timer_id)) {
+        None=>return,
+        Some(o)=>o,
+    };
+    let ev = socket_event_t {
+        socket_id:sock_idx,
+        sock_type:SOCKET_TYPE::PROTO_STREAM,
+        event:SOCK_EVENT_CONNECT,
+        result:errcode::ERROR_TIME_OUT,
+    };
+    send_socket_event(&owner, &ev);
+}
+
+//the handshake timer is fired under the timer thread, the handshake may be finished or the socket closed concurrently
+#[cfg(feature="tls")]
+fn on_handshake_timeout(timer_id:rsm::rsm_timer_id_t,timer_data:usize) {
+    let pool = match unsafe {&mut gSocketPool} {
+        None=> return,
+        Some(p)=>p,
+    };
+    let sock_idx = timer_data as i32;
+    if !pool.finish_handshake(sock_idx, Some(timer_id)) {
+        return
+    }
+    let owner = match pool.get_sock_binding_info(sock_idx) {
+        None=>return,
+        Some(info)=>info.owner.clone(),
+    };
+    let ev = socket_event_t {
+        socket_id:sock_idx,
+        sock_type:SOCKET_TYPE::PROTO_STREAM,
+        event:rsm::SOCK_EVENT_TLS_HANDSHAKE,
+        result:errcode::ERROR_TIME_OUT,
+    };
+    send_socket_event(&owner, &ev);
+}
+
+fn send_socket_event(dst:&rsm_component_t,ev:&socket_event_t) {
+    if let Some(msg) = rsm::rsm_message_t::new_with_codec::<rsm::rsm_socket_event_t>(rsm::RSM_MSG_ID_SOCKET,ev,rsm::E_RSM_MSG_CODEC::MSG_CODEC_BINARY) {
+        rsm::send_asyn_msg(dst, msg);
+    }
+}
+
+///report a SOCK_EVENT_WRITE once the socket is writable again, used when a send is not completed
+pub(crate) fn arm_write_event(sock_idx:i32)->errcode::RESULT {
+    let pool = match unsafe {&mut gSocketPool} {
+        None=> return errcode::ERROR_NOT_INITIALIZED,
+        Some(p)=>p,
+    };
+    return pool.set_write_interest(sock_idx, true, true)
 }
 
 ///init socket pool, and start socket pool thread
@@ -326,6 +836,59 @@ pub(crate) fn init_socketpool_data() {
 }
 
 
+pub(crate) fn get_sock_info<'a>(sock_id:i32)->Option<&'a mut socket_info_t> {
+    let pool = match unsafe {&mut gSocketPool} {
+        None=> return None,
+        Some(p)=>p,
+    };
+
+    return pool.get_sock_binding_info(sock_id)
+}
+
+///set the TLS configuration of a listener, the caller must be the owner of the socket
+#[cfg(feature="tls")]
+pub(crate) fn set_tls_server_config(sock_idx:i32,config:std::sync::Arc<rustls::ServerConfig>)->errcode::RESULT {
+    let pool = match unsafe {&mut gSocketPool} {
+        None=> return errcode::ERROR_NOT_INITIALIZED,
+        Some(p)=>p,
+    };
+    if !pool.check_socket_caller(sock_idx) {
+        return errcode::ERROR_NO_PERMISSION
+    }
+    if !pool.is_tcp_server(sock_idx) {
+        return errcode::ERROR_INVALID_STATE
+    }
+    return match pool.get_sock_binding_info(sock_idx) {
+        None=>errcode::ERROR_NOT_FOUND,
+        Some(info)=> {
+            info.tls_server = Some(config);
+            errcode::RESULT_SUCCESS
+        },
+    }
+}
+
+///attach a TLS session to a socket before it is connected, the caller must be the owner of the socket
+#[cfg(feature="tls")]
+pub(crate) fn attach_tls_session(sock_idx:i32,session:tlssocket::tls_session_t)->errcode::RESULT {
+    let pool = match unsafe {&mut gSocketPool} {
+        None=> return errcode::ERROR_NOT_INITIALIZED,
+        Some(p)=>p,
+    };
+    if !pool.check_socket_caller(sock_idx) {
+        return errcode::ERROR_NO_PERMISSION
+    }
+    return match pool.get_sock_binding_info(sock_idx) {
+        None=>errcode::ERROR_NOT_FOUND,
+        Some(info)=> {
+            if info.s.get_sock_state()==SOCKET_STATE::SOCK_CONNECTED || info.s.get_sock_state()==SOCKET_STATE::SOCK_CONNECTING {
+                return errcode::ERROR_INVALID_STATE
+            }
+            info.tls = Some(Box::new(session));
+            errcode::RESULT_SUCCESS
+        },
+    }
+}
+
 ///get underlying Socket instance by socket index
 pub(crate) fn get_socket_by_idx<'a>(sock_id:i32)->Option<&'a mut Socket> {
     let pool = match unsafe {&mut gSocketPool} {
@@ -382,14 +945,44 @@ fn process_events(pool:&mut SocketPool,events:Vec<socket_event_t>) {
             ev.event=SOCK_EVENT_NEW;            
         }
         
+        let connecting = match pool.get_sock_binding_info(ev.socket_id) {
+            None=>continue,
+            Some(s)=>s.s.get_sock_state()==SOCKET_STATE::SOCK_CONNECTING,
+        };
+        //the connect in progress is completed when the socket is writable or failed
+        if connecting && (ev.event & (SOCK_EVENT_WRITE | SOCK_EVENT_CLOSE | rsm::SOCK_EVENT_ERR))!=0 {
+            let result = match pool.get_socket_by_idx(ev.socket_id) {
+                None=>continue,
+                Some(s)=>s.get_socket_error(),
+            };
+            if pool.finish_connect(ev.socket_id, result, None).is_none() {
+                continue;
+            }
+            ev.event = if result==errcode::RESULT_SUCCESS { SOCK_EVENT_CONNECT | (ev.event & SOCK_EVENT_READ) } else { SOCK_EVENT_CONNECT };
+            ev.result = result;
+        }
+        #[cfg(feature="tls")]
+        {
+            if let Some(tls_ev) = pool.process_tls_event(&mut ev) {
+                if let Some(info) = pool.get_sock_binding_info(ev.socket_id) {
+                    send_socket_event(&info.owner.clone(), &tls_ev);
+                }
+            }
+            if ev.event==0 {
+                continue;
+            }
+        }
         let sck = match pool.get_sock_binding_info(ev.socket_id) {
             None=>continue,
             Some(s)=>s,
         };
         ev.sock_type = sck.s.get_sock_type();
         let dst =  sck.owner.clone();
+        if (ev.event & SOCK_EVENT_WRITE)!=0 {
+            pool.clear_oneshot_write(ev.socket_id);
+        }
         //get_lb_task_id(&sck.owner, ev.sock_id, sck.s.get_lb_policy());   
-        let msg = match rsm::rsm_message_t::new::<rsm::rsm_socket_event_t>(rsm::RSM_MSG_ID_SOCKET,&ev) {
+        let msg = match rsm::rsm_message_t::new_with_codec::<rsm::rsm_socket_event_t>(rsm::RSM_MSG_ID_SOCKET,&ev,rsm::E_RSM_MSG_CODEC::MSG_CODEC_BINARY) {
             None=>continue,
             Some(m)=>m,
         };
@@ -404,6 +997,7 @@ fn process_events(pool:&mut SocketPool,events:Vec<socket_event_t>) {
 
 ///OAM implementation
 const err_not_imp:&str="not implement";
+const MAX_OAM_SOCKET_ROWS:usize=1000;
 use rsm::oam;
 fn register_oam() {
     let urls = ["/socket".to_string()];
@@ -416,12 +1010,39 @@ fn socket_oam_callback(op:oam::E_RSM_OAM_OP,url:&String,param:&String)->oam::oam
         oam::E_RSM_OAM_OP::CLI_OP_SHOW=>{
             return read_socket_stats(url, param)
         },
+        oam::E_RSM_OAM_OP::CLI_OP_DEL=>{
+            return close_socket_by_oam(param)
+        },
         _=>(),
     }
 
     resp
 }
 
+///force close a socket, the param is the socket_id
+fn close_socket_by_oam(param:&String)->rsm::oam::oam_cmd_resp_t {
+    let mut resp = rsm::oam::oam_cmd_resp_t::new(errcode::ERROR_INVALID_PARAM, &String::default());
+    let pool = match unsafe{&mut gSocketPool} {
+        None=> {
+            resp.RetCode = errcode::ERROR_NOT_INITIALIZED;
+            return resp
+        },
+        Some(p)=>p,
+    };
+    let sock_idx = match param.trim().parse::<i32>() {
+        Err(_)=>return resp,
+        Ok(i)=>i,
+    };
+    if sock_idx<=0 || sock_idx as usize>pool.capacity() {
+        resp.RetCode = errcode::ERROR_NOT_FOUND;
+        return resp
+    }
+    resp.RetCode = pool.force_close_socket(sock_idx);
+    resp.Description = format!("close socket {},ret={}",sock_idx,resp.RetCode);
+    resp
+}
+
+///list the sockets, the param is an optional socket_id
 fn read_socket_stats(url:&String,param:&String)->rsm::oam::oam_cmd_resp_t {
     let mut resp = rsm::oam::oam_cmd_resp_t::new(errcode::ERROR_NOT_SUPPORT, &err_not_imp.to_string());
     println!("recv oam call,url={},param={}",url,param);
@@ -430,9 +1051,42 @@ fn read_socket_stats(url:&String,param:&String)->rsm::oam::oam_cmd_resp_t {
         Some(p)=>p,
     };
 
+    let sock_id = param.trim().parse::<usize>().ok();
     let desc= format!("[socket pool,capacity={},used={}]",pool.capacity(), pool.get_used_count());
     resp.Description = desc;
     resp.RetCode=errcode::RESULT_SUCCESS;
+    resp.set_row_hdr(vec!["socket_id".to_string(),"type".to_string(),"state".to_string(),"local_addr".to_string(),"peer_addr".to_string(),
+        "owner".to_string(),"lb_policy".to_string(),"tx_bytes".to_string(),"tx_packets".to_string(),"rx_bytes".to_string(),
+        "rx_packets".to_string(),"tx_errors".to_string(),"rx_errors".to_string()]);
+    let mut count=0;
+    //the sockets are created and released by the task threads concurrently
+    pool.lock.lock();
+    for i in 1..pool.capacity()+1 {
+        if sock_id.map_or(false, |id| id!=i) {
+            continue
+        }
+        if let Some(info) = &pool.sockets[i] {
+            let (local,peer) = if info.s.sock_af==SOCKET_ADDRESS_FAMILY::SOCKET_UNIX {
+                (info.s.unix_local.as_ref().map(|a| a.to_string()).unwrap_or_default(),
+                info.s.unix_peer.as_ref().map(|a| a.to_string()).unwrap_or_default())
+            } else {
+                (info.s.get_local_addr().to_string(),info.s.get_peer_addr().to_string())
+            };
+            let policy = match info.s.get_lb_policy() {
+                Ok(p)=>format!("{:?}",p),
+                Err(_)=>String::default(),
+            };
+            let stats = info.s.get_stats();
+            resp.add_row(&vec![i.to_string(),format!("{:?}",info.s.get_sock_type()),format!("{:?}",info.s.get_sock_state()),local,peer,
+                format!("{}:{}",info.owner.cid,info.owner.inst_id),policy,stats.tx_bytes.to_string(),stats.tx_packets.to_string(),
+                stats.rx_bytes.to_string(),stats.rx_packets.to_string(),stats.tx_errors.to_string(),stats.rx_errors.to_string()]);
+            count+=1;
+            if count>=MAX_OAM_SOCKET_ROWS {
+                break
+            }
+        }
+    }
+    pool.lock.unlock();
     resp
 }
 
@@ -56,25 +56,65 @@ impl TcpSocket {
         return sock.set_recv_buffer(buf_size)
     }
 
+    ///connect to the dst asynchronously, return RESULT_SUCCESS if the connection is established at once,
+    /// or ERROR_WOULD_BLOCK if it is in progress, in that case the owner receives a SOCK_EVENT_CONNECT with the result,
+    /// the event result is ERROR_TIME_OUT if the connection is not established in the connect timeout,
+    /// any other error means the connect is not started or can't be watched, no event is reported and the socket should be closed
     pub fn connect(&mut self,dst:&SocketAddr)->errcode::RESULT {
         let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
             None=>return errcode::ERROR_INVALID_INDEX,
             Some(s)=>s,
         };
 
-        return sock.connect(dst)
+        let ret = sock.connect(dst);
+        if ret==errcode::ERROR_WOULD_BLOCK {
+            let res = socketpool::start_connect(self.sck_idx);
+            if res!=errcode::RESULT_SUCCESS {
+                return res
+            }
+        }
+        return ret
+    }
+
+    ///set the timeout of asynchronous connect, the default is SOCK_DEF_CONNECT_TIMEOUT_MSEC
+    pub fn set_connect_timeout(&mut self,timeout_msec:u64)->errcode::RESULT {
+        return socketpool::set_connect_timeout(self.sck_idx, timeout_msec)
     }
 
+    ///send data, return the length has been sent, which is less than buf.len() if the socket send buffer is full,
+    /// in that case the owner receives a SOCK_EVENT_WRITE once the socket is writable, and then sends the remaining data
     pub fn send(&mut self,buf:&[u8])->Result<usize,errcode::RESULT> {
+        #[cfg(feature="tls")]
+        if let Some(ret) = self.tls_send(buf) {
+            return ret
+        }
         let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
             None=>return Err(errcode::ERROR_INVALID_INDEX),
             Some(s)=>s,
         };
 
-        return sock.send(buf)
+        let len = match sock.send(buf) {
+            Ok(l)=>l,
+            Err(errcode::ERROR_WOULD_BLOCK)=>0,
+            Err(e)=>return Err(e),
+        };
+        if len<buf.len() {
+            socketpool::arm_write_event(self.sck_idx);
+        }
+        return Ok(len)
+    }
+
+    ///enable or disable the SOCK_EVENT_WRITE of the socket, the events are edge triggered,
+    /// so a SOCK_EVENT_WRITE is reported when the socket becomes writable after the send buffer is full
+    pub fn set_write_interest(&mut self,enable:bool)->errcode::RESULT {
+        return socketpool::set_write_interest(self.sck_idx, enable)
     }
 
     pub fn recv(&mut self,buf:&mut [u8])->Result<usize,errcode::RESULT> {
+        #[cfg(feature="tls")]
+        if let Some(ret) = self.tls_recv(buf) {
+            return ret
+        }
         let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
             None=>return Err(errcode::ERROR_INVALID_INDEX),
             Some(s)=>s,
@@ -116,6 +156,8 @@ impl TcpSocket {
     }        
 
     pub fn close(&mut self)->errcode::RESULT {
+        #[cfg(feature="tls")]
+        self.tls_close();
         socketpool::close_socket(self.sck_idx)
     }
 
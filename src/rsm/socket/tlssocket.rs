@@ -0,0 +1,398 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+use crate::common::errcode;
+use std::net::SocketAddr;
+use std::io::{self,Read,Write,BufReader};
+use std::fs::File;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool,Ordering};
+use rustls::pki_types::{CertificateDer,PrivateKeyDer,ServerName};
+use super::*;
+use super::{socketpool};
+use crate::rsm;
+
+///the max length of the encrypted data waiting to be sent, send accepts less data when it is exceeded
+const TLS_SEND_BUFFER_LIMIT:usize=64*1024;
+
+///TLS configuration of a TcpListener, the certificate chain and private key are loaded from PEM files
+#[derive(Clone)]
+pub struct TlsServerConfig {
+    config:Arc<rustls::ServerConfig>,
+}
+
+///TLS configuration of a TcpSocket, the server certificate is verified by the CA certificates loaded from a PEM file
+#[derive(Clone)]
+pub struct TlsClientConfig {
+    config:Arc<rustls::ClientConfig>,
+}
+
+fn crypto_provider()->Arc<rustls::crypto::CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+fn load_certs(cert_file:&str)->Result<Vec<CertificateDer<'static>>,errcode::RESULT> {
+    let f = match File::open(cert_file) {
+        Err(_)=>return Err(errcode::ERROR_FILE_NOT_FOUND),
+        Ok(f)=>f,
+    };
+    let mut reader = BufReader::new(f);
+    let mut certs = Vec::new();
+    for cert in rustls_pemfile::certs(&mut reader) {
+        match cert {
+            Err(_)=>return Err(errcode::ERROR_DECODE_MSG),
+            Ok(c)=>certs.push(c),
+        }
+    }
+    if certs.is_empty() {
+        return Err(errcode::ERROR_NOT_FOUND)
+    }
+    return Ok(certs)
+}
+
+fn load_private_key(key_file:&str)->Result<PrivateKeyDer<'static>,errcode::RESULT> {
+    let f = match File::open(key_file) {
+        Err(_)=>return Err(errcode::ERROR_FILE_NOT_FOUND),
+        Ok(f)=>f,
+    };
+    let mut reader = BufReader::new(f);
+    return match rustls_pemfile::private_key(&mut reader) {
+        Err(_)=>Err(errcode::ERROR_DECODE_MSG),
+        Ok(None)=>Err(errcode::ERROR_NOT_FOUND),
+        Ok(Some(k))=>Ok(k),
+    }
+}
+
+fn load_root_store(ca_file:&str)->Result<rustls::RootCertStore,errcode::RESULT> {
+    let certs = match load_certs(ca_file) {
+        Err(e)=>return Err(e),
+        Ok(c)=>c,
+    };
+    let mut roots = rustls::RootCertStore::empty();
+    for c in certs {
+        if roots.add(c).is_err() {
+            return Err(errcode::ERROR_DECODE_MSG)
+        }
+    }
+    return Ok(roots)
+}
+
+impl TlsServerConfig {
+    ///load the server certificate chain and private key, if client_ca_file is set,
+    /// a client must present a certificate signed by one of the CA certificates in it
+    pub fn from_pem_files(cert_file:&str,key_file:&str,client_ca_file:Option<&str>)->Result<Self,errcode::RESULT> {
+        let certs = match load_certs(cert_file) {
+            Err(e)=>return Err(e),
+            Ok(c)=>c,
+        };
+        let key = match load_private_key(key_file) {
+            Err(e)=>return Err(e),
+            Ok(k)=>k,
+        };
+        let builder = match rustls::ServerConfig::builder_with_provider(crypto_provider()).with_safe_default_protocol_versions() {
+            Err(_)=>return Err(errcode::ERROR_INIT_FAILED),
+            Ok(b)=>b,
+        };
+        let builder = match client_ca_file {
+            None=>builder.with_no_client_auth(),
+            Some(ca_file)=> {
+                let roots = match load_root_store(ca_file) {
+                    Err(e)=>return Err(e),
+                    Ok(r)=>r,
+                };
+                let verifier = match rustls::server::WebPkiClientVerifier::builder_with_provider(Arc::new(roots), crypto_provider()).build() {
+                    Err(_)=>return Err(errcode::ERROR_INIT_FAILED),
+                    Ok(v)=>v,
+                };
+                builder.with_client_cert_verifier(verifier)
+            },
+        };
+        let config = match builder.with_single_cert(certs, key) {
+            Err(_)=>return Err(errcode::ERROR_INVALID_PARAM),
+            Ok(c)=>c,
+        };
+
+        return Ok(Self { config:Arc::new(config) })
+    }
+}
+
+impl TlsClientConfig {
+    ///load the CA certificates used to verify the server, client_cert is a pair of certificate file and private key file,
+    /// which is presented to a server requiring client authentication
+    pub fn from_pem_files(ca_file:&str,client_cert:Option<(&str,&str)>)->Result<Self,errcode::RESULT> {
+        let roots = match load_root_store(ca_file) {
+            Err(e)=>return Err(e),
+            Ok(r)=>r,
+        };
+        let builder = match rustls::ClientConfig::builder_with_provider(crypto_provider()).with_safe_default_protocol_versions() {
+            Err(_)=>return Err(errcode::ERROR_INIT_FAILED),
+            Ok(b)=>b,
+        };
+        let builder = builder.with_root_certificates(roots);
+        let config = match client_cert {
+            None=>builder.with_no_client_auth(),
+            Some((cert_file,key_file))=> {
+                let certs = match load_certs(cert_file) {
+                    Err(e)=>return Err(e),
+                    Ok(c)=>c,
+                };
+                let key = match load_private_key(key_file) {
+                    Err(e)=>return Err(e),
+                    Ok(k)=>k,
+                };
+                match builder.with_client_auth_cert(certs, key) {
+                    Err(_)=>return Err(errcode::ERROR_INVALID_PARAM),
+                    Ok(c)=>c,
+                }
+            },
+        };
+
+        return Ok(Self { config:Arc::new(config) })
+    }
+}
+
+///TLS state of a tcp connection, the handshake is driven by the socket pool thread,
+/// after that the session is only used by the owner task
+pub(crate) struct tls_session_t {
+    conn:rustls::Connection,
+    handshaking:AtomicBool,
+    //timer of the handshake, started on the first socket event of the connection,
+    //the result is reported by the thread which resets it first, the socket pool thread or the timer thread
+    pub(crate) handshake_timer:rsm::rsm_timer_id_t,
+    pub(crate) handshake_timed_out:bool,
+}
+
+impl tls_session_t {
+    pub(crate) fn new_server(config:&Arc<rustls::ServerConfig>)->Option<Self> {
+        let mut conn = match rustls::ServerConnection::new(config.clone()) {
+            Err(_)=>return None,
+            Ok(c)=>c,
+        };
+        conn.set_buffer_limit(Some(TLS_SEND_BUFFER_LIMIT));
+        return Some(Self { conn:rustls::Connection::Server(conn), handshaking:AtomicBool::new(true), handshake_timer:rsm::RSM_INVALID_TIMER_ID, handshake_timed_out:false })
+    }
+
+    fn new_client(config:&Arc<rustls::ClientConfig>,server_name:&str)->Result<Self,errcode::RESULT> {
+        let name = match ServerName::try_from(server_name.to_string()) {
+            Err(_)=>return Err(errcode::ERROR_INVALID_PARAM),
+            Ok(n)=>n,
+        };
+        let mut conn = match rustls::ClientConnection::new(config.clone(), name) {
+            Err(_)=>return Err(errcode::ERROR_INIT_FAILED),
+            Ok(c)=>c,
+        };
+        conn.set_buffer_limit(Some(TLS_SEND_BUFFER_LIMIT));
+        return Ok(Self { conn:rustls::Connection::Client(conn), handshaking:AtomicBool::new(true), handshake_timer:rsm::RSM_INVALID_TIMER_ID, handshake_timed_out:false })
+    }
+
+    pub(crate) fn is_handshaking(&self)->bool {
+        self.handshaking.load(Ordering::Acquire)
+    }
+}
+
+///adapter of the non-blocking socket for rustls
+struct sock_io_t<'a>(&'a mut Socket);
+
+impl<'a> Read for sock_io_t<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        return match self.0.recv(buf) {
+            Ok(l)=>Ok(l),
+            Err(errcode::ERROR_WOULD_BLOCK)=>Err(io::ErrorKind::WouldBlock.into()),
+            Err(_)=>Err(io::ErrorKind::ConnectionReset.into()),
+        }
+    }
+}
+
+impl<'a> Write for sock_io_t<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        return match self.0.send(buf) {
+            Ok(l)=>Ok(l),
+            Err(errcode::ERROR_WOULD_BLOCK)=>Err(io::ErrorKind::WouldBlock.into()),
+            Err(_)=>Err(io::ErrorKind::ConnectionReset.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+//send the encrypted data as much as possible, return false if some data is left
+fn flush_tls(conn:&mut rustls::Connection,io:&mut sock_io_t)->Result<bool,errcode::RESULT> {
+    while conn.wants_write() {
+        match conn.write_tls(io) {
+            Ok(_)=>(),
+            Err(e) if e.kind()==io::ErrorKind::WouldBlock=>return Ok(false),
+            Err(_)=>return Err(errcode::ERROR_SEND_MSG),
+        }
+    }
+    return Ok(true)
+}
+
+pub(crate) enum TLS_HANDSHAKE_STATE {
+    ///the handshake is in progress, true if it is waiting for the socket to be writable
+    TLS_HANDSHAKE_IN_PROGRESS(bool),
+    TLS_HANDSHAKE_FINISHED,
+    TLS_HANDSHAKE_FAILED(errcode::RESULT),
+}
+
+///continue the handshake on a socket event, called by the socket pool thread,
+/// the socket is read until no data is left because the events are edge triggered
+pub(crate) fn drive_handshake(sock:&mut Socket,tls:&mut tls_session_t)->TLS_HANDSHAKE_STATE {
+    let mut io = sock_io_t(sock);
+    let conn = &mut tls.conn;
+    loop {
+        match flush_tls(conn, &mut io) {
+            Err(_)=>return TLS_HANDSHAKE_STATE::TLS_HANDSHAKE_FAILED(errcode::ERROR_CONNECTION),
+            Ok(false)=>return TLS_HANDSHAKE_STATE::TLS_HANDSHAKE_IN_PROGRESS(true),
+            Ok(true)=>(),
+        }
+        if !conn.is_handshaking() {
+            tls.handshaking.store(false, Ordering::Release);
+            return TLS_HANDSHAKE_STATE::TLS_HANDSHAKE_FINISHED
+        }
+        match conn.read_tls(&mut io) {
+            Ok(0)=>return TLS_HANDSHAKE_STATE::TLS_HANDSHAKE_FAILED(errcode::ERROR_CONNECTION),
+            Ok(_)=>(),
+            Err(e) if e.kind()==io::ErrorKind::WouldBlock=>return TLS_HANDSHAKE_STATE::TLS_HANDSHAKE_IN_PROGRESS(false),
+            Err(_)=>return TLS_HANDSHAKE_STATE::TLS_HANDSHAKE_FAILED(errcode::ERROR_CONNECTION),
+        }
+        if conn.process_new_packets().is_err() {
+            //send the alert to the peer if possible
+            let _ = flush_tls(conn, &mut io);
+            return TLS_HANDSHAKE_STATE::TLS_HANDSHAKE_FAILED(errcode::ERROR_AUTH_FAILED)
+        }
+    }
+}
+
+///TLS extension of TcpListener
+impl TcpListener {
+    ///wrap the accepted connections by TLS, the owner of a connection receives SOCK_EVENT_NEW,
+    /// and then SOCK_EVENT_TLS_HANDSHAKE when the handshake is finished
+    pub fn enable_tls(&mut self,config:&TlsServerConfig)->errcode::RESULT {
+        return socketpool::set_tls_server_config(self.sck_idx, config.config.clone())
+    }
+
+    ///set the timeout of the handshake of the accepted connections, the default is SOCK_DEF_CONNECT_TIMEOUT_MSEC,
+    /// the owner receives SOCK_EVENT_TLS_HANDSHAKE with ERROR_TIME_OUT if the handshake is not finished in time
+    pub fn set_tls_handshake_timeout(&mut self,timeout_msec:u64)->errcode::RESULT {
+        return socketpool::set_connect_timeout(self.sck_idx, timeout_msec)
+    }
+}
+
+///TLS extension of TcpSocket, send and recv encrypt and decrypt the data once the TLS session is established
+impl TcpSocket {
+    ///connect to a TLS server, server_name is checked against the server certificate,
+    /// return ERROR_WOULD_BLOCK if the connection is in progress, the owner receives SOCK_EVENT_CONNECT,
+    /// and then SOCK_EVENT_TLS_HANDSHAKE with the result of the handshake,
+    /// the handshake is limited by the connect timeout as well, which is counted from the connection is established
+    pub fn connect_tls(&mut self,dst:&SocketAddr,server_name:&str,config:&TlsClientConfig)->errcode::RESULT {
+        let session = match tls_session_t::new_client(&config.config, server_name) {
+            Err(e)=>return e,
+            Ok(s)=>s,
+        };
+        let ret = socketpool::attach_tls_session(self.sck_idx, session);
+        if ret!=errcode::RESULT_SUCCESS {
+            return ret
+        }
+        let ret = self.connect(dst);
+        if ret==errcode::RESULT_SUCCESS {
+            //connected at once, start the handshake when the socket is writable
+            socketpool::arm_write_event(self.sck_idx);
+            return errcode::ERROR_WOULD_BLOCK
+        }
+        return ret
+    }
+
+    ///whether the socket is a TLS connection and the handshake is finished
+    pub fn is_tls_established(&self)->bool {
+        return match socketpool::get_sock_info(self.sck_idx) {
+            None=>false,
+            Some(info)=>match &info.tls {
+                None=>false,
+                Some(tls)=>!tls.is_handshaking(),
+            },
+        }
+    }
+
+    pub(crate) fn tls_send(&mut self,buf:&[u8])->Option<Result<usize,errcode::RESULT>> {
+        let info = match socketpool::get_sock_info(self.sck_idx) {
+            None=>return None,
+            Some(i)=>i,
+        };
+        let tls = match &mut info.tls {
+            None=>return None,
+            Some(t)=>t,
+        };
+        if tls.is_handshaking() {
+            return Some(Err(errcode::ERROR_INVALID_STATE))
+        }
+        let mut io = sock_io_t(&mut info.s);
+        if let Err(e) = flush_tls(&mut tls.conn, &mut io) {
+            return Some(Err(e))
+        }
+        let len = match tls.conn.writer().write(buf) {
+            Ok(l)=>l,
+            Err(_)=>return Some(Err(errcode::ERROR_SEND_MSG)),
+        };
+        let flushed = match flush_tls(&mut tls.conn, &mut io) {
+            Err(e)=>return Some(Err(e)),
+            Ok(f)=>f,
+        };
+        //the encrypted data left is sent by the next send, e.g. an empty send on SOCK_EVENT_WRITE
+        if !flushed || len<buf.len() {
+            socketpool::arm_write_event(self.sck_idx);
+        }
+        return Some(Ok(len))
+    }
+
+    pub(crate) fn tls_recv(&mut self,buf:&mut [u8])->Option<Result<usize,errcode::RESULT>> {
+        let info = match socketpool::get_sock_info(self.sck_idx) {
+            None=>return None,
+            Some(i)=>i,
+        };
+        let tls = match &mut info.tls {
+            None=>return None,
+            Some(t)=>t,
+        };
+        if tls.is_handshaking() {
+            return Some(Err(errcode::ERROR_INVALID_STATE))
+        }
+        let mut io = sock_io_t(&mut info.s);
+        loop {
+            match tls.conn.reader().read(buf) {
+                Ok(l)=>return Some(Ok(l)),
+                Err(e) if e.kind()==io::ErrorKind::WouldBlock=>(),
+                //closed by the peer without close_notify
+                Err(_)=>return Some(Err(errcode::ERROR_CONNECTION)),
+            }
+            match tls.conn.read_tls(&mut io) {
+                Ok(_)=>(),
+                Err(e) if e.kind()==io::ErrorKind::WouldBlock=>return Some(Err(errcode::ERROR_WOULD_BLOCK)),
+                Err(_)=>return Some(Err(errcode::ERROR_RECV_MSG)),
+            }
+            if tls.conn.process_new_packets().is_err() {
+                let _ = flush_tls(&mut tls.conn, &mut io);
+                return Some(Err(errcode::ERROR_DECODE_MSG))
+            }
+            //response of the post-handshake messages, e.g. key update
+            let _ = flush_tls(&mut tls.conn, &mut io);
+        }
+    }
+
+    //send close_notify to the peer before the socket is closed
+    pub(crate) fn tls_close(&mut self) {
+        let info = match socketpool::get_sock_info(self.sck_idx) {
+            None=>return,
+            Some(i)=>i,
+        };
+        if let Some(tls) = &mut info.tls {
+            if !tls.is_handshaking() {
+                tls.conn.send_close_notify();
+                let _ = flush_tls(&mut tls.conn, &mut sock_io_t(&mut info.s));
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+use crate::common::errcode;
+use crate::net_ext::{RawFdType,unix::rawsocket};
+use super::*;
+use super::{socketpool};
+
+///Unix domain socket extension of the inner Socket
+impl Socket {
+    pub fn bind_unix(&mut self,addr:&UnixSocketAddr)->errcode::RESULT {
+        if self.state!=SOCKET_STATE::SOCK_INIT {
+            return errcode::ERROR_INVALID_STATE
+        }
+        let ret = rawsocket::bind_unix(self.get_raw_fd(), addr);
+        if ret == errcode::RESULT_SUCCESS {
+            self.state=SOCKET_STATE::SOCK_BIND;
+            self.unix_local = Some(addr.clone());
+        }
+        return ret
+    }
+
+    pub fn connect_unix(&mut self,dst:&UnixSocketAddr)->errcode::RESULT {
+        let ret = rawsocket::connect_unix(self.os_fd, dst);
+        if ret==errcode::RESULT_SUCCESS {
+            self.state=SOCKET_STATE::SOCK_CONNECTED;
+            self.unix_peer = Some(dst.clone());
+        }
+        return ret
+    }
+
+    pub(crate) fn accept_unix(&self)->Result<(RawFdType,UnixSocketAddr),errcode::RESULT> {
+        return rawsocket::accept_unix(self.os_fd)
+    }
+
+    pub fn send_to_unix(&mut self,dst:&UnixSocketAddr,buf:&[u8])->Result<usize,errcode::RESULT> {
+        let ret = rawsocket::send_to_unix(self.os_fd, buf, dst);
+        self.stats.update_tx(&ret);
+        return ret
+    }
+
+    pub fn recv_from_unix(&mut self,buf:&mut [u8])->Result<(usize,UnixSocketAddr),errcode::RESULT> {
+        let ret = rawsocket::recv_from_unix(self.os_fd, buf);
+        self.stats.update_rx(ret.as_ref().map(|(l,_)| *l).map_err(|e| *e));
+        return ret
+    }
+
+    pub fn get_peer_cred(&self)->Result<UnixCredential,errcode::RESULT> {
+        let (pid,uid,gid) = match rawsocket::get_peer_cred(self.os_fd) {
+            Ok(c)=>c,
+            Err(e)=>return Err(e),
+        };
+        return Ok(UnixCredential { pid:pid, uid:uid, gid:gid })
+    }
+
+    pub fn get_unix_local_addr(&self)->Option<UnixSocketAddr> {
+        self.unix_local.clone()
+    }
+
+    pub fn get_unix_peer_addr(&self)->Option<UnixSocketAddr> {
+        self.unix_peer.clone()
+    }
+}
+
+fn new_unix_socket(sock_type:SOCKET_TYPE)->Result<i32,errcode::RESULT> {
+    return socketpool::new_socket(SOCKET_ADDRESS_FAMILY::SOCKET_UNIX, sock_type, 0)
+}
+
+///UnixListener API Implementation
+///the stream connections are accepted by rsm, and dispatched to the component by the loadbalance policy, the same as TcpListener
+impl UnixListener {
+    ///listen on a file system path or an abstract name, the path must not exist, it is removed when the listener is closed
+    pub fn new(local_addr:&UnixSocketAddr,max_back_log:i32,policy:SOCKET_LB_POLICY)->Result<Self,errcode::RESULT> {
+        let sock_id = match new_unix_socket(SOCKET_TYPE::PROTO_STREAM) {
+            Ok(s)=>s,
+            Err(e)=>return Err(e),
+        };
+        let sock = match socketpool::get_socket_by_idx(sock_id) {
+            None=>return Err(errcode::ERROR_INIT_FAILED),
+            Some(s)=>s,
+        };
+        let ret = sock.bind_unix(local_addr);
+        if ret!=errcode::RESULT_SUCCESS {
+            socketpool::close_socket(sock_id);
+            return Err(ret)
+        }
+        let ret = sock.listen(max_back_log);
+        if ret!=errcode::RESULT_SUCCESS {
+            socketpool::close_socket(sock_id);
+            return Err(ret)
+        }
+        sock.set_lb_policy(policy);
+
+        return Ok(Self { sck_idx:sock_id })
+    }
+
+    pub fn get_socket_by_id(id:i32)->Self {
+        return Self { sck_idx: id }
+    }
+
+    pub fn get_socket_id(&self)->i32 {
+        self.sck_idx
+    }
+
+    pub fn get_local_addr(&self)->Option<UnixSocketAddr> {
+        let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
+            None=>return None,
+            Some(s)=>s,
+        };
+
+        return sock.get_unix_local_addr()
+    }
+
+    pub fn get_lb_policy(&self)->Result<SOCKET_LB_POLICY,errcode::RESULT> {
+        let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
+            None=>return Err(errcode::ERROR_INVALID_INDEX),
+            Some(s)=>s,
+        };
+
+        return sock.get_lb_policy()
+    }
+
+    pub fn close(&mut self)->errcode::RESULT {
+        let local = self.get_local_addr();
+        let ret = socketpool::close_socket(self.sck_idx);
+        if ret==errcode::RESULT_SUCCESS {
+            if let Some(UnixSocketAddr::Path(p)) = local {
+                let _ = std::fs::remove_file(p);
+            }
+        }
+        return ret
+    }
+}
+
+///UnixStream API Implementation, a stream connection of unix domain socket
+impl UnixStream {
+    ///connect to a unix listener, the connection is established at once,
+    /// ERROR_WOULD_BLOCK is returned if the backlog of the listener is full
+    pub fn connect(dst:&UnixSocketAddr)->Result<Self,errcode::RESULT> {
+        let sock_id = match new_unix_socket(SOCKET_TYPE::PROTO_STREAM) {
+            Ok(s)=>s,
+            Err(e)=>return Err(e),
+        };
+        let sock = match socketpool::get_socket_by_idx(sock_id) {
+            None=>return Err(errcode::ERROR_INIT_FAILED),
+            Some(s)=>s,
+        };
+        let ret = sock.connect_unix(dst);
+        if ret!=errcode::RESULT_SUCCESS {
+            socketpool::close_socket(sock_id);
+            return Err(ret)
+        }
+
+        return Ok(Self { sck_idx:sock_id })
+    }
+
+    pub fn get_socket_by_id(id:i32)->Self {
+        return Self { sck_idx: id }
+    }
+
+    pub fn get_socket_id(&self)->i32 {
+        self.sck_idx
+    }
+
+    ///send data, return the length has been sent, the same as TcpSocket::send
+    pub fn send(&mut self,buf:&[u8])->Result<usize,errcode::RESULT> {
+        let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
+            None=>return Err(errcode::ERROR_INVALID_INDEX),
+            Some(s)=>s,
+        };
+
+        let len = match sock.send(buf) {
+            Ok(l)=>l,
+            Err(errcode::ERROR_WOULD_BLOCK)=>0,
+            Err(e)=>return Err(e),
+        };
+        if len<buf.len() {
+            socketpool::arm_write_event(self.sck_idx);
+        }
+        return Ok(len)
+    }
+
+    pub fn recv(&mut self,buf:&mut [u8])->Result<usize,errcode::RESULT> {
+        let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
+            None=>return Err(errcode::ERROR_INVALID_INDEX),
+            Some(s)=>s,
+        };
+
+        return sock.recv(buf)
+    }
+
+    pub fn set_write_interest(&mut self,enable:bool)->errcode::RESULT {
+        return socketpool::set_write_interest(self.sck_idx, enable)
+    }
+
+    ///get the pid, uid and gid of the peer process
+    pub fn get_peer_cred(&self)->Result<UnixCredential,errcode::RESULT> {
+        let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
+            None=>return Err(errcode::ERROR_INVALID_INDEX),
+            Some(s)=>s,
+        };
+
+        return sock.get_peer_cred()
+    }
+
+    ///the peer address, it is usually unnamed for an accepted connection
+    pub fn get_peer_addr(&self)->Option<UnixSocketAddr> {
+        let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
+            None=>return None,
+            Some(s)=>s,
+        };
+
+        return sock.get_unix_peer_addr()
+    }
+
+    pub fn close(&mut self)->errcode::RESULT {
+        socketpool::close_socket(self.sck_idx)
+    }
+}
+
+///UnixDatagram API Implementation, connectionless unix domain socket
+impl UnixDatagram {
+    ///create a datagram socket, an unnamed socket can send but can't be replied
+    pub fn new(local_addr:&UnixSocketAddr)->Result<Self,errcode::RESULT> {
+        let sock_id = match new_unix_socket(SOCKET_TYPE::PROTO_DGRAM) {
+            Ok(s)=>s,
+            Err(e)=>return Err(e),
+        };
+        let sock = match socketpool::get_socket_by_idx(sock_id) {
+            None=>return Err(errcode::ERROR_INIT_FAILED),
+            Some(s)=>s,
+        };
+        if *local_addr!=UnixSocketAddr::Unnamed {
+            let ret = sock.bind_unix(local_addr);
+            if ret!=errcode::RESULT_SUCCESS {
+                socketpool::close_socket(sock_id);
+                return Err(ret)
+            }
+        }
+
+        return Ok(Self { sck_idx:sock_id })
+    }
+
+    pub fn get_socket_by_id(id:i32)->Self {
+        return Self { sck_idx: id }
+    }
+
+    pub fn get_socket_id(&self)->i32 {
+        self.sck_idx
+    }
+
+    pub fn send_to(&mut self,dst:&UnixSocketAddr,buf:&[u8])->Result<usize,errcode::RESULT> {
+        let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
+            None=>return Err(errcode::ERROR_INVALID_INDEX),
+            Some(s)=>s,
+        };
+
+        return sock.send_to_unix(dst, buf)
+    }
+
+    pub fn recv_from(&mut self,buf:&mut [u8])->Result<(usize,UnixSocketAddr),errcode::RESULT> {
+        let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
+            None=>return Err(errcode::ERROR_INVALID_INDEX),
+            Some(s)=>s,
+        };
+
+        return sock.recv_from_unix(buf)
+    }
+
+    pub fn get_local_addr(&self)->Option<UnixSocketAddr> {
+        let sock = match socketpool::get_socket_by_idx(self.sck_idx) {
+            None=>return None,
+            Some(s)=>s,
+        };
+
+        return sock.get_unix_local_addr()
+    }
+
+    ///the bound path is removed when the socket is closed
+    pub fn close(&mut self)->errcode::RESULT {
+        let local = self.get_local_addr();
+        let ret = socketpool::close_socket(self.sck_idx);
+        if ret==errcode::RESULT_SUCCESS {
+            if let Some(UnixSocketAddr::Path(p)) = local {
+                let _ = std::fs::remove_file(p);
+            }
+        }
+        return ret
+    }
+}
@@ -6,7 +6,87 @@
 //rsm scheduler, manager task control block, schedule task according to the message
 use super::*;
 use common::{errcode,atomicqueue::AtomicDequeue};
-use common::sched;
+use common::{sched,histogram_t};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::ops::{Deref,DerefMut};
+
+//max message ids tracked by the latency histogram, the others are counted under RSM_INVALID_MESSAGE_ID
+const MAX_LATENCY_MSG_ID:usize = 32;
+
+///queue wait time and handler execution time of a message id, in usec
+#[derive(Default,Clone)]
+pub(crate) struct msg_latency_t {
+    pub(crate) wait:histogram_t,
+    pub(crate) exec:histogram_t,
+}
+
+///the Runnable instance of a task, a boxed instance is owned by the task and dropped when the task is restarted or released
+pub(crate) enum task_obj_t {
+    STATIC(&'static mut dyn Runnable),
+    BOXED(Box<dyn Runnable>),
+}
+
+impl Deref for task_obj_t {
+    type Target = dyn Runnable;
+    fn deref(&self)->&Self::Target {
+        match self {
+            Self::STATIC(t)=>&**t,
+            Self::BOXED(t)=>t.as_ref(),
+        }
+    }
+}
+
+impl DerefMut for task_obj_t {
+    fn deref_mut(&mut self)->&mut Self::Target {
+        match self {
+            Self::STATIC(t)=>&mut **t,
+            Self::BOXED(t)=>t.as_mut(),
+        }
+    }
+}
+
+///latency histograms of a task, recorded by the task thread and read or cleared by the OAM thread
+#[derive(Default)]
+pub(crate) struct task_latency_t {
+    latency:Mutex<HashMap<rsm_message_id_t,msg_latency_t>>,
+}
+
+impl task_latency_t {
+    pub(crate) fn record(&self,msg_id:rsm_message_id_t,wait_usec:Option<u64>,exec_usec:u64) {
+        let mut latency = match self.latency.lock() {
+            Err(_)=>return,
+            Ok(l)=>l,
+        };
+        let key = if latency.contains_key(&msg_id) || latency.len()<MAX_LATENCY_MSG_ID {
+            msg_id
+        } else {
+            RSM_INVALID_MESSAGE_ID
+        };
+        let l = latency.entry(key).or_default();
+        if let Some(w) = wait_usec {
+            l.wait.record(w);
+        }
+        l.exec.record(exec_usec);
+    }
+
+    ///get a copy of the histograms, sorted by message id
+    pub(crate) fn snapshot(&self)->Vec<(rsm_message_id_t,msg_latency_t)> {
+        let latency = match self.latency.lock() {
+            Err(_)=>return Vec::new(),
+            Ok(l)=>l,
+        };
+        let mut v:Vec<(rsm_message_id_t,msg_latency_t)> = latency.iter().map(|(k,l)| (*k,l.clone())).collect();
+        v.sort_by_key(|l| l.0);
+        return v
+    }
+
+    pub(crate) fn clear(&self) {
+        if let Ok(mut l) = self.latency.lock() {
+            l.clear();
+        }
+    }
+}
 
 #[derive(Default,Clone,Serialize)]
 pub(crate) struct task_stats_t {
@@ -15,35 +95,55 @@ pub(crate) struct task_stats_t {
     drop_msg:u64,
     drop_prio_msg:u64,
     timer_ev_count:u64,    
-    cur_msg_id:u32,
-    last_run_at:u64,    
+    pub(crate) cur_msg_id:u32,
+    pub(crate) last_run_at:u64,    
     last_run_usec:u64,
-    cur_run_usec:u64,
+    pub(crate) cur_run_usec:u64,
+    restart_count:u64,
+    pub(crate) dispatch_count:u64,
+    ///the number of the messages processed longer than the component deadline_usec
+    pub(crate) overrun_count:u64,
+    pub(crate) last_overrun_msg_id:u32,
+    pub(crate) last_overrun_usec:u64,
 }
 
 pub(crate) struct task_t{
     tid:rsm_component_t,
     os_tid:sched::os_task_id_t,
     need_init_ack:bool,
+    powered_on:bool,
+    init_acked:bool,
     recv_q:Option<AtomicDequeue<rsm_message_t>>,
     priority:E_RSM_TASK_PRIORITY,
+    //the deadline budget of a message handler, 0 means no deadline
+    deadline_usec:u64,
     stats:task_stats_t,
     sender:Option<rsm_component_t>,
     terminated:bool,
-    task_obj:&'static mut dyn Runnable,
+    //the task exits abnormally and is not restarted
+    failed:bool,
+    restart_at:Vec<u64>,
+    latency:task_latency_t,
+    task_obj:task_obj_t,
 }
 
 impl task_t {
-    pub(crate)  fn new(tid:&rsm_component_t,need_init_ack:bool,q_len:usize,prio:E_RSM_TASK_PRIORITY,task_obj:&'static mut dyn Runnable)->Self {
+    pub(crate)  fn new(tid:&rsm_component_t,need_init_ack:bool,q_len:usize,prio:E_RSM_TASK_PRIORITY,deadline_usec:u64,task_obj:task_obj_t)->Self {
         return Self {
             tid:tid.clone(),
             need_init_ack:need_init_ack,
+            powered_on:false,
+            init_acked:false,
             os_tid:0,
             recv_q:Some(AtomicDequeue::new(q_len)),
             priority:prio,
+            deadline_usec:deadline_usec,
             stats:task_stats_t::default(),
             sender:None,   
             terminated:false,
+            failed:false,
+            restart_at:Vec::new(),
+            latency:task_latency_t::default(),
             task_obj:task_obj,
         }
     }
@@ -52,13 +152,22 @@ impl task_t {
     pub(crate) fn get_self_cid(&self)->Option<&rsm_component_t> {
         Some(&self.tid)
     }
+    ///power on is acknowledged, or the task report it has been initialized
     pub(crate) fn is_init_completed(&self)->bool {
-        !self.need_init_ack || self.task_obj.is_inited()
+        self.init_acked || (self.powered_on && self.need_init_ack && self.task_obj.is_inited())
+    }
+
+    pub(crate) fn set_init_acked(&mut self) {
+        self.init_acked = true;
     }
 
-    pub(crate)  fn send_asyn_msg(&mut self,msg:rsm_message_t)->errcode::RESULT {
+    pub(crate)  fn send_asyn_msg(&mut self,mut msg:rsm_message_t)->errcode::RESULT {
+        if self.failed {
+            self.stats.drop_msg+=1;
+            return errcode::ERROR_INVALID_STATE
+        }
+        msg.sent_at = common::get_now_usec64();
         if let Some(q)=&mut self.recv_q {
-            self.sender = Some(msg.sender.clone());
             let res = q.push_back(msg);
             if res==errcode::RESULT_SUCCESS {
                 self.stats.recv_msg+=1;
@@ -74,9 +183,13 @@ impl task_t {
     }
 
     ///send one high priority message to specific component
-    pub(crate) fn send_asyn_priority_msg(&mut self,msg:rsm_message_t)->errcode::RESULT {
+    pub(crate) fn send_asyn_priority_msg(&mut self,mut msg:rsm_message_t)->errcode::RESULT {
+        if self.failed {
+            self.stats.drop_prio_msg+=1;
+            return errcode::ERROR_INVALID_STATE
+        }
+        msg.sent_at = common::get_now_usec64();
          if let Some(q)=&mut self.recv_q {
-            self.sender = Some(msg.sender.clone());
             let res =  q.push_front(msg);
             if res==errcode::RESULT_SUCCESS {
                 q.notify();
@@ -91,6 +204,10 @@ impl task_t {
         }
     }
 
+    pub(crate) fn get_os_task_id(&self)->sched::os_task_id_t {
+        self.os_tid
+    }
+
     pub(crate) fn get_task_priority(&self)->E_RSM_TASK_PRIORITY {
         self.priority
     }
@@ -122,7 +239,6 @@ impl task_t {
             None=>return,
             Some(q)=>q,
         };
-        self.task_obj.on_init(&self.tid);
         loop {
             rq.wait();
             loop {
@@ -132,22 +248,47 @@ impl task_t {
                 };
                 self.stats.cur_msg_id = msg.msg_id;
                 self.stats.last_run_at = common::get_now_usec64();
+                self.stats.dispatch_count+=1;
+                self.sender = Some(msg.sender.clone());
+                if msg.is_response() {
+                    if rsm_rpc::on_response_received(&self.tid,&msg) {
+                        self.task_obj.on_response(&self.tid,msg.req_id,errcode::RESULT_SUCCESS,Some(&msg));
+                    }
+                    Self::end_dispatch(&mut self.stats, &self.latency, self.deadline_usec, msg.msg_id, msg.sent_at);
+                    continue;
+                }
                 match msg.msg_id {
                     RSM_MSG_ID_TIMER=> {
                         self.stats.timer_ev_count+=1;
                         self.task_obj.on_timer(&self.tid,msg.timer_id,msg.timer_data);
                     },
+                    RSM_MSG_ID_REQ_TIMEOUT=> {
+                        let req_id = msg.timer_data as rsm_request_id_t;
+                        if rsm_rpc::on_request_timeout(req_id) {
+                            self.task_obj.on_response(&self.tid,req_id,errcode::ERROR_TIME_OUT,None);
+                        }
+                    },
                     RSM_MSG_ID_SOCKET=> {
-                        if let Some(ev) = msg.decode() {
+                        if let Some(ev) = msg.decode::<rsm_socket_event_t>() {
+                            let (sock_id,closed) = (ev.socket_id,(ev.event & SOCK_EVENT_CLOSE)!=0);
                             self.task_obj.on_socket_event(&self.tid, ev);
+                            //the socket closed by OAM is released by the owner, after the application stops using it
+                            if closed {
+                                socket::socketpool::release_force_closed_socket(sock_id);
+                            }
                         }
                         
                     },                   
-                    RSM_MSG_ID_MASTER_POWER_ON..=RSM_MSG_ID_SLAVE_POWER_ON=>self.task_obj.on_init(&self.tid),
+                    RSM_MSG_ID_MASTER_POWER_ON..=RSM_MSG_ID_SLAVE_POWER_ON=> {
+                        self.task_obj.on_init(&self.tid);
+                        self.powered_on = true;
+                        if !self.need_init_ack {
+                            rsm_sched::send_power_on_ack(&self.tid);
+                        }
+                    },
 
                     
                     RSM_MSG_ID_POWER_OFF=>{
-                        self.task_obj.on_close(&self.tid);
                         self.terminated = true;
                         break;
                     },
@@ -155,20 +296,77 @@ impl task_t {
                         self.task_obj.on_message(&self.tid,msg.msg_id,&msg);                        
                     },               
                 }
-                self.stats.cur_msg_id = RSM_INVALID_MESSAGE_ID;
-                self.stats.last_run_usec = common::get_now_usec64()-self.stats.last_run_at;
+                Self::end_dispatch(&mut self.stats, &self.latency, self.deadline_usec, msg.msg_id, msg.sent_at);
             }
             if self.terminated {
                 break;
             }
         }
         self.task_obj.on_close(&self.tid);
+        self.release_resources();
+    }
+
+    ///update the run time and latency histogram after a message is processed,
+    /// the overrun is recorded for the watchdog, which may not see a handler finished within its check period
+    fn end_dispatch(stats:&mut task_stats_t,latency:&task_latency_t,deadline_usec:u64,msg_id:rsm_message_id_t,sent_at:u64) {
+        let now = common::get_now_usec64();
+        stats.cur_msg_id = RSM_INVALID_MESSAGE_ID;
+        stats.last_run_usec = now.saturating_sub(stats.last_run_at);
+        if deadline_usec>0 && stats.last_run_usec>deadline_usec {
+            stats.overrun_count+=1;
+            stats.last_overrun_msg_id = msg_id;
+            stats.last_overrun_usec = stats.last_run_usec;
+        }
+        let wait = if sent_at>0 { Some(stats.last_run_at.saturating_sub(sent_at)) } else { None };
+        latency.record(msg_id, wait, stats.last_run_usec);
+    }
+
+    ///get the latency histograms, sorted by message id
+    pub(crate) fn get_latency_stats(&self)->Vec<(rsm_message_id_t,msg_latency_t)> {
+        return self.latency.snapshot()
+    }
+
+    ///release the resources owned by the task, under the task context to pass the owner check
+    pub(crate) fn release_resources(&self) {
+        rsm_timer::kill_timers_by_owner(&self.tid);
+        rsm_rpc::remove_requests_by_owner(&self.tid);
+        socket::socketpool::close_sockets_by_owner(&self.tid);
+    }
+
+    pub(crate) fn is_terminated(&self)->bool {
+        self.terminated
+    }
+
+    ///the task exits after panic and is not restarted
+    pub(crate) fn set_failed(&mut self) {
+        self.failed = true;
+        if let Some(q)=&mut self.recv_q {
+            while q.pop_front().is_some() {}
+        }
+    }
+
+    ///count the restarts in the window, return the restart count including current one
+    pub(crate) fn record_restart(&mut self,window_sec:u64)->usize {
+        let now = common::get_now_usec64();
+        self.restart_at.retain(|t| now-*t<window_sec*1000000);
+        self.restart_at.push(now);
+        return self.restart_at.len()
+    }
+
+    ///replace the task object with a new instance, the old boxed instance is dropped, and send the power on message to init it
+    pub(crate) fn restart(&mut self,task_obj:task_obj_t) {
+        self.task_obj = task_obj;
+        self.stats.restart_count+=1;
+        self.stats.cur_msg_id = RSM_INVALID_MESSAGE_ID;
+        self.powered_on = false;
+        let msg_id = if self.tid.inst_id==1 { RSM_MSG_ID_MASTER_POWER_ON } else { RSM_MSG_ID_SLAVE_POWER_ON };
+        self.send_asyn_priority_msg(rsm_message_t::new_sys_msg(msg_id));
     }
 
     pub fn get_task_stats(&self)->task_stats_t {
         let mut stats = self.stats.clone();
         if stats.cur_msg_id!=RSM_INVALID_MESSAGE_ID {
-            stats.cur_run_usec = common::get_now_usec64()-self.stats.last_run_at;
+            stats.cur_run_usec = common::get_now_usec64().saturating_sub(self.stats.last_run_at);
         } else {
             stats.cur_run_usec=0;
         }
@@ -181,7 +379,15 @@ impl task_t {
         }
     }
     pub fn clear_task_stats(&mut self) {
-        self.stats = task_stats_t::default();
+        //keep the running state, which is used by the watchdog
+        let stats = task_stats_t {
+            cur_msg_id:self.stats.cur_msg_id,
+            last_run_at:self.stats.last_run_at,
+            dispatch_count:self.stats.dispatch_count,
+            ..Default::default()
+        };
+        self.stats = stats;
+        self.latency.clear();
     }
 
     pub fn to_string(&self)->String {
@@ -194,4 +400,38 @@ impl task_t {
         self.stats.recv_msg,self.stats.recv_prio_msg,self.stats.drop_msg,self.stats.drop_prio_msg)
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    #[test]
+    fn test_task_latency_concurrent() {
+        let latency = Arc::new(task_latency_t::default());
+        let l = latency.clone();
+        let h = std::thread::spawn(move || {
+            for i in 0..100000u32 {
+                l.record(RSM_USER_MESSAGE_ID_START+i%64, Some(i as u64), (i%1000) as u64);
+            }
+        });
+        let mut max_ids = 0;
+        while !h.is_finished() {
+            let v = latency.snapshot();
+            max_ids = std::cmp::max(max_ids,v.len());
+            for (_,s) in v.iter() {
+                assert!(s.exec.count()>=s.wait.count());
+            }
+            latency.clear();
+        }
+        h.join().unwrap();
+        //the message ids exceed MAX_LATENCY_MSG_ID are counted under RSM_INVALID_MESSAGE_ID
+        assert!(max_ids<=MAX_LATENCY_MSG_ID+1);
+        latency.clear();
+        latency.record(RSM_USER_MESSAGE_ID_START, None, 10);
+        let v = latency.snapshot();
+        assert_eq!(v.len(),1);
+        assert_eq!(v[0].1.exec.count(),1);
+        assert_eq!(v[0].1.wait.count(),0);
+    }
+}
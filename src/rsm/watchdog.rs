@@ -0,0 +1,290 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+//rsm task watchdog, periodically check the running tasks,
+//raise an alarm if a task exceed its deadline budget or the message queue is not draining
+use super::*;
+use super::oam::E_RSM_OAM_OP;
+use crate::common::{errcode,spin_lock::spin_lock_t};
+use std::collections::{HashMap,VecDeque};
+use std::sync::atomic::{AtomicBool,Ordering};
+use std::thread;
+use std::time::Duration;
+
+const RSM_WATCHDOG_PERIOD_MSEC:u64 = 100;
+const MAX_ALARM_HISTORY:usize = 256;
+const MAX_ALARM_CALLBACK:usize = 16;
+const RSM_ALARM_URL:&str = "/alarm";
+
+///alarm type raised by the watchdog
+#[derive(Copy,Clone,PartialEq,Eq,Hash,Debug,Serialize)]
+pub enum E_RSM_ALARM_TYPE {
+    ///a message handler runs longer than the component deadline_usec
+    ALARM_DEADLINE_EXCEED = 1,
+    ///message queue is not empty, but no message is dispatched within queue_stall_msec
+    ALARM_QUEUE_STALLED = 2,
+}
+
+#[derive(Clone,Debug)]
+pub struct rsm_alarm_t {
+    pub alarm_id:u64,
+    pub tid:rsm_component_t,
+    pub alarm_type:E_RSM_ALARM_TYPE,
+    ///the message id being processed when the alarm is raised
+    pub msg_id:rsm_message_id_t,
+    ///run time in usec for the deadline alarm, queue length for the queue alarm
+    pub value:u64,
+    pub raised_at:u64,
+    ///0 if the alarm is still active
+    pub cleared_at:u64,
+}
+
+///alarm callback, called under the watchdog thread context when an alarm is raised or cleared
+pub type rsm_alarm_callback = fn(alarm:&rsm_alarm_t);
+
+struct watchdog_t {
+    active:HashMap<(rsm_component_t,E_RSM_ALARM_TYPE),rsm_alarm_t>,
+    history:VecDeque<rsm_alarm_t>,
+    //dispatch count and the time it last changed, used to detect the queue stall
+    progress:HashMap<rsm_component_t,(u64,u64)>,
+    //the overrun count seen by the last check, a handler finished within the check period is detected by the count
+    overruns:HashMap<rsm_component_t,u64>,
+    callbacks:Vec<rsm_alarm_callback>,
+    next_alarm_id:u64,
+    log:Option<xlog::xlogger_t>,
+    lock:spin_lock_t,
+}
+
+static mut gWatchdog:Option<watchdog_t>=None;
+static gWatchdogStopped:AtomicBool=AtomicBool::new(false);
+static mut gWatchdogThread:Option<thread::JoinHandle<()>>=None;
+
+pub(crate) fn init_watchdog() {
+    unsafe {
+        if gWatchdog.is_some() {
+            return
+        }
+        gWatchdog = Some(watchdog_t {
+            active:HashMap::new(),
+            history:VecDeque::with_capacity(MAX_ALARM_HISTORY),
+            progress:HashMap::new(),
+            overruns:HashMap::new(),
+            callbacks:Vec::new(),
+            next_alarm_id:1,
+            log:None,
+            lock:spin_lock_t::new(),
+        });
+    }
+    let urls = [RSM_ALARM_URL.to_string()];
+    oam::RegisterOamModule(&urls, process_alarm_oam);
+}
+
+pub(crate) fn start_watchdog() {
+    let wd = match unsafe {&mut gWatchdog} {
+        None=>return,
+        Some(w)=>w,
+    };
+    if wd.log.is_none() {
+        wd.log = Some(new_xlog("rsm_watchdog"));
+    }
+    gWatchdogStopped.store(false, Ordering::SeqCst);
+    let h = thread::spawn(|| watchdog_loop());
+    unsafe {
+        gWatchdogThread = Some(h);
+    }
+}
+
+pub(crate) fn stop_watchdog() {
+    gWatchdogStopped.store(true, Ordering::SeqCst);
+    if let Some(h) = unsafe { gWatchdogThread.take() } {
+        let _ = h.join();
+    }
+}
+
+///register an application callback, which is invoked when an alarm is raised or cleared
+pub(crate) fn register_alarm_callback(callback:rsm_alarm_callback)->errcode::RESULT {
+    let wd = match unsafe {&mut gWatchdog} {
+        None=>return errcode::ERROR_NOT_INITIALIZED,
+        Some(w)=>w,
+    };
+    wd.lock.lock();
+    if wd.callbacks.len()>=MAX_ALARM_CALLBACK {
+        wd.lock.unlock();
+        return errcode::ERROR_OUTOF_MEM
+    }
+    wd.callbacks.push(callback);
+    wd.lock.unlock();
+    errcode::RESULT_SUCCESS
+}
+
+///get the active alarms
+pub fn get_active_alarms()->Vec<rsm_alarm_t> {
+    let wd = match unsafe {&gWatchdog} {
+        None=>return Vec::new(),
+        Some(w)=>w,
+    };
+    wd.lock.lock();
+    let mut alarms:Vec<rsm_alarm_t> = wd.active.values().cloned().collect();
+    wd.lock.unlock();
+    alarms.sort_by_key(|a| a.alarm_id);
+    return alarms
+}
+
+fn watchdog_loop() {
+    let wd = match unsafe {&mut gWatchdog} {
+        None=>return,
+        Some(w)=>w,
+    };
+    while !gWatchdogStopped.load(Ordering::SeqCst) {
+        thread::sleep(Duration::from_millis(RSM_WATCHDOG_PERIOD_MSEC));
+        wd.check_tasks();
+    }
+}
+
+impl watchdog_t {
+    fn check_tasks(&mut self) {
+        let now = common::get_now_usec64();
+        let tasks = rsm_sched::get_all_tasks();
+        for tid in &tasks {
+            let reg = match rsm_sched::get_component_registry(tid.cid) {
+                None=>continue,
+                Some(c)=>c,
+            };
+            let policy = &reg.policy;
+            let stats = match rsm_sched::get_task_stats(tid) {
+                None=>continue,
+                Some(s)=>s,
+            };
+            let last_overruns = self.overruns.insert(tid.clone(), stats.overrun_count).unwrap_or(0);
+            if policy.deadline_usec>0 {
+                if stats.cur_msg_id!=RSM_INVALID_MESSAGE_ID && stats.cur_run_usec>policy.deadline_usec {
+                    self.update_alarm(tid, E_RSM_ALARM_TYPE::ALARM_DEADLINE_EXCEED, true, stats.cur_msg_id, stats.cur_run_usec, now);
+                } else {
+                    //the count is reset if the task statistics are cleared
+                    let exceed = stats.overrun_count>last_overruns;
+                    self.update_alarm(tid, E_RSM_ALARM_TYPE::ALARM_DEADLINE_EXCEED, exceed, stats.last_overrun_msg_id, stats.last_overrun_usec, now);
+                }
+            }
+
+            let (count,changed_at) = match self.progress.get(tid) {
+                None=>(stats.dispatch_count,now),
+                Some(p)=>if p.0==stats.dispatch_count { *p } else { (stats.dispatch_count,now) },
+            };
+            self.progress.insert(tid.clone(), (count,changed_at));
+            if policy.queue_stall_msec>0 {
+                let qlen = rsm_sched::get_task_qlen(tid);
+                let stalled = qlen>0 && now.saturating_sub(changed_at)>=policy.queue_stall_msec*1000;
+                self.update_alarm(tid, E_RSM_ALARM_TYPE::ALARM_QUEUE_STALLED, stalled, stats.cur_msg_id, qlen as u64, now);
+            }
+        }
+        //clear the alarms of the tasks which have been stopped
+        let stale:Vec<(rsm_component_t,E_RSM_ALARM_TYPE)> = self.active.keys().filter(|k| !tasks.contains(&k.0)).cloned().collect();
+        for k in stale {
+            self.update_alarm(&k.0, k.1, false, RSM_INVALID_MESSAGE_ID, 0, now);
+        }
+        self.progress.retain(|t,_| tasks.contains(t));
+        self.overruns.retain(|t,_| tasks.contains(t));
+    }
+
+    fn update_alarm(&mut self,tid:&rsm_component_t,alarm_type:E_RSM_ALARM_TYPE,raised:bool,msg_id:rsm_message_id_t,value:u64,now:u64) {
+        let key = (tid.clone(),alarm_type);
+        let is_active = self.active.contains_key(&key);
+        if raised==is_active {
+            return
+        }
+        let alarm = if raised {
+            let alarm = rsm_alarm_t {
+                alarm_id:self.next_alarm_id,
+                tid:tid.clone(),
+                alarm_type:alarm_type,
+                msg_id:msg_id,
+                value:value,
+                raised_at:now,
+                cleared_at:0,
+            };
+            self.next_alarm_id+=1;
+            self.lock.lock();
+            self.active.insert(key, alarm.clone());
+            self.lock.unlock();
+            self.log_alarm(&alarm, xlog::LOG_LEVEL_ERROR,"raised");
+            alarm
+        } else {
+            self.lock.lock();
+            let mut alarm = match self.active.remove(&key) {
+                None=> {
+                    self.lock.unlock();
+                    return
+                },
+                Some(a)=>a,
+            };
+            alarm.cleared_at = now;
+            if self.history.len()>=MAX_ALARM_HISTORY {
+                self.history.pop_front();
+            }
+            self.history.push_back(alarm.clone());
+            self.lock.unlock();
+            self.log_alarm(&alarm, xlog::LOG_LEVEL_WARNING,"cleared");
+            alarm
+        };
+        //the callbacks may be registered by the other threads, call them out of the lock
+        self.lock.lock();
+        let callbacks = self.callbacks.clone();
+        self.lock.unlock();
+        for cb in callbacks.iter() {
+            (cb)(&alarm);
+        }
+    }
+
+    fn log_alarm(&mut self,alarm:&rsm_alarm_t,level:xlog::LOG_LEVEL,action:&str) {
+        let desc = format!("alarm {},id={},task={}:{},type={:?},msg_id={},value={}",action,alarm.alarm_id,
+            alarm.tid.cid,alarm.tid.inst_id,alarm.alarm_type,alarm.msg_id,alarm.value);
+        if let Some(log) = &mut self.log {
+            if level==xlog::LOG_LEVEL_ERROR {
+                log.Errorf("[rsm_watchdog]", errcode::ERROR_THRESHOLD_EXCEED, &desc);
+            } else {
+                log.Warningf("[rsm_watchdog]", errcode::RESULT_SUCCESS, &desc);
+            }
+        }
+    }
+}
+
+fn format_alarm_time(usec:u64)->String {
+    if usec==0 {
+        return String::from("active")
+    }
+    return common::format_datetime(&common::get_time_from_usec(usec))
+}
+
+///list the active alarms and the cleared alarms
+fn process_alarm_oam(op:E_RSM_OAM_OP,_url:&String,_param:&String)->oam::oam_cmd_resp_t {
+    let mut tResp = oam::oam_cmd_resp_t::new(errcode::RESULT_SUCCESS,&String::default());
+    if op!=E_RSM_OAM_OP::CLI_OP_SHOW {
+        tResp.RetCode = errcode::ERROR_NOT_SUPPORT;
+        return tResp
+    }
+    let wd = match unsafe {&gWatchdog} {
+        None=> {
+            tResp.RetCode = errcode::ERROR_NOT_INITIALIZED;
+            return tResp
+        },
+        Some(w)=>w,
+    };
+    let mut alarms = get_active_alarms();
+    wd.lock.lock();
+    for a in wd.history.iter().rev() {
+        alarms.push(a.clone());
+    }
+    wd.lock.unlock();
+
+    let hdr = vec!["alarm_id".to_string(),"task".to_string(),"type".to_string(),"msg_id".to_string(),
+        "value".to_string(),"raised_at".to_string(),"cleared_at".to_string()];
+    tResp.set_row_hdr(hdr);
+    for a in alarms {
+        let row = vec![a.alarm_id.to_string(),format!("{}:{}",a.tid.cid,a.tid.inst_id),format!("{:?}",a.alarm_type),
+            a.msg_id.to_string(),a.value.to_string(),format_alarm_time(a.raised_at),format_alarm_time(a.cleared_at)];
+        tResp.add_row(&row);
+    }
+    return tResp
+}
@@ -0,0 +1,90 @@
+/* log_bridge.rs
+   模块功能描述：log facade的适配器，将第三方库通过log输出的日志转发给xlog服务端，
+以log记录的module path作为xlog的模块名称
+*/
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+use std::sync::Mutex;
+use crate::common::errcode;
+use super::*;
+
+///log_bridge_t, implements log::Log and forwards the records to the xlog server by an xlogger_t,
+/// the records above the log level of the xlogger_t are dropped.
+/// the server filters each module path by the default module levels, see xlog_server::set_module_log_level
+pub struct log_bridge_t {
+    logger: Mutex<xlogger_t>,
+}
+
+///map log::Level to LOG_LEVEL_*, Trace is mapped to LOG_LEVEL_DEBUG
+pub fn map_log_level(level: log::Level) -> LOG_LEVEL {
+    match level {
+        log::Level::Error => LOG_LEVEL_ERROR,
+        log::Level::Warn => LOG_LEVEL_WARNING,
+        log::Level::Info => LOG_LEVEL_INFO,
+        log::Level::Debug | log::Level::Trace => LOG_LEVEL_DEBUG,
+    }
+}
+
+fn to_level_filter(level: LOG_LEVEL) -> log::LevelFilter {
+    if level >= LOG_LEVEL_DEBUG {
+        log::LevelFilter::Trace
+    } else if level >= LOG_LEVEL_INFO {
+        log::LevelFilter::Info
+    } else if level >= LOG_LEVEL_WARNING {
+        log::LevelFilter::Warn
+    } else {
+        log::LevelFilter::Error
+    }
+}
+
+impl log_bridge_t {
+    pub fn new(logger: xlogger_t) -> Self {
+        return Self {
+            logger: Mutex::new(logger),
+        };
+    }
+}
+
+impl log::Log for log_bridge_t {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        match self.logger.lock() {
+            Err(_) => false,
+            Ok(l) => map_log_level(metadata.level()) <= l.level,
+        }
+    }
+
+    fn log(&self, record: &log::Record) {
+        let level = map_log_level(record.level());
+        let mut logger = match self.logger.lock() {
+            Err(_) => return,
+            Ok(l) => l,
+        };
+        if level > logger.level {
+            return;
+        }
+        let module = record.module_path().unwrap_or(record.target());
+        let position = match (record.file(), record.line()) {
+            (Some(f), Some(l)) => format!("{}:{}", f, l),
+            _ => String::default(),
+        };
+        let mut fields = Vec::new();
+        if record.target() != module {
+            fields.push(("target".to_string(), E_LOG_FIELD_VALUE::from(record.target())));
+        }
+        let desc = record.args().to_string();
+        logger.sentRecord(module, level, &position, errcode::RESULT_SUCCESS, &desc, fields);
+    }
+
+    fn flush(&self) {}
+}
+
+///install a log_bridge_t as the global logger of the log facade, the max level of the facade follows the level of the xlogger_t,
+/// ERROR_ALREADY_EXIST is returned if a global logger has been installed
+pub fn init_log_bridge(logger: xlogger_t) -> errcode::RESULT {
+    let max_level = to_level_filter(logger.level);
+    if log::set_boxed_logger(Box::new(log_bridge_t::new(logger))).is_err() {
+        return errcode::ERROR_ALREADY_EXIST;
+    }
+    log::set_max_level(max_level);
+    return errcode::RESULT_SUCCESS;
+}
@@ -11,14 +11,19 @@ use super::*;
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr,SocketAddr,UdpSocket};
 use crate::common::{self,tsmap::TsHashMap};
-use std::collections::{VecDeque};
+use std::collections::{HashMap,VecDeque};
 use std::io::{self,Read,Write};
 use std::fs;
+use std::sync::Mutex;
 use libdeflater;
 
 pub mod syslog;
 pub mod xlogger;
 pub mod xlog_server;
+#[cfg(feature="log-bridge")]
+pub mod log_bridge;
+#[cfg(feature="tracing-bridge")]
+pub mod tracing_bridge;
 
 pub type LOG_LEVEL = i32;
 
@@ -42,6 +47,88 @@ pub const LOG_DEF_CONSOLE_OUTPUT_LEVEL: LOG_LEVEL  = LOG_LEVEL_INFO;
 pub const LOG_DEF_SERVICE_PORT: u16 = 61000;
 pub const SYSLOG_DEF_UDP_PORT:u16=512;
 
+//RFC 5424 STRUCTURED-DATA的SD-ID，32473为RFC 5612保留给文档示例的企业编号
+const LOG_SD_ID:&str = "rsm@32473";
+const LOG_SD_MAX_NAME_LEN:usize = 32;
+
+///typed value of a structured log field
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub enum E_LOG_FIELD_VALUE {
+	LOG_STR(String),
+	LOG_INT(i64),
+	LOG_UINT(u64),
+	LOG_FLOAT(f64),
+	LOG_BOOL(bool),
+}
+
+impl E_LOG_FIELD_VALUE {
+	fn to_json(&self)->serde_json::Value {
+		match self {
+			Self::LOG_STR(s)=>serde_json::Value::from(s.clone()),
+			Self::LOG_INT(v)=>serde_json::Value::from(*v),
+			Self::LOG_UINT(v)=>serde_json::Value::from(*v),
+			Self::LOG_FLOAT(v)=>serde_json::Value::from(*v),
+			Self::LOG_BOOL(v)=>serde_json::Value::from(*v),
+		}
+	}
+}
+
+impl std::fmt::Display for E_LOG_FIELD_VALUE {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::LOG_STR(s)=>write!(f,"{}",s),
+			Self::LOG_INT(v)=>write!(f,"{}",v),
+			Self::LOG_UINT(v)=>write!(f,"{}",v),
+			Self::LOG_FLOAT(v)=>write!(f,"{}",v),
+			Self::LOG_BOOL(v)=>write!(f,"{}",v),
+		}
+	}
+}
+
+macro_rules! impl_log_field_from {
+	($variant:ident,$target:ty,$($t:ty),+) => {
+		$(impl From<$t> for E_LOG_FIELD_VALUE {
+			fn from(v:$t)->Self {
+				E_LOG_FIELD_VALUE::$variant(v as $target)
+			}
+		})+
+	};
+}
+impl_log_field_from!(LOG_INT,i64,i8,i16,i32,i64,isize);
+impl_log_field_from!(LOG_UINT,u64,u8,u16,u32,u64,usize);
+impl_log_field_from!(LOG_FLOAT,f64,f32,f64);
+
+impl From<bool> for E_LOG_FIELD_VALUE {
+	fn from(v:bool)->Self {
+		E_LOG_FIELD_VALUE::LOG_BOOL(v)
+	}
+}
+impl From<&str> for E_LOG_FIELD_VALUE {
+	fn from(v:&str)->Self {
+		E_LOG_FIELD_VALUE::LOG_STR(v.to_string())
+	}
+}
+impl From<String> for E_LOG_FIELD_VALUE {
+	fn from(v:String)->Self {
+		E_LOG_FIELD_VALUE::LOG_STR(v)
+	}
+}
+impl From<&String> for E_LOG_FIELD_VALUE {
+	fn from(v:&String)->Self {
+		E_LOG_FIELD_VALUE::LOG_STR(v.clone())
+	}
+}
+impl From<SocketAddr> for E_LOG_FIELD_VALUE {
+	fn from(v:SocketAddr)->Self {
+		E_LOG_FIELD_VALUE::LOG_STR(v.to_string())
+	}
+}
+impl From<IpAddr> for E_LOG_FIELD_VALUE {
+	fn from(v:IpAddr)->Self {
+		E_LOG_FIELD_VALUE::LOG_STR(v.to_string())
+	}
+}
+
 /*内部传递的Log数据结构*/
 #[derive(Clone, Serialize, Deserialize)]
 pub struct InnerLogMsg {
@@ -54,6 +141,12 @@ pub struct InnerLogMsg {
     ErrCode: errcode::RESULT,
     LogDesc: String,
     Context: String,
+	//结构化日志的键值对，旧版本的服务端忽略此字段
+	#[serde(default,skip_serializing_if = "Vec::is_empty")]
+	Fields: Vec<(String,E_LOG_FIELD_VALUE)>,
+	//客户端发送的序列号，从1开始，服务端据此统计丢失的日志，0表示旧版本的客户端
+	#[serde(default)]
+	Seq: u64,
 }
 
 ///Log listener, for application want to implement specific log storage & report function
@@ -69,10 +162,63 @@ pub (crate) fn LogFormat(msgStru:&InnerLogMsg, msgId:u64,sender:&SocketAddr)->St
         msgStru.OccureTime.to_string(),
 		sender.ip(), sender.port(), msgStru.ModuleName, msgStru.Position, msgId);
 
-	let strMsg = format!("{} ErrorCode=\"{}\" {} {}\n", strHdr,msgStru.ErrCode, msgStru.LogDesc, msgStru.Context);
+	let strMsg = format!("{}{} ErrorCode=\"{}\" {} {}\n", strHdr,format_structured_data(&msgStru.Fields),
+		msgStru.ErrCode, msgStru.LogDesc, msgStru.Context);
 	return strMsg
 }
 
+/*将键值对格式化为RFC 5424的SD-ELEMENT，如 [rsm@32473 peer="1.1.1.1:80" seq="3"]，没有键值对时返回空字符串*/
+fn format_structured_data(fields:&Vec<(String,E_LOG_FIELD_VALUE)>)->String {
+	if fields.is_empty() {
+		return String::default()
+	}
+	let mut sd = format!(" [{}",LOG_SD_ID);
+	//PARAM-NAME为1~32个可打印字符，不能包含'=',' ',']','"'
+	let is_name_char = |c:char| c.is_ascii_graphic() && c!='=' && c!=']' && c!='"';
+	for (i,(k,v)) in fields.iter().enumerate() {
+		let mut name:String = k.chars().take(LOG_SD_MAX_NAME_LEN).map(|c| if is_name_char(c) {c} else {'_'}).collect();
+		//键为空或者没有可用的字符时，以键值对的序号作为名称
+		if !k.chars().take(LOG_SD_MAX_NAME_LEN).any(is_name_char) {
+			name = format!("field{}",i+1);
+		}
+		//PARAM-VALUE中的'"','\\',']'需要转义
+		let mut value = String::new();
+		for c in v.to_string().chars() {
+			if c=='"' || c=='\\' || c==']' {
+				value.push('\\');
+			}
+			value.push(c);
+		}
+		sd += &format!(" {}=\"{}\"",name,value);
+	}
+	sd.push(']');
+	return sd
+}
+
+/*JSON Lines格式的日志，每条日志为一行JSON，便于机器处理*/
+pub (crate) fn LogFormatJson(msgStru:&InnerLogMsg, msgId:u64,sender:&SocketAddr)->String {
+	let mut fields = serde_json::Map::new();
+	for (k,v) in msgStru.Fields.iter() {
+		fields.insert(k.clone(), v.to_json());
+	}
+	let time = match msgStru.OccureTime.format(&time::format_description::well_known::Rfc3339) {
+		Ok(t)=>t,
+		Err(_)=>msgStru.OccureTime.to_string(),
+	};
+	let line = serde_json::json!({
+		"seq":msgId,
+		"time":time,
+		"level":msgStru.LogLevel,
+		"module":msgStru.ModuleName,
+		"addr":sender.to_string(),
+		"position":msgStru.Position,
+		"errcode":msgStru.ErrCode,
+		"msg":msgStru.LogDesc,
+		"fields":fields,
+	});
+	return format!("{}\n",line)
+}
+
 #[derive(Clone,Debug)]
 pub struct log_client_t {
 	persistent_log_level:LOG_LEVEL,
@@ -84,6 +230,23 @@ pub struct log_client_t {
 }
 
 const LOG_DEF_STORAGE_SIZE:u64=2*1024*1024;
+const LOG_DEF_FILE_SIZE:u64=1024*1024;
+const LOG_DEF_ROTATE_PERIOD:u64=24*3600;
+
+///format of the records written to the log file
+#[derive(Clone,Debug,PartialEq,Serialize,Deserialize)]
+pub enum E_LOG_FILE_FORMAT {
+	///RFC 5424 syslog text, the fields are written as STRUCTURED-DATA
+	LOG_FORMAT_SYSLOG,
+	///one JSON object per line
+	LOG_FORMAT_JSON_LINES,
+}
+
+impl Default for E_LOG_FILE_FORMAT {
+	fn default()->Self {
+		E_LOG_FILE_FORMAT::LOG_FORMAT_SYSLOG
+	}
+}
 const LOG_DEF_PATH:&str = "./";
 const LOG_DEF_PREFIX:&str = "rsm_xlog";
 
@@ -92,13 +255,32 @@ const LOG_DEF_PREFIX:&str = "rsm_xlog";
 pub struct log_service_config_t {
 	pub persistentLevel:LOG_LEVEL,
 	pub consoleLevel:LOG_LEVEL,
-	///max log file disk size, unit is bytes, if the stored log file exceed this value, then the file will be tuncated
+	///max disk usage of the current and archived log files, unit is bytes, the oldest archives are removed when it is exceeded
 	pub maxStorageSize:u64, 
+	///days the archived log files are kept, 0 means no limit
 	pub maxStoragePeriod:i32,  
     pub logFilePath:String,
 	pub logFilePrefix:String, 
     pub self_addr:SocketAddr,
 	pub syslog_server:Option<SocketAddr>,
+	///the current log file is rotated when it exceeds this size, unit is bytes, 0 means no size based rotation
+	#[serde(default="default_max_file_size")]
+	pub maxFileSize:u64,
+	///the current log file is rotated every rotatePeriod seconds, aligned to the local midnight, 0 means no time based rotation.
+	/// the default is one day
+	#[serde(default="default_rotate_period")]
+	pub rotatePeriod:u64,
+	///format of the log file, the console and the syslog server always receive the syslog text
+	#[serde(default)]
+	pub logFileFormat:E_LOG_FILE_FORMAT,
+}
+
+fn default_max_file_size()->u64 {
+	LOG_DEF_FILE_SIZE
+}
+
+fn default_rotate_period()->u64 {
+	LOG_DEF_ROTATE_PERIOD
 }
 impl log_service_config_t {
 	pub fn new_default()->Self {
@@ -106,7 +288,9 @@ impl log_service_config_t {
 		return Self { persistentLevel:LOG_LEVEL_ERROR, consoleLevel: LOG_LEVEL_ERROR, 
 			maxStorageSize: LOG_DEF_STORAGE_SIZE, maxStoragePeriod: 2, 
 			logFilePath:LOG_DEF_PATH.to_string(), logFilePrefix: LOG_DEF_PREFIX.to_string(), 
-			self_addr:def_addr, syslog_server: None 
+			self_addr:def_addr, syslog_server: None,
+			maxFileSize:LOG_DEF_FILE_SIZE, rotatePeriod:LOG_DEF_ROTATE_PERIOD,
+			logFileFormat:E_LOG_FILE_FORMAT::LOG_FORMAT_SYSLOG,
 		}
 	}
 }
@@ -116,6 +300,9 @@ pub struct log_service_t  {
     service_conf:log_service_config_t,
 	sck:UdpSocket,
 	curLogFile:Option<fs::File>, 
+	curLogFileName:String,
+	curFileSize:u64,
+	fileStat:Mutex<log_file_stat_t>, //OAM线程读取的当前文件状态
 	unSyncedMsg:i32,      
 	lastWriteTime:common::datetime_t,
 	queue:VecDeque<String>,
@@ -127,6 +314,38 @@ pub struct log_service_t  {
 	logSeq:u64, //Log Msg序列号
 	stdout:io::Stdout,
 	sys_client:Option<sys_log_client_t>,
+	logSenderSeq:TsHashMap<SocketAddr,log_sender_seq_t>, //以客户端地址索引的序列号统计
+}
+
+//当前日志文件的名称、大小和轮转次数，由日志线程更新，OAM线程在锁内读取
+#[derive(Clone,Debug,Default)]
+struct log_file_stat_t {
+	name:String,
+	size:u64,
+	rotated:u64,
+}
+
+//服务端统计的客户端序列号，lost为序列号的空洞，late为晚于后续序列号到达的日志
+#[derive(Clone,Debug,Default)]
+struct log_sender_seq_t {
+	last_seq:u64,
+	received:u64,
+	lost:u64,
+	late:u64,
+}
+
+///statistics of a log client
+#[derive(Clone,Debug,Default)]
+pub struct xlogger_stats_t {
+	pub sent_packets:u64,
+	pub sent_bytes:u64,
+	///the records dropped by the rate limit
+	pub suppressed:u64,
+	///the records waiting in the fallback buffer
+	pub buffered:u64,
+	///the records dropped because the fallback buffer is full, or not enabled when the server is unreachable
+	pub dropped:u64,
+	pub send_errors:u64,
 }
 
 ///Log client instance, should be created before using log function
@@ -139,6 +358,19 @@ pub struct xlogger_t {
     level: LOG_LEVEL,
     sentPackets: u64,
     sentbytes: u64,
+    seq: u64,
+    //每个模块的令牌桶，rate_limit为0表示不限速
+    rate_limit: u32,
+    rate_burst: u32,
+    buckets: HashMap<String, xlogger::token_bucket_t>,
+    suppressed: u64,
+    //服务端不可达时缓存的日志，保留原始的序列号
+    fallback: VecDeque<Vec<u8>>,
+    fallback_bytes: usize,
+    fallback_max_bytes: usize,
+    retry_time: Option<std::time::Instant>,
+    dropped: u64,
+    sendErrors: u64,
 }
 
 
@@ -159,26 +391,95 @@ pub(crate) fn compressFile(fileIn:&String, fileOut:&String)->errcode::RESULT {
 	};
 	let complvl=libdeflater::CompressionLvl::default();
 	let mut comp =libdeflater::Compressor::new(complvl);
-	comp.gzip_compress_bound(stats.len() as usize);
 	let mut vec_buf_in = Vec::with_capacity(stats.len() as usize);
-	let mut vec_buf_out = Vec::with_capacity(stats.len() as usize);
-	unsafe {
-		vec_buf_in.set_len(stats.len() as usize);
-		vec_buf_out.set_len(stats.len() as usize);
-	}
-	let n_bytes = match fp1.read(vec_buf_in.as_mut_slice()) {
+	let n_bytes = match fp1.read_to_end(&mut vec_buf_in) {
 		Err(_)=>return errcode::ERROR_BUFFER_TOO_SMALL,
 		Ok(l)=>l,
 	};
+	//压缩后的数据可能比原始数据大，按照gzip的上限分配输出缓冲区
+	let mut vec_buf_out = vec![0u8;comp.gzip_compress_bound(n_bytes)];
 	let comp_len = match comp.gzip_compress(&vec_buf_in.as_slice()[0..n_bytes], vec_buf_out.as_mut_slice()) {
 		Err(_)=>return errcode::ERROR_BUFFER_TOO_SMALL,
 		Ok(l)=>l,		
 	};
 
-	fp2.write(&vec_buf_out.as_slice()[0..comp_len]);
-	fp2.flush();
+	if fp2.write_all(&vec_buf_out.as_slice()[0..comp_len]).is_err() || fp2.flush().is_err() {
+		return errcode::ERROR_OPEN_FILE
+	}
 
 	return errcode::RESULT_SUCCESS
 
 }
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn new_test_msg(fields:Vec<(String,E_LOG_FIELD_VALUE)>)->InnerLogMsg {
+		return InnerLogMsg {
+			ModuleName:"app".to_string(),
+			OccureTime:common::rsm_time_t::UNIX_EPOCH,
+			LogType:LOG_TYPE_SYSTEM,
+			LogLevel:LOG_LEVEL_WARNING,
+			Position:"main.rs:10".to_string(),
+			ErrCode:errcode::ERROR_TIME_OUT,
+			LogDesc:"connect failed".to_string(),
+			Context:"Null".to_string(),
+			Fields:fields,
+			Seq:1,
+		}
+	}
+
+	#[test]
+	fn test_structured_data() {
+		assert_eq!(format_structured_data(&Vec::new()), "");
+		let fields:Vec<(String,E_LOG_FIELD_VALUE)> = vec![
+			("peer".to_string(),"1.1.1.1:80".into()),
+			("seq".to_string(),3u32.into()),
+			//PARAM-VALUE转义'"','\\',']'
+			("path".to_string(),r#"a"b\c]d"#.into()),
+			//PARAM-NAME中不允许的字符被替换，超过32个字符的被截断
+			("a b=c]d\"e".to_string(),true.into()),
+			("k".repeat(40),1.5f64.into()),
+		];
+		let sd = format_structured_data(&fields);
+		assert_eq!(sd, format!(r#" [{} peer="1.1.1.1:80" seq="3" path="a\"b\\c\]d" a_b_c_d_e="true" {}="1.5"]"#,LOG_SD_ID,"k".repeat(32)));
+	}
+
+	#[test]
+	fn test_structured_data_invalid_name() {
+		let fields:Vec<(String,E_LOG_FIELD_VALUE)> = vec![
+			("".to_string(),1i32.into()),
+			("ok".to_string(),2i32.into()),
+			(" \t\n".to_string(),3i32.into()),
+			("中文".to_string(),4i32.into()),
+		];
+		let sd = format_structured_data(&fields);
+		assert_eq!(sd, format!(r#" [{} field1="1" ok="2" field3="3" field4="4"]"#,LOG_SD_ID));
+	}
+
+	#[test]
+	fn test_log_format_json() {
+		let sender = SocketAddr::new(IpAddr::from([127,0,0,1]),16000);
+		let msg = new_test_msg(vec![("peer".to_string(),"1.1.1.1:80".into()),("seq".to_string(),(-3i32).into()),
+			("ratio".to_string(),0.5f64.into()),("ok".to_string(),true.into()),("quote".to_string(),"a\"b\n".into())]);
+		let line = LogFormatJson(&msg, 7, &sender);
+		//每条日志为一行
+		assert!(line.ends_with('\n'));
+		assert_eq!(line.matches('\n').count(), 1);
+		let v:serde_json::Value = serde_json::from_str(&line).unwrap();
+		assert_eq!(v["seq"], 7);
+		assert_eq!(v["time"], "1970-01-01T00:00:00Z");
+		assert_eq!(v["level"], LOG_LEVEL_WARNING);
+		assert_eq!(v["module"], "app");
+		assert_eq!(v["addr"], "127.0.0.1:16000");
+		assert_eq!(v["position"], "main.rs:10");
+		assert_eq!(v["errcode"], errcode::ERROR_TIME_OUT);
+		assert_eq!(v["msg"], "connect failed");
+		//字段保留原始的类型
+		assert_eq!(v["fields"], serde_json::json!({"peer":"1.1.1.1:80","seq":-3,"ratio":0.5,"ok":true,"quote":"a\"b\n"}));
+
+		let v:serde_json::Value = serde_json::from_str(&LogFormatJson(&new_test_msg(Vec::new()), 8, &sender)).unwrap();
+		assert_eq!(v["fields"], serde_json::json!({}));
+	}
+}
@@ -0,0 +1,112 @@
+/* tracing_bridge.rs
+   模块功能描述：tracing的Layer，将tracing的event转发给xlog服务端，event的字段作为结构化日志的键值对，
+以event的module path作为xlog的模块名称
+*/
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+use std::fmt;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use crate::common::errcode;
+use super::*;
+
+//tracing的宏将格式化的消息记录在名为message的字段中
+const TRACING_MESSAGE_FIELD: &str = "message";
+
+///tracing_layer_t, a tracing_subscriber Layer forwarding the events to the xlog server by an xlogger_t,
+/// e.g. tracing_subscriber::registry().with(tracing_layer_t::new(rsm::new_xlog("app"))).init().
+/// the server filters each module path by the default module levels, see xlog_server::set_module_log_level
+pub struct tracing_layer_t {
+    logger: Mutex<xlogger_t>,
+}
+
+///map tracing::Level to LOG_LEVEL_*, TRACE is mapped to LOG_LEVEL_DEBUG
+pub fn map_tracing_level(level: &tracing::Level) -> LOG_LEVEL {
+    match *level {
+        tracing::Level::ERROR => LOG_LEVEL_ERROR,
+        tracing::Level::WARN => LOG_LEVEL_WARNING,
+        tracing::Level::INFO => LOG_LEVEL_INFO,
+        _ => LOG_LEVEL_DEBUG,
+    }
+}
+
+impl tracing_layer_t {
+    pub fn new(logger: xlogger_t) -> Self {
+        return Self {
+            logger: Mutex::new(logger),
+        };
+    }
+}
+
+//收集event的字段，message字段作为日志的描述
+struct field_visitor_t {
+    desc: String,
+    fields: Vec<(String, E_LOG_FIELD_VALUE)>,
+}
+
+impl field_visitor_t {
+    fn add(&mut self, field: &Field, value: E_LOG_FIELD_VALUE) {
+        self.fields.push((field.name().to_string(), value));
+    }
+}
+
+impl Visit for field_visitor_t {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.add(field, E_LOG_FIELD_VALUE::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.add(field, E_LOG_FIELD_VALUE::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.add(field, E_LOG_FIELD_VALUE::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.add(field, E_LOG_FIELD_VALUE::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == TRACING_MESSAGE_FIELD {
+            self.desc = value.to_string();
+        } else {
+            self.add(field, E_LOG_FIELD_VALUE::from(value));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == TRACING_MESSAGE_FIELD {
+            self.desc = format!("{:?}", value);
+        } else {
+            self.add(field, E_LOG_FIELD_VALUE::from(format!("{:?}", value)));
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for tracing_layer_t {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = map_tracing_level(metadata.level());
+        let mut logger = match self.logger.lock() {
+            Err(_) => return,
+            Ok(l) => l,
+        };
+        if level > logger.level {
+            return;
+        }
+        let mut visitor = field_visitor_t {
+            desc: String::default(),
+            fields: Vec::new(),
+        };
+        event.record(&mut visitor);
+        let module = metadata.module_path().unwrap_or(metadata.target());
+        let position = match (metadata.file(), metadata.line()) {
+            (Some(f), Some(l)) => format!("{}:{}", f, l),
+            _ => String::default(),
+        };
+        logger.sentRecord(module, level, &position, errcode::RESULT_SUCCESS, &visitor.desc, visitor.fields);
+    }
+}
@@ -11,13 +11,33 @@ use std::fs::{self,OpenOptions};
 use std::collections::{VecDeque};
 use std::io::{self,Read,Write};
 use std::thread;
+use std::sync::atomic::{AtomicBool,Ordering};
+use std::sync::{mpsc,Mutex};
+use crate::rsm::oam::{self,E_RSM_OAM_OP};
 
 const  MAX_LOG_QUEUE_LEN:usize    = 2048;
 const  MAX_LOG_MSG_LEN:usize      = 65000;
 const  MAX_OOB_MSG_LEN:usize      = 4096;
 const  MAX_UNSYNCED_MSG:i32     = 256;
 const  LOG_SYNC_DISK_PERIOD:u64 = 2; //刷新到磁盘的周期
-const MAX_LOG_FILE:usize = 365;
+const SECS_PER_DAY:u64 = 24*3600;
+const LOG_ARCHIVE_SUFFIX:&str = ".gz";
+//旧版本清理任务生成的压缩文件
+const LOG_LEGACY_ARCHIVE_SUFFIX:&str = ".zip";
+const RSM_LOG_URL:&str = "/log";
+const RSM_LOG_FILE_URL:&str = "/log/file";
+//OAM设置全局日志级别时使用的模块名称
+const LOG_GLOBAL_MODULE_NAME:&str = "*";
+//OAM线程等待日志线程修改日志级别的时间
+const LOG_LEVEL_CHANGE_TIMEOUT:Duration = Duration::from_secs(1);
+const MAX_OAM_LOG_FILE_ROWS:usize = 1000;
+const MAX_LOG_SENDERS:usize = 4096; //统计序列号的客户端数量上限
+//序列号回退超过该值，认为客户端在相同地址端口上重启，重新开始统计
+const LOG_SEQ_RESTART_GAP:u64 = 1024;
+
+const LOG_FILE_CURRENT:&str = "current";
+const LOG_FILE_ROTATED:&str = "rotated";
+const LOG_FILE_ARCHIVE:&str = "archive";
 
 impl log_service_t {
     pub fn new(conf:&log_service_config_t)->Option<log_service_t> {
@@ -30,6 +50,9 @@ impl log_service_t {
             service_conf:conf.clone(),
             sck:sck,
             curLogFile:None,
+            curLogFileName:String::default(),
+            curFileSize:0,
+            fileStat:Mutex::new(log_file_stat_t::default()),
             unSyncedMsg:0,      //已经写入文件，但是没有存盘的消息计数
             lastWriteTime:common::get_datetime_from_std(&SystemTime::now()),
             queue:VecDeque::with_capacity(MAX_LOG_QUEUE_LEN),
@@ -41,6 +64,7 @@ impl log_service_t {
             logSeq:1,
 			stdout:io::stdout(),
 			sys_client:None,
+			logSenderSeq:TsHashMap::new(128),
         };
 		if let Some(syslog_addr) = conf.syslog_server {
 			if let Ok(mut syslog)=syslog::sys_log_client_t::new(&conf.self_addr) {
@@ -65,10 +89,99 @@ impl log_service_t {
 				return errcode::ERROR_OPEN_FILE;
 			},
         };
+		self.curFileSize = 0;
+		/*继续写已有的文件时，以文件的修改时间作为最后写入时间，以便判断是否需要轮转*/
+		if let Ok(m) = fd.metadata() {
+			self.curFileSize = m.len();
+			if let (true,Ok(t)) = (m.len()>0, m.modified()) {
+				self.lastWriteTime = common::get_datetime_from_std(&t);
+			}
+		}
         self.curLogFile=Some(fd);
+		self.curLogFileName=fileName;
+		self.update_file_stat(false);
         return errcode::RESULT_SUCCESS
     }
 
+	/*更新OAM读取的文件状态，文件名称和大小只在日志线程中修改*/
+	fn update_file_stat(&self,rotated:bool) {
+		if let Ok(mut stat) = self.fileStat.lock() {
+			if stat.name!=self.curLogFileName {
+				stat.name = self.curLogFileName.clone();
+			}
+			stat.size = self.curFileSize;
+			if rotated {
+				stat.rotated+=1;
+			}
+		}
+	}
+
+	/*返回轮转周期的编号，周期以本地时间的零点对齐*/
+	fn get_rotate_slot(&self,tm:&common::datetime_t)->u64 {
+		let period = self.service_conf.rotatePeriod;
+		let secs_in_day = tm.get_hour_in_day() as u64*3600+tm.get_min_in_hour() as u64*60+tm.get_secs_in_min() as u64;
+		let day_start = (tm.to_usecs()/1000000).saturating_sub(secs_in_day);
+		if period>=SECS_PER_DAY {
+			return (day_start/SECS_PER_DAY)/(period/SECS_PER_DAY)
+		}
+		return day_start+secs_in_day/period*period
+	}
+
+	fn need_rotate(&self,cur:&common::datetime_t,msg_len:usize)->bool {
+		if self.curLogFile.is_none() {
+			return false
+		}
+		if self.service_conf.maxFileSize>0 && self.curFileSize>0 &&
+			self.curFileSize+msg_len as u64>self.service_conf.maxFileSize {
+			return true
+		}
+		if self.service_conf.rotatePeriod>0 && self.curFileSize>0 &&
+			self.get_rotate_slot(cur)!=self.get_rotate_slot(&self.lastWriteTime) {
+			return true
+		}
+		return false
+	}
+
+	/*轮转后的文件名称，一般为prefix+"_"+YYYYMMDD_HHMMSS+".log"，时间为文件中最后一条日志的时间*/
+	fn getRotatedFileName(&self,tm:&common::datetime_t)->String {
+		let stem = format!("{}{}_{:04}{:02}{:02}_{:02}{:02}{:02}", self.service_conf.logFilePath, self.service_conf.logFilePrefix,
+			tm.get_year(), tm.get_mon_in_year(), tm.get_day_in_mon(), tm.get_hour_in_day(), tm.get_min_in_hour(), tm.get_secs_in_min());
+		let mut fileName = format!("{}.log",stem);
+		let mut seq = 1;
+		while fs::metadata(&fileName).is_ok() || fs::metadata(format!("{}{}",fileName,LOG_ARCHIVE_SUFFIX)).is_ok() {
+			fileName = format!("{}_{}.log",stem,seq);
+			seq+=1;
+		}
+		return fileName
+	}
+
+	/*轮转当前日志文件，然后打开新的日志文件，轮转后的文件由后台线程压缩*/
+	fn rotateLogFile(&mut self)->errcode::RESULT {
+		self.forceSyncLogFile();
+		self.curLogFile=None;
+		let rotated = self.getRotatedFileName(&self.lastWriteTime);
+		match fs::rename(&self.curLogFileName, &rotated) {
+			Ok(_)=>self.update_file_stat(true),
+			Err(e)=>println!("LogServer: rotate log file {} error,err={}",self.curLogFileName,e),
+		}
+		let ret = self.openLogFile();
+
+		let conf = self.service_conf.clone();
+		thread::spawn(move || clean_log_files(&conf));
+		return ret
+	}
+
+	/*启动时将以前遗留的按天命名的日志文件改为轮转后的名称，由清理任务压缩*/
+	fn rotateStaleLogFiles(&mut self) {
+		for f in list_log_files(&self.service_conf, &self.curLogFileName) {
+			if f.kind!=LOG_FILE_ROTATED || is_rotated_file_name(&self.service_conf, &f.name) {
+				continue
+			}
+			let rotated = self.getRotatedFileName(&common::get_datetime_from_std(&f.modified));
+			let _ = fs::rename(&f.path, &rotated);
+		}
+	}
+
     /*给定一个时间，返回一个规整的日志文件名称，一般为prefix+"_"+YYYYMMDD+".log"*/
     fn getLogFileName(&self,tm:&common::datetime_t)->String {
 	    let fileName = format!("{}_{:#04}{:#02}{:#02}.log", self.service_conf.logFilePrefix, 
@@ -195,8 +308,15 @@ impl log_service_t {
 		let _ = self.sck.set_read_timeout(Some(Duration::from_millis(50)));
 		let mut recv_buf=[0u8;MAX_LOG_MSG_LEN];
 		loop {
+			self.apply_log_level_changes();
 			let (len,addr)=match self.sck.recv_from(&mut recv_buf[..]) {
-				Err(_)=>continue,
+				Err(_)=> {
+					//all the buffered log has been received, exit if the service is stopping
+					if gLogServiceStopped.load(Ordering::SeqCst) {
+						break;
+					}
+					continue
+				},
 				Ok((l,a))=>(l,a),
 			};
 			//println!("log server recv message from {},len={},msg = {}",addr,len,String::from_utf8_lossy(&recv_buf[0..len]));
@@ -216,13 +336,41 @@ impl log_service_t {
 	}
 
 	/*内部汇总输出日志的函数*/
-	fn innerOutputLog(&mut self,msg:&InnerLogMsg, m:&mut log_client_t,formated_msg:&String) {		
-		self.persistentLog(msg,m, formated_msg); //首先进行持久化处理
+	fn innerOutputLog(&mut self,msg:&InnerLogMsg, m:&mut log_client_t,formated_msg:&String,seq:u64,sender:&SocketAddr) {		
+		self.persistentLog(msg,m, formated_msg,seq,sender); //首先进行持久化处理
 		self.consoleOutputLog(msg, m, formated_msg);    //然后进行控制台处理
 	}
 
 	/*真正的处理Log日志的任务，初始化日志实例时创建线程任务运行*/
+	/*根据客户端的序列号统计丢失和乱序的日志，序列号为0的旧版本客户端不统计*/
+	fn updateSenderSeq(&mut self,sender:&SocketAddr,seq:u64) {
+		if seq==0 {
+			return
+		}
+		if let Some(s) = self.logSenderSeq.get_mut(sender) {
+			if seq==1 || seq+LOG_SEQ_RESTART_GAP<s.last_seq {
+				*s=log_sender_seq_t { last_seq:seq, received:1, lost:seq-1, late:0 };
+				return
+			}
+			s.received+=1;
+			if seq>s.last_seq {
+				s.lost+=seq-s.last_seq-1;
+				s.last_seq=seq;
+			} else {
+				//晚到的日志填补了之前统计的空洞
+				s.late+=1;
+				s.lost=s.lost.saturating_sub(1);
+			}
+			return
+		}
+		if self.logSenderSeq.len()>=MAX_LOG_SENDERS {
+			return
+		}
+		self.logSenderSeq.insert(sender.clone(),log_sender_seq_t { last_seq:seq, received:1, lost:seq-1, late:0 });
+	}
+
 	fn processLog(&mut self,msg:&InnerLogMsg,origin_msg:&[u8],sender:&SocketAddr) {
+			self.updateSenderSeq(sender, msg.Seq);
 			self.logSeq+=1;
 			let ret = self.processListener(msg, self.logSeq);
 			if !ret {
@@ -241,7 +389,7 @@ impl log_service_t {
 			/*输出Log文件*/
 			let c= unsafe {&mut *(client as *mut log_client_t)};
 			let strMsg = LogFormat(msg,seq,sender);
-			self.innerOutputLog(msg, c,&strMsg);
+			self.innerOutputLog(msg, c,&strMsg,seq,sender);
 	}
 
 
@@ -262,18 +410,27 @@ impl log_service_t {
 	}
 
 /*处理日志持久化流程，首先判断级别是否够*/
-fn persistentLog(&mut self,msgStru:&InnerLogMsg, m:&log_client_t, formated_msg:&String)->errcode::RESULT {
+fn persistentLog(&mut self,msgStru:&InnerLogMsg, m:&log_client_t, formated_msg:&String,seq:u64,sender:&SocketAddr)->errcode::RESULT {
 	if msgStru.LogLevel > self.service_conf.persistentLevel || msgStru.LogLevel > m.persistent_log_level {
 		return errcode::ERROR_NO_OP;
 	}
+	let json_msg;
+	let formated_msg = match self.service_conf.logFileFormat {
+		E_LOG_FILE_FORMAT::LOG_FORMAT_SYSLOG=>formated_msg,
+		E_LOG_FILE_FORMAT::LOG_FORMAT_JSON_LINES=> {
+			json_msg = LogFormatJson(msgStru, seq, sender);
+			&json_msg
+		},
+	};
 
 	let cur =common::get_datetime_from_std(&time::SystemTime::now());
-	let last = self.lastWriteTime.clone();
-	/*已经是另外一天，需要打开新的日志文件*/
-	if (cur.get_day_in_year() != last.get_day_in_year()) || (cur.get_year()!= last.get_year()) {
-		self.forceSyncLogFile();
-
-		/*自动根据当前日期计算一个新的日志文件*/
+	/*超过文件大小或者进入新的轮转周期，需要轮转日志文件*/
+	if self.need_rotate(&cur, formated_msg.len()) {
+		let err = self.rotateLogFile();
+		if err != errcode::RESULT_SUCCESS {
+			return err
+		}
+	} else if self.curLogFile.is_none() {
 		let err = self.openLogFile();
 		if err != errcode::RESULT_SUCCESS {
 			return err
@@ -284,13 +441,16 @@ fn persistentLog(&mut self,msgStru:&InnerLogMsg, m:&log_client_t, formated_msg:&
 	self.unSyncedMsg+=1;
 	//println!("[log server]write to disk,seq={},unsynced={},msg_len={}",self.logSeq,self.unSyncedMsg,formated_msg.len());
 	self.flushLogFile();
-	self.lastWriteTime = common::get_datetime_from_std(&time::SystemTime::now());
+	self.lastWriteTime = cur;
 	return errcode::RESULT_SUCCESS
 }
 
 	fn write_to_file(&mut self,buf:&[u8])->errcode::RESULT {
 		if let Some(ref mut f) = &mut self.curLogFile {
-			let _ = f.write(buf);
+			if let Ok(l) = f.write(buf) {
+				self.curFileSize+=l as u64;
+				self.update_file_stat(false);
+			}
 				return errcode::RESULT_SUCCESS
 		} else {
 			return errcode::ERROR_OPEN_FILE;
@@ -298,17 +458,10 @@ fn persistentLog(&mut self,msgStru:&InnerLogMsg, m:&log_client_t, formated_msg:&
 	}
 /*强制刷新到磁盘文件，将未同步的日志文件，刷新到磁盘*/
 fn forceSyncLogFile(&mut self) {
-	/*假如当前日志超过了存盘的最大尺寸限制，则截断日志大小*/
 	let file=match &mut self.curLogFile {
 		None=>return,
 		Some(f)=>f,
 	};
-	if let Ok(stats) = file.metadata() {
-		if stats.len()>self.service_conf.maxStorageSize {
-			let _ = file.set_len(self.service_conf.maxStorageSize);
-					
-		}
-	}
 	let _ = file.sync_all();
 	self.unSyncedMsg = 0;
 
@@ -349,37 +502,125 @@ fn forceSyncLogFile(&mut self) {
 
 	
 	fn do_clean(&mut self) {
-		let cur = time::SystemTime::now();
-		let mut toBeClean=false;
-		let mut total_file_size =0u64;
 		println!("[LogClean Task]Begin Log Clean Task");
-		for i in 1..MAX_LOG_FILE {
-
-			let tm = common::get_datetime_from_std(&cur.checked_sub(Duration::from_secs(3600*24*i as u64)).unwrap());
-			let f1 = self.getLogFileName(&tm);
-			let f2 = format!("{}{}",self.GetLogFilePath(),f1);
-			let ziped = format!("{}{}",f2, ".zip");
-			if toBeClean {
-				let _ = fs::remove_file(ziped);
-				let _ = fs::remove_file(f2);
-				continue
+		clean_log_files(&self.service_conf);
+	}
+
+	/*OAM查询全局和模块的日志级别以及收到的日志统计，name为空表示所有模块*/
+	fn get_log_level_oam(&self,name:&str)->oam::oam_cmd_resp_t {
+		let mut tResp = oam::oam_cmd_resp_t::new(errcode::RESULT_SUCCESS,&String::default());
+		tResp.set_row_hdr(vec!["persistent_level".to_string(),"console_level".to_string(),"recv_packets".to_string(),
+			"recv_bytes".to_string(),"log_seq".to_string(),"queue_len".to_string()]);
+		tResp.add_row(&vec![self.service_conf.persistentLevel.to_string(),self.service_conf.consoleLevel.to_string(),
+			self.logPackets.to_string(),self.LogBytes.to_string(),self.logSeq.to_string(),self.queue.len().to_string()]);
+
+		tResp.set_row_hdr(vec!["module".to_string(),"addr".to_string(),"persistent_level".to_string(),"console_level".to_string(),
+			"recv_packets".to_string(),"recv_bytes".to_string()]);
+		let mut rows = Vec::new();
+		for (_, v) in self.logMuduleControl.iter() {
+			if name.is_empty() || v.name.eq_ignore_ascii_case(name) {
+				rows.push(vec![v.name.clone(),v.addr.to_string(),v.persistent_log_level.to_string(),v.console_log_level.to_string(),
+					v.logPackets.to_string(),v.logBytes.to_string()]);
 			}
-			
-			if errcode::RESULT_SUCCESS == compressFile(&f2, &ziped) {
-					let _ = fs::remove_file(f2);
-			};
-			
-
-		match fs::metadata(ziped) {
-			Err(_)=>continue,
-			Ok(m)=>{
-				total_file_size+=m.len();
-				if total_file_size>self.service_conf.maxStorageSize || i>self.service_conf.maxStoragePeriod as usize {
-					toBeClean = true;
+		}
+		self.logMuduleControl.end_iter();
+		if !name.is_empty() && rows.is_empty() {
+			tResp.RetCode = errcode::ERROR_NOT_FOUND;
+			return tResp
+		}
+		rows.sort();
+		for r in rows.iter() {
+			tResp.add_row(r);
+		}
+		if !name.is_empty() {
+			return tResp
+		}
+
+		tResp.set_row_hdr(vec!["client".to_string(),"last_seq".to_string(),"received".to_string(),"lost".to_string(),"late".to_string()]);
+		let mut rows = Vec::new();
+		for (k, v) in self.logSenderSeq.iter() {
+			rows.push(vec![k.to_string(),v.last_seq.to_string(),v.received.to_string(),v.lost.to_string(),v.late.to_string()]);
+		}
+		self.logSenderSeq.end_iter();
+		rows.sort();
+		for r in rows.iter() {
+			tResp.add_row(r);
+		}
+		return tResp
+	}
+
+	/*修改全局或者模块的日志级别，只在日志线程中调用，返回修改的模块名称，全局级别返回空字符串；
+	create为true时按照名称精确匹配，模块不存在则预先创建*/
+	fn set_log_level(&mut self,module:&str,persistent:Option<LOG_LEVEL>,console:Option<LOG_LEVEL>,create:bool)->Result<String,errcode::RESULT> {
+		if module==LOG_GLOBAL_MODULE_NAME {
+			if let Some(l) = persistent {
+				self.SetGlobalPersitentLogLevel(l);
+			}
+			if let Some(l) = console {
+				self.SetGlobalConsoleLogLevel(l);
+			}
+			return Ok(String::default())
+		}
+		//OAM的url被转换为小写，按照不区分大小写匹配模块名称
+		let mut name = None;
+		if create {
+			name = Some(module.to_string());
+		} else {
+			for (k, _) in self.logMuduleControl.iter() {
+				if k.eq_ignore_ascii_case(module) {
+					name = Some(k.clone());
 				}
+			}
+			self.logMuduleControl.end_iter();
+		}
+		let name = match name {
+			None=>return Err(errcode::ERROR_NOT_FOUND),
+			Some(n)=>n,
+		};
+		if let Some(l) = persistent {
+			self.SetModulePersitentLogLevel(&name, l);
+		}
+		if let Some(l) = console {
+			self.SetModuleConsoleLogLevel(&name, l);
+		}
+		return Ok(name)
+	}
+
+	/*执行OAM线程提交的日志级别修改，并将结果返回给等待的OAM线程*/
+	fn apply_log_level_changes(&mut self) {
+		let changes = match gLogLevelChanges.lock() {
+			Err(_)=>return,
+			Ok(mut q)=> {
+				if q.is_empty() {
+					return
+				}
+				std::mem::take(&mut *q)
 			},
+		};
+		for c in changes {
+			let _ = c.resp.send(self.set_log_level(&c.module, c.persistent, c.console, c.create));
 		}
+	}
+
+	fn get_log_file_oam(&self)->oam::oam_cmd_resp_t {
+		let mut tResp = oam::oam_cmd_resp_t::new(errcode::RESULT_SUCCESS,&String::default());
+		let stat = match self.fileStat.lock() {
+			Err(_)=>return oam::oam_cmd_resp_t::new(errcode::ERROR_LOCK_FAILED,&String::default()),
+			Ok(s)=>s.clone(),
+		};
+		let files = list_log_files(&self.service_conf, &stat.name);
+		let total_size:u64 = files.iter().map(|f| f.size).sum();
+		tResp.set_row_hdr(vec!["current_file".to_string(),"file_size".to_string(),"max_file_size".to_string(),"rotate_period".to_string(),
+			"rotated_files".to_string(),"total_size".to_string(),"max_storage_size".to_string(),"max_storage_days".to_string()]);
+		tResp.add_row(&vec![stat.name.clone(),stat.size.to_string(),self.service_conf.maxFileSize.to_string(),
+			self.service_conf.rotatePeriod.to_string(),stat.rotated.to_string(),total_size.to_string(),
+			self.service_conf.maxStorageSize.to_string(),self.service_conf.maxStoragePeriod.to_string()]);
+
+		tResp.set_row_hdr(vec!["file".to_string(),"kind".to_string(),"size".to_string(),"modified".to_string()]);
+		for f in files.iter().take(MAX_OAM_LOG_FILE_ROWS) {
+			tResp.add_row(&vec![f.name.clone(),f.kind.to_string(),f.size.to_string(),common::format_datetime(&f.modified)]);
 		}
+		return tResp
 	}
 
     pub fn PrintLogServiceStats(&self) {
@@ -407,6 +648,8 @@ fn forceSyncLogFile(&mut self) {
 }
 
 static mut gLogServer:Option<log_service_t>=None;
+static gLogServiceStopped:AtomicBool=AtomicBool::new(false);
+static mut gLogServiceThread:Option<thread::JoinHandle<()>>=None;
 /*init log service,parameter is log_service_config_t*/
 pub fn InitLogService(conf:&log_service_config_t) ->errcode::RESULT {
 	unsafe {
@@ -426,7 +669,14 @@ pub fn InitLogService(conf:&log_service_config_t) ->errcode::RESULT {
 	if ret != errcode::RESULT_SUCCESS {
 		return ret
 	}
-	std::thread::spawn(||run_log_service());
+	service.rotateStaleLogFiles();
+	oam::RegisterOamModule(&[RSM_LOG_URL.to_string()], process_log_level_oam);
+	oam::RegisterOamModule(&[RSM_LOG_FILE_URL.to_string()], process_log_file_oam);
+	gLogServiceStopped.store(false, Ordering::SeqCst);
+	let h = std::thread::spawn(||run_log_service());
+	unsafe {
+		gLogServiceThread = Some(h);
+	}
 	InitLogCleanTask(); //初始化清理任务，定期清理任务
 
 	return errcode::RESULT_SUCCESS
@@ -440,9 +690,23 @@ fn run_log_service() {
         Some(s)=>s,
     };
 
-	loop {
+	while !gLogServiceStopped.load(Ordering::SeqCst) {
 		service.recvLog();
 	}
+	service.forceSyncLogFile();
+	println!("LogServer: log service stopped");
+}
+
+/*stop log service, the pending log messages are written to the log file before exit*/
+pub fn StopLogService()->errcode::RESULT {
+	if unsafe {gLogServer.is_none()} {
+		return errcode::ERROR_NOT_INITIALIZED
+	}
+	gLogServiceStopped.store(true, Ordering::SeqCst);
+	if let Some(h) = unsafe { gLogServiceThread.take() } {
+		let _ = h.join();
+	}
+	return errcode::RESULT_SUCCESS
 }
 
 
@@ -498,15 +762,420 @@ pub(crate) fn InitLogCleanTask() {
 	std::thread::spawn(|| LogCleanTask()); //日志清理的任务
 }
 
-/*整理日志文件，仅保留最近30天并不超过总容量的日志文件，并且对每天的日志文件进行压缩处理
-对于一天以及以前的文件压缩为.zip文件，并删除原始Log文件*/
+/*整理日志文件，启动时以及每小时压缩轮转后的日志文件，并按照保存天数和总容量删除旧的归档文件*/
 fn LogCleanTask() {
 	let log_serv = match unsafe{&mut gLogServer} {
 		None=>return,
 		Some(s)=>s,
 	};
 	loop {
-		thread::sleep(time::Duration::from_secs(3600));
 		log_serv.do_clean();
+		thread::sleep(time::Duration::from_secs(3600));
+	}
+}
+
+struct log_file_info_t {
+	name:String,
+	path:String,
+	kind:&'static str,
+	size:u64,
+	modified:SystemTime,
+}
+
+/*轮转后的文件名在日期后还有时间，如prefix_YYYYMMDD_HHMMSS.log，当前日志文件为prefix_YYYYMMDD.log*/
+fn is_rotated_file_name(conf:&log_service_config_t,name:&String)->bool {
+	let stem = name.strip_prefix(conf.logFilePrefix.as_str()).and_then(|s| s.strip_prefix("_")).and_then(|s| s.strip_suffix(".log"));
+	match stem {
+		None=>false,
+		Some(s)=>s.len()>8,
+	}
+}
+
+/*列出日志目录下本服务的日志文件，按修改时间从新到旧排序*/
+fn list_log_files(conf:&log_service_config_t,active:&String)->Vec<log_file_info_t> {
+	let mut files = Vec::new();
+	let dir = match fs::read_dir(&conf.logFilePath) {
+		Err(_)=>return files,
+		Ok(d)=>d,
+	};
+	let prefix = format!("{}_",conf.logFilePrefix);
+	for entry in dir.flatten() {
+		let name = entry.file_name().to_string_lossy().to_string();
+		if !name.starts_with(prefix.as_str()) {
+			continue
+		}
+		let path = format!("{}{}",conf.logFilePath,name);
+		let kind = if path.eq(active) {
+			LOG_FILE_CURRENT
+		} else if name.ends_with(".log") {
+			LOG_FILE_ROTATED
+		} else if name.ends_with(LOG_ARCHIVE_SUFFIX) || name.ends_with(LOG_LEGACY_ARCHIVE_SUFFIX) {
+			LOG_FILE_ARCHIVE
+		} else {
+			continue
+		};
+		let meta = match entry.metadata() {
+			Ok(m) if m.is_file()=>m,
+			_=>continue,
+		};
+		files.push(log_file_info_t { name, path, kind, size:meta.len(), modified:meta.modified().unwrap_or(time::UNIX_EPOCH) });
+	}
+	files.sort_by(|a,b| b.modified.cmp(&a.modified));
+	return files
+}
+
+static gLogCleanLock:Mutex<()>=Mutex::new(());
+/*压缩轮转后的日志文件，然后从最新的文件开始累计，删除超过总容量或者保存天数的归档文件*/
+fn clean_log_files(conf:&log_service_config_t) {
+	let _guard = match gLogCleanLock.lock() {
+		Err(_)=>return,
+		Ok(g)=>g,
+	};
+	for f in list_log_files(conf, &String::default()) {
+		if f.kind!=LOG_FILE_ROTATED || !is_rotated_file_name(conf, &f.name) {
+			continue
+		}
+		let archive = format!("{}{}",f.path,LOG_ARCHIVE_SUFFIX);
+		//上次压缩没有完成遗留的文件
+		let _ = fs::remove_file(&archive);
+		if compressFile(&f.path, &archive)==errcode::RESULT_SUCCESS {
+			//归档文件保留原文件的修改时间，按照最后一条日志的时间计算保存天数
+			if let Ok(fp) = OpenOptions::new().write(true).open(&archive) {
+				let _ = fp.set_modified(f.modified);
+			}
+			let _ = fs::remove_file(&f.path);
+		} else {
+			let _ = fs::remove_file(&archive);
+		}
+	}
+
+	let now = SystemTime::now();
+	let max_age = Duration::from_secs(SECS_PER_DAY*conf.maxStoragePeriod.max(0) as u64);
+	let mut total_file_size = 0u64;
+	let mut toBeClean = false;
+	for f in list_log_files(conf, &String::default()) {
+		total_file_size+=f.size;
+		if total_file_size>conf.maxStorageSize {
+			toBeClean = true;
+		}
+		//只删除归档文件，当前文件和没有压缩的文件仅计入容量
+		if f.kind!=LOG_FILE_ARCHIVE {
+			continue
+		}
+		let expired = conf.maxStoragePeriod>0 && now.duration_since(f.modified).map_or(false, |d| d>max_age);
+		if toBeClean || expired {
+			let _ = fs::remove_file(&f.path);
+		}
+	}
+}
+
+/*日志级别可以是0~7的数字，或者级别的名称，如error、debug，空字符串表示不修改*/
+fn parse_log_level(s:&str)->Result<Option<LOG_LEVEL>,errcode::RESULT> {
+	const LEVEL_NAMES:[&str;8] = ["emergency","alert","critical","error","warning","notice","info","debug"];
+	if s.is_empty() {
+		return Ok(None)
+	}
+	if let Some(l) = LEVEL_NAMES.iter().position(|n| n.eq_ignore_ascii_case(s)) {
+		return Ok(Some(l as LOG_LEVEL))
+	}
+	match s.parse::<LOG_LEVEL>() {
+		Ok(l) if l>=0 && l<=LOG_LEVEL_MAX=>Ok(Some(l)),
+		_=>Err(errcode::ERROR_INVALID_PARAM),
+	}
+}
+
+/*解析OAM设置日志级别的参数module:persistent_level:console_level，module为*表示全局级别，级别为空表示不修改，至少修改一个级别*/
+fn parse_log_level_param(param:&str)->Result<(String,Option<LOG_LEVEL>,Option<LOG_LEVEL>),errcode::RESULT> {
+	let items:Vec<&str> = param.split(':').map(|s| s.trim()).collect();
+	if items.len()<2 || items.len()>3 || items[0].is_empty() {
+		return Err(errcode::ERROR_INVALID_PARAM)
+	}
+	match (parse_log_level(items[1]),parse_log_level(items.get(2).unwrap_or(&""))) {
+		(Ok(p),Ok(c)) if p.is_some() || c.is_some()=>Ok((items[0].to_string(),p,c)),
+		_=>Err(errcode::ERROR_INVALID_PARAM),
+	}
+}
+
+//OAM线程或者应用提交的日志级别修改，由日志线程执行，避免和日志的处理同时读写级别
+struct log_level_change_t {
+	module:String,
+	persistent:Option<LOG_LEVEL>,
+	console:Option<LOG_LEVEL>,
+	create:bool,
+	resp:mpsc::Sender<Result<String,errcode::RESULT>>,
+}
+static gLogLevelChanges:Mutex<VecDeque<log_level_change_t>>=Mutex::new(VecDeque::new());
+
+/*提交日志级别的修改，等待日志线程修改完成，返回修改的模块名称*/
+fn submit_log_level_change(module:String,persistent:Option<LOG_LEVEL>,console:Option<LOG_LEVEL>,create:bool)->Result<String,errcode::RESULT> {
+	let (tx,rx) = mpsc::channel();
+	match gLogLevelChanges.lock() {
+		Err(_)=>return Err(errcode::ERROR_LOCK_FAILED),
+		Ok(mut q)=>q.push_back(log_level_change_t { module, persistent, console, create, resp:tx }),
+	}
+	match rx.recv_timeout(LOG_LEVEL_CHANGE_TIMEOUT) {
+		Ok(r)=>r,
+		Err(_)=>Err(errcode::ERROR_TIME_OUT),
+	}
+}
+
+/*OAM设置日志级别，等待日志线程修改完成后返回新的级别*/
+fn set_log_level_oam(service:&log_service_t,param:&str)->oam::oam_cmd_resp_t {
+	let (module,persistent,console) = match parse_log_level_param(param) {
+		Err(e)=>return oam::oam_cmd_resp_t::new(e,&String::default()),
+		Ok(p)=>p,
+	};
+	match submit_log_level_change(module, persistent, console, false) {
+		Ok(name)=>service.get_log_level_oam(&name),
+		Err(e)=>oam::oam_cmd_resp_t::new(e,&String::default()),
+	}
+}
+
+///set the persistent and console log levels of a module on the log server, the module is created if it has not sent any log yet,
+/// e.g. a module path forwarded by log-bridge or tracing-bridge, which is registered with LOG_LEVEL_WARNING and LOG_LEVEL_INFO by default.
+/// the global levels of the log service still apply
+pub fn set_module_log_level(name:&str,persistent:LOG_LEVEL,console:LOG_LEVEL)->errcode::RESULT {
+	if unsafe {gLogServer.is_none()} {
+		return errcode::ERROR_NOT_INITIALIZED
+	}
+	let valid = |l:LOG_LEVEL| l>=0 && l<=LOG_LEVEL_MAX;
+	if name.is_empty() || name==LOG_GLOBAL_MODULE_NAME || !valid(persistent) || !valid(console) {
+		return errcode::ERROR_INVALID_PARAM
+	}
+	match submit_log_level_change(name.to_string(), Some(persistent), Some(console), true) {
+		Ok(_)=>errcode::RESULT_SUCCESS,
+		Err(e)=>e,
+	}
+}
+
+fn process_log_level_oam(op:E_RSM_OAM_OP,_url:&String,param:&String)->oam::oam_cmd_resp_t {
+	let service = match unsafe {&gLogServer} {
+		None=>return oam::oam_cmd_resp_t::new(errcode::ERROR_NOT_INITIALIZED,&String::default()),
+		Some(s)=>s,
+	};
+	match op {
+		E_RSM_OAM_OP::CLI_OP_SHOW=>service.get_log_level_oam(param.trim()),
+		E_RSM_OAM_OP::CLI_OP_SET=>set_log_level_oam(service, param),
+		_=>oam::oam_cmd_resp_t::new(errcode::ERROR_NOT_SUPPORT,&String::default()),
 	}
-}
\ No newline at end of file
+}
+
+fn process_log_file_oam(op:E_RSM_OAM_OP,_url:&String,_param:&String)->oam::oam_cmd_resp_t {
+	if op!=E_RSM_OAM_OP::CLI_OP_SHOW {
+		return oam::oam_cmd_resp_t::new(errcode::ERROR_NOT_SUPPORT,&String::default())
+	}
+	match unsafe {&gLogServer} {
+		None=>oam::oam_cmd_resp_t::new(errcode::ERROR_NOT_INITIALIZED,&String::default()),
+		Some(s)=>s.get_log_file_oam(),
+	}
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn new_test_service()->log_service_t {
+		let mut conf = log_service_config_t::new_default();
+		conf.self_addr = SocketAddr::new(IpAddr::from([127,0,0,1]),0);
+		return log_service_t::new(&conf).unwrap()
+	}
+
+	#[test]
+	fn test_sender_seq_restart() {
+		let mut service = new_test_service();
+		let sender = SocketAddr::new(IpAddr::from([127,0,0,1]),16000);
+		for seq in 1..=5000u64 {
+			if seq!=100 {
+				service.updateSenderSeq(&sender, seq);
+			}
+		}
+		let s = service.logSenderSeq.get(&sender).unwrap();
+		assert_eq!((s.last_seq,s.received,s.lost,s.late),(5000,4999,1,0));
+
+		//晚到的日志不触发重置
+		service.updateSenderSeq(&sender, 4990);
+		let s = service.logSenderSeq.get(&sender).unwrap();
+		assert_eq!((s.last_seq,s.lost,s.late),(5000,0,1));
+
+		//客户端在相同地址端口上重启，序列号从1开始
+		service.updateSenderSeq(&sender, 1);
+		service.updateSenderSeq(&sender, 2);
+		let s = service.logSenderSeq.get(&sender).unwrap();
+		assert_eq!((s.last_seq,s.received,s.lost,s.late),(2,2,0,0));
+
+		//丢失了重启后的前几条日志，序列号大幅回退
+		for seq in 3..=3000u64 {
+			service.updateSenderSeq(&sender, seq);
+		}
+		service.updateSenderSeq(&sender, 5);
+		let s = service.logSenderSeq.get(&sender).unwrap();
+		assert_eq!((s.last_seq,s.received,s.lost,s.late),(5,1,4,0));
+	}
+
+	#[test]
+	fn test_parse_log_level() {
+		assert_eq!(parse_log_level(""), Ok(None));
+		assert_eq!(parse_log_level("debug"), Ok(Some(LOG_LEVEL_DEBUG)));
+		assert_eq!(parse_log_level("Warning"), Ok(Some(LOG_LEVEL_WARNING)));
+		assert_eq!(parse_log_level("EMERGENCY"), Ok(Some(LOG_LEVEL_EMERGENCY)));
+		assert_eq!(parse_log_level("0"), Ok(Some(LOG_LEVEL_EMERGENCY)));
+		assert_eq!(parse_log_level("7"), Ok(Some(LOG_LEVEL_MAX)));
+		assert_eq!(parse_log_level("8"), Err(errcode::ERROR_INVALID_PARAM));
+		assert_eq!(parse_log_level("-1"), Err(errcode::ERROR_INVALID_PARAM));
+		assert_eq!(parse_log_level("verbose"), Err(errcode::ERROR_INVALID_PARAM));
+	}
+
+	#[test]
+	fn test_parse_log_level_param() {
+		assert_eq!(parse_log_level_param("app:debug:info"), Ok(("app".to_string(),Some(LOG_LEVEL_DEBUG),Some(LOG_LEVEL_INFO))));
+		assert_eq!(parse_log_level_param(" app : 3 "), Ok(("app".to_string(),Some(LOG_LEVEL_ERROR),None)));
+		assert_eq!(parse_log_level_param("*::warning"), Ok(("*".to_string(),None,Some(LOG_LEVEL_WARNING))));
+		//没有模块名称、没有修改任何级别，或者参数个数不对
+		for p in ["", "app", ":debug:info", "app::", "app:debug:info:7", "app:debug:loud"] {
+			assert_eq!(parse_log_level_param(p), Err(errcode::ERROR_INVALID_PARAM), "{}", p);
+		}
+	}
+
+	#[test]
+	fn test_set_log_level() {
+		let mut service = new_test_service();
+		let addr = SocketAddr::new(IpAddr::from([127,0,0,1]),16001);
+		service.RegisterLogModule(&"NetApp".to_string(), &addr);
+
+		assert_eq!(service.set_log_level("*", Some(LOG_LEVEL_DEBUG), None, false), Ok(String::default()));
+		assert_eq!((service.service_conf.persistentLevel,service.service_conf.consoleLevel), (LOG_LEVEL_DEBUG,LOG_LEVEL_ERROR));
+
+		//模块名称不区分大小写，级别为空的不修改
+		assert_eq!(service.set_log_level("netapp", None, Some(LOG_LEVEL_DEBUG), false), Ok("NetApp".to_string()));
+		let m = service.logMuduleControl.get(&"NetApp".to_string()).unwrap();
+		assert_eq!((m.persistent_log_level,m.console_log_level), (LOG_LEVEL_WARNING,LOG_LEVEL_DEBUG));
+		assert_eq!(service.set_log_level("other", Some(LOG_LEVEL_DEBUG), None, false), Err(errcode::ERROR_NOT_FOUND));
+		assert_eq!(service.get_log_level_oam("netapp").RetCode, errcode::RESULT_SUCCESS);
+	}
+
+	#[test]
+	fn test_set_log_level_before_register() {
+		let mut service = new_test_service();
+		//按照名称精确匹配，预先创建的模块在收到日志时沿用设置的级别
+		let name = "mycrate::net".to_string();
+		assert_eq!(service.set_log_level(&name, Some(LOG_LEVEL_DEBUG), Some(LOG_LEVEL_NOTICE), true), Ok(name.clone()));
+		let addr = SocketAddr::new(IpAddr::from([127,0,0,1]),16002);
+		let m = service.RegisterLogModule(&name, &addr).unwrap();
+		assert_eq!((m.persistent_log_level,m.console_log_level,m.addr), (LOG_LEVEL_DEBUG,LOG_LEVEL_NOTICE,addr));
+
+		//没有设置级别的模块使用默认级别
+		let m = service.RegisterLogModule(&"mycrate::io".to_string(), &addr).unwrap();
+		assert_eq!((m.persistent_log_level,m.console_log_level), (LOG_LEVEL_WARNING,LOG_LEVEL_INFO));
+		assert_eq!(set_module_log_level(&name, LOG_LEVEL_DEBUG, LOG_LEVEL_DEBUG), errcode::ERROR_NOT_INITIALIZED);
+	}
+
+	fn at_secs(secs:u64)->common::datetime_t {
+		return common::get_datetime_from_std(&(time::UNIX_EPOCH+Duration::from_secs(secs)))
+	}
+
+	#[test]
+	fn test_rotate_slot() {
+		let mut service = new_test_service();
+		let now = common::get_datetime_from_std(&SystemTime::now());
+		let secs_in_day = now.get_hour_in_day() as u64*3600+now.get_min_in_hour() as u64*60+now.get_secs_in_min() as u64;
+		//本地时间的零点
+		let midnight = now.to_usecs()/1000000-secs_in_day;
+
+		service.service_conf.rotatePeriod = SECS_PER_DAY;
+		let slot = service.get_rotate_slot(&at_secs(midnight));
+		assert_eq!(service.get_rotate_slot(&at_secs(midnight+SECS_PER_DAY-1)), slot);
+		assert_eq!(service.get_rotate_slot(&at_secs(midnight-1)), slot-1);
+		assert_eq!(service.get_rotate_slot(&at_secs(midnight+SECS_PER_DAY)), slot+1);
+
+		//一小时的周期从整点开始
+		service.service_conf.rotatePeriod = 3600;
+		let hour = midnight+3*3600;
+		let slot = service.get_rotate_slot(&at_secs(hour));
+		assert_eq!(service.get_rotate_slot(&at_secs(hour+3599)), slot);
+		assert_ne!(service.get_rotate_slot(&at_secs(hour-1)), slot);
+		assert_ne!(service.get_rotate_slot(&at_secs(hour+3600)), slot);
+
+		//两天的周期，相邻的三天中只有一次轮转
+		service.service_conf.rotatePeriod = 2*SECS_PER_DAY;
+		let slots:Vec<u64> = (0..3).map(|d| service.get_rotate_slot(&at_secs(midnight+d*SECS_PER_DAY))).collect();
+		assert_eq!(slots[2], slots[0]+1);
+		assert!(slots[1]==slots[0] || slots[1]==slots[2]);
+		assert_eq!(service.get_rotate_slot(&at_secs(midnight+SECS_PER_DAY-1)), slots[0]);
+	}
+
+	#[test]
+	fn test_rotated_file_name() {
+		let conf = log_service_config_t::new_default();
+		let prefix = conf.logFilePrefix.clone();
+		assert!(is_rotated_file_name(&conf, &format!("{}_20261018_235959.log",prefix)));
+		assert!(is_rotated_file_name(&conf, &format!("{}_20261018_235959_1.log",prefix)));
+		//当前文件和压缩后的文件
+		assert!(!is_rotated_file_name(&conf, &format!("{}_20261018.log",prefix)));
+		assert!(!is_rotated_file_name(&conf, &format!("{}_20261018_235959.log.gz",prefix)));
+		assert!(!is_rotated_file_name(&conf, &"other_20261018_235959.log".to_string()));
+		assert!(!is_rotated_file_name(&conf, &format!("{}20261018_235959.log",prefix)));
+	}
+
+	fn new_test_dir(name:&str)->log_service_config_t {
+		let mut conf = log_service_config_t::new_default();
+		let dir = std::env::temp_dir().join(format!("rsm_xlog_{}_{}",name,std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		conf.logFilePath = format!("{}/",dir.to_string_lossy());
+		return conf
+	}
+
+	//创建日志目录下的文件，修改时间为secs_ago秒之前
+	fn new_test_file(conf:&log_service_config_t,name:&str,size:usize,secs_ago:u64)->String {
+		let path = format!("{}{}_{}",conf.logFilePath,conf.logFilePrefix,name);
+		fs::write(&path, vec![b'x';size]).unwrap();
+		let fp = OpenOptions::new().write(true).open(&path).unwrap();
+		fp.set_modified(SystemTime::now()-Duration::from_secs(secs_ago)).unwrap();
+		return path
+	}
+
+	fn exists(path:&String)->bool {
+		return fs::metadata(path).is_ok()
+	}
+
+	#[test]
+	fn test_clean_log_files_by_size() {
+		let mut conf = new_test_dir("size");
+		conf.maxStorageSize = 2500;
+		conf.maxStoragePeriod = 0;
+		let current = new_test_file(&conf, "20261018.log", 100, 0);
+		let rotated = new_test_file(&conf, "20261018_093000.log", 1000, 1800);
+		let a = new_test_file(&conf, "20261018_090000.log.gz", 1000, 3600);
+		let b = new_test_file(&conf, "20261018_080000.log.gz", 1000, 2*3600);
+		let c = new_test_file(&conf, "20261018_070000.log.gz", 1000, 3*3600);
+		clean_log_files(&conf);
+
+		//轮转后的文件被压缩，保留原文件的修改时间
+		let archive = format!("{}{}",rotated,LOG_ARCHIVE_SUFFIX);
+		assert!(!exists(&rotated));
+		let modified = fs::metadata(&archive).unwrap().modified().unwrap();
+		assert!(SystemTime::now().duration_since(modified).unwrap()>=Duration::from_secs(1800));
+		//从最新的文件开始累计，只删除超过总容量的归档文件
+		assert!(exists(&current) && exists(&a) && exists(&b));
+		assert!(!exists(&c));
+		let _ = fs::remove_dir_all(&conf.logFilePath);
+	}
+
+	#[test]
+	fn test_clean_log_files_by_age() {
+		let mut conf = new_test_dir("age");
+		conf.maxStorageSize = u64::MAX;
+		conf.maxStoragePeriod = 2;
+		let current = new_test_file(&conf, "20261018.log", 100, 3*SECS_PER_DAY);
+		let recent = new_test_file(&conf, "20261017_235959.log.gz", 100, SECS_PER_DAY);
+		let expired = new_test_file(&conf, "20261015_235959.log.gz", 100, 3*SECS_PER_DAY);
+		let legacy = new_test_file(&conf, "20261014.log.zip", 100, 4*SECS_PER_DAY);
+		//压缩后按照原文件的修改时间过期
+		let rotated = new_test_file(&conf, "20261015_120000.log", 100, 3*SECS_PER_DAY);
+		clean_log_files(&conf);
+
+		assert!(exists(&current) && exists(&recent));
+		assert!(!exists(&expired) && !exists(&legacy));
+		assert!(!exists(&rotated) && !exists(&format!("{}{}",rotated,LOG_ARCHIVE_SUFFIX)));
+		let _ = fs::remove_dir_all(&conf.logFilePath);
+	}
+}
@@ -14,12 +14,44 @@
 use crate::common::errcode;
 use serde_json;
 use std::net::{self, IpAddr, SocketAddr, UdpSocket};
-use std::time;
+use std::time::{self, Instant};
 use crate::common::{self,rsm_time_t};
 use super::*;
 
 
 const _DEBUG: bool = false;
+const SUPPRESSED_SUMMARY_POSITION: &str = "xlog";
+//服务端不可达后，重新尝试发送缓存日志的间隔
+const FALLBACK_RETRY_MSEC: u64 = 1000;
+
+//令牌桶，按照rate_limit补充令牌，最多积累rate_burst个
+pub(crate) struct token_bucket_t {
+    tokens: f64,
+    last: Instant,
+    suppressed: u64,
+}
+
+impl token_bucket_t {
+    fn new(burst: u32) -> Self {
+        return Self {
+            tokens: burst as f64,
+            last: Instant::now(),
+            suppressed: 0,
+        };
+    }
+
+    fn take(&mut self, rate: u32, burst: u32) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.last = now;
+        self.tokens = (self.tokens + elapsed * rate as f64).min(burst as f64);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        return true;
+    }
+}
 
 /*初始化Log，每个模块使用Log前要初始化一个实例，用此实例输出日志,入参为自己的模块名称，自身IP和端口，LogService的IP和端口
 支持LogClient和Service在不同的节点中部署*/
@@ -33,7 +65,10 @@ pub fn new_xlogger(
     let server_addr = SocketAddr::new(servIp.clone(), servPort);
     let mut socket: Option<UdpSocket> = None;
     if let Ok(conn) = UdpSocket::bind(SocketAddr::new(*selfIp, self_port)) {
-        socket = Some(conn);
+        //连接后的UDP socket可以收到ICMP端口不可达，据此判断服务端不可达
+        if conn.connect(server_addr).is_ok() {
+            socket = Some(conn);
+        }
     }
 
     let logger = xlogger_t {
@@ -45,6 +80,17 @@ pub fn new_xlogger(
         level: LOG_LEVEL_ERROR,
         sentPackets: 0,
         sentbytes: 0,
+        seq: 0,
+        rate_limit: 0,
+        rate_burst: 0,
+        buckets: HashMap::new(),
+        suppressed: 0,
+        fallback: VecDeque::new(),
+        fallback_bytes: 0,
+        fallback_max_bytes: 0,
+        retry_time: None,
+        dropped: 0,
+        sendErrors: 0,
     };
 
     return logger;
@@ -67,8 +113,53 @@ impl xlogger_t {
         if level > self.level {
             return;
         }
+        let module = self.module_name.clone();
+        self.sentRecord(&module, level, position, err, logDesc, Vec::new());
+    }
+
+    pub(crate) fn sentRecord(
+        &mut self,
+        module: &str,
+        level: LOG_LEVEL,
+        position: &str,
+        err: errcode::RESULT,
+        logDesc: &String,
+        fields: Vec<(String, E_LOG_FIELD_VALUE)>,
+    ) -> errcode::RESULT {
+        if self.rate_limit > 0 {
+            let (rate, burst) = (self.rate_limit, self.rate_burst);
+            let bucket = self
+                .buckets
+                .entry(module.to_string())
+                .or_insert_with(|| token_bucket_t::new(burst));
+            if !bucket.take(rate, burst) {
+                bucket.suppressed += 1;
+                self.suppressed += 1;
+                return errcode::ERROR_THRESHOLD_EXCEED;
+            }
+            //恢复发送时，先报告被限速丢弃的日志条数
+            let suppressed = std::mem::take(&mut bucket.suppressed);
+            if suppressed > 0 {
+                let desc = format!("{} messages suppressed", suppressed);
+                let summary = vec![("suppressed".to_string(), E_LOG_FIELD_VALUE::from(suppressed))];
+                self.sentMsg(module, LOG_LEVEL_WARNING, SUPPRESSED_SUMMARY_POSITION, errcode::RESULT_SUCCESS, &desc, summary);
+            }
+        }
+        return self.sentMsg(module, level, position, err, logDesc, fields);
+    }
+
+    fn sentMsg(
+        &mut self,
+        module: &str,
+        level: LOG_LEVEL,
+        position: &str,
+        err: errcode::RESULT,
+        logDesc: &String,
+        fields: Vec<(String, E_LOG_FIELD_VALUE)>,
+    ) -> errcode::RESULT {
+        self.seq += 1;
         let logMsg = InnerLogMsg {
-            ModuleName: self.module_name.clone(),
+            ModuleName: module.to_string(),
             OccureTime: rsm_time_t::now_utc(),
             LogType: LOG_TYPE_SYSTEM,
             LogLevel: level,
@@ -76,9 +167,53 @@ impl xlogger_t {
             ErrCode: err,
             LogDesc: logDesc.clone(),
             Context: String::from("Null"),
+            Fields: fields,
+            Seq: self.seq,
+        };
+
+        return self.sentLog(&logMsg);
+    }
+
+    ///start a structured log record, e.g. log.info().field("peer", addr).field("seq", n).msg("connected"),
+    /// the record is dropped without being formatted if the level is above the client's log level
+    pub fn record(&mut self, level: LOG_LEVEL) -> log_record_t<'_> {
+        let enabled = level <= self.level;
+        return log_record_t {
+            logger: self,
+            level: level,
+            enabled: enabled,
+            position: String::default(),
+            err: errcode::RESULT_SUCCESS,
+            fields: Vec::new(),
         };
+    }
 
-        self.sentLog(&logMsg);
+    pub fn alert(&mut self) -> log_record_t<'_> {
+        self.record(LOG_LEVEL_ALERT)
+    }
+
+    pub fn error(&mut self) -> log_record_t<'_> {
+        self.record(LOG_LEVEL_ERROR)
+    }
+
+    pub fn warning(&mut self) -> log_record_t<'_> {
+        self.record(LOG_LEVEL_WARNING)
+    }
+
+    pub fn notice(&mut self) -> log_record_t<'_> {
+        self.record(LOG_LEVEL_NOTICE)
+    }
+
+    pub fn info(&mut self) -> log_record_t<'_> {
+        self.record(LOG_LEVEL_INFO)
+    }
+
+    pub fn debug(&mut self) -> log_record_t<'_> {
+        self.record(LOG_LEVEL_DEBUG)
+    }
+
+    pub fn get_log_level(&self) -> LOG_LEVEL {
+        self.level
     }
 
     pub fn set_log_level(&mut self, new_level: LOG_LEVEL) {
@@ -87,6 +222,99 @@ impl xlogger_t {
         }
     }
 
+    ///limit the records of each module to rate_per_sec with a burst of burst records, 0 disables the rate limit,
+    /// the suppressed records are reported by a "N messages suppressed" warning when the module is allowed to log again
+    pub fn set_rate_limit(&mut self, rate_per_sec: u32, burst: u32) {
+        self.rate_limit = rate_per_sec;
+        self.rate_burst = if burst == 0 { rate_per_sec } else { burst };
+        self.buckets.clear();
+    }
+
+    ///keep up to max_bytes of encoded records in memory when the server is unreachable, they are sent before the next record
+    /// once the server is reachable, the oldest ones are dropped when the buffer is full. 0 disables the buffer
+    pub fn enable_fallback_buffer(&mut self, max_bytes: usize) {
+        self.fallback_max_bytes = max_bytes;
+        while self.fallback_bytes > max_bytes {
+            self.drop_oldest();
+        }
+    }
+
+    ///send the records in the fallback buffer, return the number of records left,
+    /// the records are kept if the server has been unreachable within the last second
+    pub fn flush_fallback_buffer(&mut self) -> usize {
+        if let Some(t) = self.retry_time {
+            if Instant::now() < t {
+                return self.fallback.len();
+            }
+        }
+        while let Some(buf) = self.fallback.pop_front() {
+            if !self.try_send(&buf) {
+                self.fallback.push_front(buf);
+                break;
+            }
+            self.fallback_bytes -= buf.len();
+        }
+        return self.fallback.len();
+    }
+
+    /*发送一条日志，启用缓存时检查socket上的ICMP错误，出错后在重试间隔内不再发送*/
+    fn try_send(&mut self, buf: &[u8]) -> bool {
+        let sock = match &self.socket {
+            None => return false,
+            Some(s) => s,
+        };
+        //已连接的socket只能用send，BSD/macOS上对已连接socket调用send_to会返回EISCONN
+        let ok = match sock.send(buf) {
+            Err(_) => false,
+            Ok(len) => {
+                if self.fallback_max_bytes > 0 && !matches!(sock.take_error(), Ok(None)) {
+                    false
+                } else {
+                    self.sentbytes += len as u64;
+                    self.sentPackets += 1;
+                    true
+                }
+            },
+        };
+        if !ok {
+            self.sendErrors += 1;
+            self.retry_time = Some(Instant::now() + time::Duration::from_millis(FALLBACK_RETRY_MSEC));
+        } else {
+            self.retry_time = None;
+        }
+        return ok;
+    }
+
+    pub fn get_stats(&self) -> xlogger_stats_t {
+        return xlogger_stats_t {
+            sent_packets: self.sentPackets,
+            sent_bytes: self.sentbytes,
+            suppressed: self.suppressed,
+            buffered: self.fallback.len() as u64,
+            dropped: self.dropped,
+            send_errors: self.sendErrors,
+        };
+    }
+
+    fn drop_oldest(&mut self) {
+        if let Some(buf) = self.fallback.pop_front() {
+            self.fallback_bytes -= buf.len();
+            self.dropped += 1;
+        }
+    }
+
+    fn buffer_record(&mut self, buf: &[u8]) {
+        if buf.len() > self.fallback_max_bytes {
+            self.dropped += 1;
+            return;
+        }
+        while self.fallback_bytes + buf.len() > self.fallback_max_bytes {
+            self.drop_oldest();
+        }
+        self.fallback.push_back(buf.to_vec());
+        self.fallback_bytes += buf.len();
+    }
+
     fn print_debug(&self,level: LOG_LEVEL,ec:errcode::RESULT,position: &str,logDesc:&String) {
         if ec == 0 {
             println!(
@@ -113,14 +341,21 @@ impl xlogger_t {
             Ok(s) => s,
         };
 
-        if let Some(ref c) = self.socket {
-            if let Ok(len) = c.send_to(json_str.as_bytes(), self.server_addr) {
-                self.sentbytes += len as u64;
-                self.sentPackets += 1;
+        if self.socket.is_some() {
+            //缓存的日志没有发送完时，新的日志也进入缓存，保持顺序
+            if self.fallback_max_bytes > 0 && self.flush_fallback_buffer() > 0 {
+                self.buffer_record(json_str.as_bytes());
+                return errcode::ERROR_SEND_MSG;
+            }
+            if self.try_send(json_str.as_bytes()) {
                 return errcode::RESULT_SUCCESS;
+            }
+            if self.fallback_max_bytes > 0 {
+                self.buffer_record(json_str.as_bytes());
             } else {
-                return errcode::ERROR_SEND_MSG;
+                self.dropped += 1;
             }
+            return errcode::ERROR_SEND_MSG;
         } else {
             println!(
                 "LogClient: udp connection is not ready, ip={},port={}\n",
@@ -163,12 +398,57 @@ impl xlogger_t {
             self.server_addr.port()
         );
         println!(
-            "LogClient: sent packets={}, bytes={}\n",
-            self.sentPackets, self.sentbytes
+            "LogClient: sent packets={}, bytes={}, suppressed={}, buffered={}, dropped={}, send_errors={}\n",
+            self.sentPackets, self.sentbytes, self.suppressed, self.fallback.len(), self.dropped, self.sendErrors
         );
     }
 }
 
+///a structured log record being built, sent to the log server by msg()
+pub struct log_record_t<'a> {
+    logger: &'a mut xlogger_t,
+    level: LOG_LEVEL,
+    enabled: bool,
+    position: String,
+    err: errcode::RESULT,
+    fields: Vec<(String, E_LOG_FIELD_VALUE)>,
+}
+
+impl<'a> log_record_t<'a> {
+    ///add a typed key/value pair, rendered as an SD-PARAM in the syslog text or a member of "fields" in JSON Lines
+    pub fn field<V: Into<E_LOG_FIELD_VALUE>>(mut self, key: &str, value: V) -> Self {
+        if self.enabled {
+            self.fields.push((key.to_string(), value.into()));
+        }
+        self
+    }
+
+    pub fn position(mut self, position: &str) -> Self {
+        if self.enabled {
+            self.position = position.to_string();
+        }
+        self
+    }
+
+    pub fn err(mut self, err: errcode::RESULT) -> Self {
+        self.err = err;
+        self
+    }
+
+    ///send the record with the description
+    pub fn msg(self, logDesc: &str) -> errcode::RESULT {
+        if !self.enabled {
+            return errcode::ERROR_NO_OP;
+        }
+        let desc = logDesc.to_string();
+        if _DEBUG {
+            self.logger.print_debug(self.level, self.err, &self.position, &desc);
+        }
+        let module = self.logger.module_name.clone();
+        return self.logger.sentRecord(&module, self.level, &self.position, self.err, &desc, self.fields);
+    }
+}
+
 #[macro_export]
 macro_rules! log {
     ($($arg:tt)*) => (xlog::_print($crate::format_args!($($arg)*)));
@@ -450,6 +450,25 @@ fn test_ring_buffer() {
 
 }
 
+#[test]
+fn test_ring_buffer_wrap() {
+    use crate::common::ring_buffer_t;
+    let mut ring=ring_buffer_t::new(16, None).unwrap();
+    let mut out_data=[0u8;16];
+    assert_eq!(ring.put_data(&[1u8;10]),errcode::RESULT_SUCCESS);
+    assert_eq!(ring.pull_data(4, &mut out_data),Ok(4));
+    //the buffer is full and the data is wrapped
+    let data:Vec<u8>=(100..110).collect();
+    assert_eq!(ring.put_data(&data),errcode::RESULT_SUCCESS);
+    assert_eq!(ring.buffer_available(),0);
+    assert_eq!(ring.peek_data(0, 16, &mut out_data),Ok(16));
+    assert_eq!(&out_data[0..6],&[1u8;6]);
+    assert_eq!(&out_data[6..16],data.as_slice());
+    assert_eq!(ring.pull_data(16, &mut out_data),Ok(16));
+    assert_eq!(&out_data[6..16],data.as_slice());
+    assert_eq!(ring.len(),0);
+}
+
 use crate::common::uuid_t;
 #[test]
 fn test_uuid() {
@@ -0,0 +1,29 @@
+#![allow(dead_code)]
+
+//helpers shared by the integration tests, included by `mod test_common;`
+use rust_rsm::rsm;
+use std::net::SocketAddr;
+use std::time::{Duration,Instant};
+use std::thread;
+
+///poll the condition every 10ms, return false if it is not met within 10 seconds
+pub fn wait_until(cond:impl Fn()->bool)->bool {
+    let start = Instant::now();
+    while start.elapsed()<Duration::from_secs(10) {
+        if cond() {
+            return true
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    return false
+}
+
+///init rsm as node 1 with the log and oam servers on the loopback ports, then start it,
+/// the components registered afterwards are powered on before the registration returns
+pub fn start_test_rsm(log_port:u16,oam_port:u16) {
+    let log_addr=SocketAddr::new("127.0.0.1".parse().unwrap(),log_port);
+    let oam_addr=SocketAddr::new("127.0.0.1".parse().unwrap(),oam_port);
+    let cfg = rsm::config::rsm_init_cfg_t::new(1, Some(log_addr), Some(oam_addr), None);
+    rsm::rsm_init(&cfg);
+    rsm::start_rsm();
+}
@@ -0,0 +1,181 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+//frame reassembly of FramedStream over the partial reads, and flush of the send buffer
+use rust_rsm::common::errcode;
+use rust_rsm::rsm::{self,socket};
+use rust_rsm::rsm::socket::{FramedStream,STREAM_FRAMER};
+use std::collections::HashMap;
+use std::io::{Read,Write};
+use std::net::{SocketAddr,TcpStream};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::time::Duration;
+use std::thread;
+
+mod test_common;
+use test_common::{wait_until,start_test_rsm};
+
+const TEST_APP_ID:u32 = rsm::RSM_USER_CID_START+63;
+//the framer of a connection is chosen by the listening port
+const LENGTH_PORT:u16 = 16430;
+const DELIMITER_PORT:u16 = 16431;
+const CUSTOM_PORT:u16 = 16432;
+//the app sends FLUSH_FRAMES frames to the peer which doesn't read at first
+const FLUSH_PORT:u16 = 16433;
+const FLUSH_FRAMES:usize = 8000;
+const FLUSH_FRAME_LEN:usize = 1000;
+const DELIMITER:&[u8] = b"\r\n";
+
+static gFrames:Mutex<Vec<(u16,Vec<u8>)>> = Mutex::new(Vec::new());
+static gRecvErrors:AtomicUsize = AtomicUsize::new(0);
+static gPending:AtomicUsize = AtomicUsize::new(0);
+static gFlushed:AtomicUsize = AtomicUsize::new(0);
+
+struct framed_app_t {
+    listeners:Vec<socket::TcpListener>,
+    streams:HashMap<i32,(u16,FramedStream)>,
+}
+
+fn new_framed_app(_cid:&rsm::rsm_component_t)->Box<dyn rsm::Runnable> {
+    return Box::new(framed_app_t { listeners:Vec::new(), streams:HashMap::new() })
+}
+
+fn local_addr(port:u16)->SocketAddr {
+    return SocketAddr::new("127.0.0.1".parse().unwrap(), port)
+}
+
+//the first byte is the length of the whole frame
+fn check_custom_frame(start_bytes:&[u8],_req_size:usize,total:usize)->Result<usize,errcode::RESULT> {
+    if start_bytes.is_empty() || total<start_bytes[0] as usize {
+        return Err(errcode::ERROR_MSG_TOO_SHORT)
+    }
+    return Ok(start_bytes[0] as usize)
+}
+
+impl framed_app_t {
+    fn new_stream(&mut self,sock_id:i32,port:u16) {
+        let framer = match port {
+            LENGTH_PORT=>STREAM_FRAMER::FRAMER_LENGTH_PREFIXED(2),
+            DELIMITER_PORT=>STREAM_FRAMER::FRAMER_DELIMITER(DELIMITER.to_vec()),
+            CUSTOM_PORT=>STREAM_FRAMER::FRAMER_CUSTOM(check_custom_frame),
+            _=>STREAM_FRAMER::FRAMER_LENGTH_PREFIXED(4),
+        };
+        let mut fs = FramedStream::new(sock_id, framer, 1024, 2*FLUSH_FRAMES*FLUSH_FRAME_LEN).unwrap();
+        if port==FLUSH_PORT {
+            for i in 0..FLUSH_FRAMES {
+                let mut frame = vec![0u8;FLUSH_FRAME_LEN];
+                frame[0..4].copy_from_slice(&(i as u32).to_be_bytes());
+                assert_eq!(fs.send_frame(&frame), errcode::RESULT_SUCCESS);
+            }
+            gPending.store(fs.pending_len(), Ordering::SeqCst);
+        }
+        self.streams.insert(sock_id, (port,fs));
+    }
+}
+
+impl rsm::Runnable for framed_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+        for port in [LENGTH_PORT,DELIMITER_PORT,CUSTOM_PORT,FLUSH_PORT] {
+            let lis = socket::TcpListener::new(&local_addr(port), 16, socket::SOCKET_LB_POLICY::SOCK_LB_CALLER_INSTANCE).unwrap();
+            self.listeners.push(lis);
+        }
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,event:rsm::rsm_socket_event_t) {
+        if (event.event & rsm::SOCK_EVENT_NEW)!=0 {
+            let port = socket::TcpSocket::get_socket_by_id(event.socket_id).get_local_addr().unwrap().port();
+            self.new_stream(event.socket_id, port);
+        }
+        let (port,fs) = match self.streams.get_mut(&event.socket_id) {
+            None=>return,
+            Some(s)=>s,
+        };
+        if (event.event & rsm::SOCK_EVENT_READ)!=0 {
+            match fs.recv_frames() {
+                Ok(frames)=>gFrames.lock().unwrap().extend(frames.into_iter().map(|f| (*port,f))),
+                Err(_)=> { gRecvErrors.fetch_add(1, Ordering::SeqCst); },
+            }
+        }
+        if (event.event & rsm::SOCK_EVENT_WRITE)!=0 {
+            if fs.flush()==Ok(0) {
+                gFlushed.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+    fn on_message(&mut self,_cid:&rsm::rsm_component_t,_msg_id:rsm::rsm_message_id_t,_msg:&rsm::rsm_message_t) {
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+}
+
+//write the stream in small pieces, so the frames and the headers are split across the reads
+fn send_in_pieces(port:u16,stream:&[u8])->TcpStream {
+    let mut peer = TcpStream::connect(local_addr(port)).unwrap();
+    peer.set_nodelay(true).unwrap();
+    for piece in stream.chunks(3) {
+        peer.write_all(piece).unwrap();
+        thread::sleep(Duration::from_millis(2));
+    }
+    return peer
+}
+
+fn received_frames(port:u16)->Vec<Vec<u8>> {
+    return gFrames.lock().unwrap().iter().filter(|(p,_)| *p==port).map(|(_,f)| f.clone()).collect()
+}
+
+#[test]
+fn test_framed_stream() {
+    start_test_rsm(15260, 12260);
+    let attrs = rsm::component_attrs_t::new(&TEST_APP_ID, "framed_app", 1, 64, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    assert_eq!(rsm::registry_boxed_component(TEST_APP_ID, &attrs, &rsm::component_policy_t::default(), new_framed_app), errcode::RESULT_SUCCESS);
+    thread::sleep(Duration::from_millis(200));
+
+    let long_payload = vec![0x33u8;300];
+    let payloads:Vec<&[u8]> = vec![b"hello", b"", &long_payload, b"bye"];
+
+    //a 2 bytes big endian length header
+    let mut stream = Vec::new();
+    for p in &payloads {
+        stream.extend_from_slice(&(p.len() as u16).to_be_bytes());
+        stream.extend_from_slice(p);
+    }
+    let _length_peer = send_in_pieces(LENGTH_PORT, &stream);
+    assert!(wait_until(|| received_frames(LENGTH_PORT).len()==payloads.len()));
+    assert_eq!(received_frames(LENGTH_PORT), payloads.iter().map(|p| p.to_vec()).collect::<Vec<Vec<u8>>>());
+
+    //the delimiter may be split across the reads
+    let mut stream = Vec::new();
+    for p in &payloads {
+        stream.extend_from_slice(p);
+        stream.extend_from_slice(DELIMITER);
+    }
+    let _delimiter_peer = send_in_pieces(DELIMITER_PORT, &stream);
+    assert!(wait_until(|| received_frames(DELIMITER_PORT).len()==payloads.len()));
+    assert_eq!(received_frames(DELIMITER_PORT), payloads.iter().map(|p| p.to_vec()).collect::<Vec<Vec<u8>>>());
+
+    //the custom frames are delivered with their headers
+    let custom_frames:Vec<Vec<u8>> = vec![vec![1u8], vec![5u8,1,2,3,4], vec![200u8;200], vec![2u8,9]];
+    let _custom_peer = send_in_pieces(CUSTOM_PORT, &custom_frames.concat());
+    assert!(wait_until(|| received_frames(CUSTOM_PORT).len()==custom_frames.len()));
+    assert_eq!(received_frames(CUSTOM_PORT), custom_frames);
+    assert_eq!(gRecvErrors.load(Ordering::SeqCst), 0);
+
+    //the frames not sent at once are buffered, and flushed in order once the peer reads
+    let mut peer = TcpStream::connect(local_addr(FLUSH_PORT)).unwrap();
+    assert!(wait_until(|| gPending.load(Ordering::SeqCst)>0));
+    assert_eq!(gFlushed.load(Ordering::SeqCst), 0);
+    peer.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let mut frame = vec![0u8;4+FLUSH_FRAME_LEN];
+    for i in 0..FLUSH_FRAMES {
+        peer.read_exact(&mut frame).unwrap();
+        assert_eq!(&frame[0..4], &(FLUSH_FRAME_LEN as u32).to_be_bytes());
+        assert_eq!(&frame[4..8], &(i as u32).to_be_bytes());
+    }
+    assert!(wait_until(|| gFlushed.load(Ordering::SeqCst)==1));
+}
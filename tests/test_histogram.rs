@@ -0,0 +1,28 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use rust_rsm::common::histogram_t;
+
+#[test]
+fn test_histogram() {
+    let mut h = histogram_t::new();
+    assert_eq!(h.percentile(99.0),0);
+    for v in 1..1001 {
+        h.record(v);
+    }
+    assert_eq!(h.count(),1000);
+    assert_eq!(h.min(),1);
+    assert_eq!(h.max(),1000);
+    assert_eq!(h.mean(),500);
+    let p50 = h.percentile(50.0);
+    assert!(p50>=500 && p50<=500+500/8,"p50={}",p50);
+    let p99 = h.percentile(99.0);
+    assert!(p99>=990 && p99<=1000,"p99={}",p99);
+    assert_eq!(h.percentile(100.0),1000);
+
+    h.record(u64::MAX);
+    assert_eq!(h.max(),1<<40);
+    h.reset();
+    assert_eq!(h.count(),0);
+}
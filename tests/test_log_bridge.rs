@@ -0,0 +1,71 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![cfg(feature="log-bridge")]
+
+//the records of the log facade are sent to the xlog server with the module path as the module name
+use rust_rsm::common::errcode;
+use rust_rsm::rsm::xlog::{self,log_bridge};
+use std::net::{IpAddr,UdpSocket};
+use std::time::Duration;
+
+//the module path of the records is test_log_bridge::net
+mod net {
+    pub fn log_records() {
+        log::error!("connect failed");
+        log::warn!("retry {}", 3);
+        log::info!(target:"conn", "connected");
+        log::debug!("send {} bytes", 10);
+        log::trace!("state changed");
+    }
+}
+
+//the records received by a fake xlog server
+fn recv_records(server:&UdpSocket,count:usize)->Vec<serde_json::Value> {
+    let mut records = Vec::new();
+    let mut buf = [0u8;65536];
+    while records.len()<count {
+        let len = match server.recv(&mut buf) {
+            Err(_)=>break,
+            Ok(l)=>l,
+        };
+        records.push(serde_json::from_slice(&buf[0..len]).unwrap());
+    }
+    return records
+}
+
+#[test]
+fn test_log_bridge() {
+    assert_eq!(log_bridge::map_log_level(log::Level::Error), xlog::LOG_LEVEL_ERROR);
+    assert_eq!(log_bridge::map_log_level(log::Level::Warn), xlog::LOG_LEVEL_WARNING);
+    assert_eq!(log_bridge::map_log_level(log::Level::Info), xlog::LOG_LEVEL_INFO);
+    assert_eq!(log_bridge::map_log_level(log::Level::Debug), xlog::LOG_LEVEL_DEBUG);
+    assert_eq!(log_bridge::map_log_level(log::Level::Trace), xlog::LOG_LEVEL_DEBUG);
+
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    server.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let local = IpAddr::from([127,0,0,1]);
+    let mut logger = xlog::xlogger::new_xlogger("app", &local, 0, &local, server.local_addr().unwrap().port());
+    logger.set_log_level(xlog::LOG_LEVEL_INFO);
+    assert_eq!(log_bridge::init_log_bridge(logger), errcode::RESULT_SUCCESS);
+    let other = xlog::xlogger::new_xlogger("app", &local, 0, &local, server.local_addr().unwrap().port());
+    assert_eq!(log_bridge::init_log_bridge(other), errcode::ERROR_ALREADY_EXIST);
+
+    //the debug and trace records are above the level of the xlogger
+    net::log_records();
+    let records = recv_records(&server, 3);
+    assert_eq!(records.len(), 3);
+    server.set_read_timeout(Some(Duration::from_millis(200))).unwrap();
+    assert!(recv_records(&server, 1).is_empty());
+    let levels:Vec<i64> = records.iter().map(|r| r["LogLevel"].as_i64().unwrap()).collect();
+    assert_eq!(levels, vec![xlog::LOG_LEVEL_ERROR as i64, xlog::LOG_LEVEL_WARNING as i64, xlog::LOG_LEVEL_INFO as i64]);
+    for r in records.iter() {
+        assert_eq!(r["ModuleName"], "test_log_bridge::net");
+        assert!(r["Position"].as_str().unwrap().starts_with("tests"));
+    }
+    assert_eq!(records[0]["LogDesc"], "connect failed");
+    assert_eq!(records[1]["LogDesc"], "retry 3");
+    //the target different from the module path is sent as a field
+    assert!(records[1].get("Fields").is_none());
+    assert_eq!(records[2]["Fields"], serde_json::json!([["target",{"LOG_STR":"conn"}]]));
+}
@@ -0,0 +1,61 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+use rust_rsm::rsm::{self,rsm_message_t,E_RSM_MSG_CODEC};
+use rust_rsm::net_ext::pktbuf::{self,PktBufType};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize,Deserialize,Debug,PartialEq,Clone)]
+struct test_body_t {
+    id:u32,
+    name:String,
+    values:Vec<u64>,
+}
+
+#[test]
+fn test_msg_codec() {
+    let body = test_body_t{id:100,name:"rsm".to_string(),values:vec![1,2,3]};
+    let msg1 = rsm_message_t::new::<test_body_t>(rsm::RSM_USER_MESSAGE_ID_START, &body).unwrap();
+    assert_eq!(msg1.get_codec(),E_RSM_MSG_CODEC::MSG_CODEC_JSON);
+    assert_eq!(msg1.decode::<test_body_t>(),Some(body.clone()));
+
+    let msg2 = rsm_message_t::new_with_codec::<test_body_t>(rsm::RSM_USER_MESSAGE_ID_START, 
+        &body,E_RSM_MSG_CODEC::MSG_CODEC_BINARY).unwrap();
+    assert_eq!(msg2.get_codec(),E_RSM_MSG_CODEC::MSG_CODEC_BINARY);
+    assert_eq!(msg2.decode::<test_body_t>(),Some(body.clone()));
+
+    assert!(rsm_message_t::new_with_codec::<test_body_t>(rsm::RSM_USER_MESSAGE_ID_START, 
+        &body,E_RSM_MSG_CODEC::MSG_CODEC_RAW).is_none());
+
+    let msg3 = rsm_message_t::new_raw(rsm::RSM_USER_MESSAGE_ID_START, vec![1u8,2,3,4]);
+    assert_eq!(msg3.get_codec(),E_RSM_MSG_CODEC::MSG_CODEC_RAW);
+    assert_eq!(msg3.get_raw_body(),Some(&[1u8,2,3,4][..]));
+    assert!(msg3.decode::<test_body_t>().is_none());
+}
+
+#[test]
+fn test_msg_pkt_buf() {
+    pktbuf::init_pkt_buf(16);
+    let mut buf = pktbuf::allocate_pkt_buf(PktBufType::BufTypeSend);
+    buf.extend_from_slice(&[1u8,2,3]);
+    let ptr = buf.as_ptr();
+    let mut msg = rsm_message_t::new_pkt_buf(rsm::RSM_USER_MESSAGE_ID_START, buf);
+    assert_eq!(msg.get_codec(),E_RSM_MSG_CODEC::MSG_CODEC_RAW);
+
+    //the clone shares the buffer instead of copying it from the pool
+    let cloned = msg.clone();
+    assert_eq!(cloned.get_pkt_buf().map(|b| b.as_ptr()), Some(ptr));
+    assert_eq!(cloned.get_pkt_buf().map(|b| b.as_slice()), Some(&[1u8,2,3][..]));
+    //a shared buffer can't be taken
+    assert!(msg.take_pkt_buf().is_none());
+    assert!(msg.get_pkt_buf().is_some());
+    drop(cloned);
+
+    let buf = msg.take_pkt_buf().unwrap();
+    assert_eq!(buf.as_ptr(), ptr);
+    assert_eq!(buf.as_slice(), &[1u8,2,3][..]);
+    assert!(msg.take_pkt_buf().is_none());
+    assert!(msg.get_pkt_buf().is_none());
+    assert!(msg.get_raw_body().is_none());
+}
@@ -0,0 +1,100 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+//publish/subscribe between the task instances subscribing the same topics concurrently
+use rust_rsm::common::errcode;
+use rust_rsm::rsm;
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::time::Duration;
+use std::thread;
+
+mod test_common;
+use test_common::{wait_until,start_test_rsm};
+
+const SUB_APP_ID:u32 = rsm::RSM_USER_CID_START+40;
+const SUB_INST_NUM:usize = 8;
+const MSG_DATA:u32 = rsm::RSM_USER_MESSAGE_ID_START+1;
+const MSG_UNSUB:u32 = rsm::RSM_USER_MESSAGE_ID_START+2;
+const TOPIC_A:&str = "test.topic.a";
+const TOPIC_B:&str = "test.topic.b";
+
+static gSubscribed:AtomicUsize = AtomicUsize::new(0);
+static gReceived:AtomicUsize = AtomicUsize::new(0);
+static gPayloadSum:AtomicUsize = AtomicUsize::new(0);
+static gUnsubscribed:AtomicUsize = AtomicUsize::new(0);
+
+struct sub_app_t;
+
+fn new_sub_app(_cid:&rsm::rsm_component_t)->Box<dyn rsm::Runnable> {
+    return Box::new(sub_app_t)
+}
+
+impl rsm::Runnable for sub_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+        //all the instances subscribe the topics at the same time on power on
+        if rsm::subscribe_topic(TOPIC_A)==errcode::RESULT_SUCCESS && rsm::subscribe_topic(TOPIC_B)==errcode::RESULT_SUCCESS
+            && rsm::subscribe_topic(TOPIC_A)==errcode::ERROR_ALREADY_EXIST {
+            gSubscribed.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,_event:rsm::rsm_socket_event_t) {
+    }
+    fn on_message(&mut self,_cid:&rsm::rsm_component_t,msg_id:rsm::rsm_message_id_t,msg:&rsm::rsm_message_t) {
+        match msg_id {
+            MSG_DATA=> {
+                gPayloadSum.fetch_add(msg.decode::<u32>().unwrap() as usize, Ordering::SeqCst);
+                gReceived.fetch_add(1, Ordering::SeqCst);
+            },
+            MSG_UNSUB=> {
+                if rsm::unsubscribe_topic(TOPIC_B)==errcode::RESULT_SUCCESS && rsm::unsubscribe_topic(TOPIC_B)==errcode::ERROR_NOT_FOUND {
+                    gUnsubscribed.fetch_add(1, Ordering::SeqCst);
+                }
+            },
+            _=>(),
+        }
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+}
+
+#[test]
+fn test_publish_subscribe() {
+    start_test_rsm(15210, 12210);
+    let attrs = rsm::component_attrs_t::new(&SUB_APP_ID, "subscriber", SUB_INST_NUM, 64, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    assert_eq!(rsm::registry_boxed_component(SUB_APP_ID, &attrs, &rsm::component_policy_t::default(), new_sub_app), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gSubscribed.load(Ordering::SeqCst)==SUB_INST_NUM));
+
+    //only a rsm task can subscribe
+    assert_eq!(rsm::subscribe_topic(TOPIC_A), errcode::ERROR_INVALID_STATE);
+    assert_eq!(rsm::publish("test.topic.none", MSG_DATA, &1u32), errcode::ERROR_NOT_FOUND);
+
+    //every subscriber receives each published message once
+    assert_eq!(rsm::publish(TOPIC_A, MSG_DATA, &1u32), errcode::RESULT_SUCCESS);
+    assert_eq!(rsm::publish(TOPIC_B, MSG_DATA, &10u32), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gReceived.load(Ordering::SeqCst)==2*SUB_INST_NUM));
+    assert_eq!(gPayloadSum.load(Ordering::SeqCst), 11*SUB_INST_NUM);
+
+    //the topic without subscribers is kept, the message is not delivered
+    for i in 1..=SUB_INST_NUM {
+        let dst = rsm::rsm_component_t::new(SUB_APP_ID, rsm::get_local_node_id(), i);
+        assert_eq!(rsm::send_asyn_msg_ext(&dst, MSG_UNSUB, &0u32), errcode::RESULT_SUCCESS);
+    }
+    assert!(wait_until(|| gUnsubscribed.load(Ordering::SeqCst)==SUB_INST_NUM));
+    assert_eq!(rsm::publish(TOPIC_B, MSG_DATA, &10u32), errcode::ERROR_NO_OP);
+    assert_eq!(rsm::publish(TOPIC_A, MSG_DATA, &100u32), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gReceived.load(Ordering::SeqCst)==3*SUB_INST_NUM));
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(gReceived.load(Ordering::SeqCst), 3*SUB_INST_NUM);
+    assert_eq!(gPayloadSum.load(Ordering::SeqCst), 111*SUB_INST_NUM);
+
+    //the stopped tasks are removed from the subscribers
+    assert_eq!(rsm::stop_component(SUB_APP_ID), errcode::RESULT_SUCCESS);
+    assert_eq!(rsm::publish(TOPIC_A, MSG_DATA, &1000u32), errcode::ERROR_NO_OP);
+    assert_eq!(gReceived.load(Ordering::SeqCst), 3*SUB_INST_NUM);
+}
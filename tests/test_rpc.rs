@@ -0,0 +1,221 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+//request/response between tasks, response correlation, timeout and late response
+use rust_rsm::common::errcode;
+use rust_rsm::rsm::{self,rsm_rpc};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::time::Duration;
+use std::thread;
+
+mod test_common;
+use test_common::{wait_until,start_test_rsm};
+
+const CLIENT_APP_ID:u32 = rsm::RSM_USER_CID_START+50;
+const SERVER_APP_ID:u32 = rsm::RSM_USER_CID_START+51;
+const CLIENT_INST_NUM:usize = 4;
+const SERVER_INST_NUM:usize = 2;
+//kick the client to send the requests, the body is the kind of the request
+const MSG_KICK:u32 = rsm::RSM_USER_MESSAGE_ID_START+1;
+const MSG_ECHO_REQ:u32 = rsm::RSM_USER_MESSAGE_ID_START+2;
+const MSG_ECHO_RSP:u32 = rsm::RSM_USER_MESSAGE_ID_START+3;
+//the server never replies
+const MSG_IGNORED_REQ:u32 = rsm::RSM_USER_MESSAGE_ID_START+4;
+//the server replies after the request timed out
+const MSG_SLOW_REQ:u32 = rsm::RSM_USER_MESSAGE_ID_START+5;
+//the first server instance forwards the request to the second one, whose response is not from the destination
+const MSG_FORWARD_REQ:u32 = rsm::RSM_USER_MESSAGE_ID_START+6;
+const KICK_ECHO:u32 = 0;
+const KICK_IGNORED:u32 = 1;
+const KICK_SLOW:u32 = 2;
+const KICK_FORWARD:u32 = 3;
+//the request is still pending when the client is stopped
+const KICK_PENDING:u32 = 4;
+const ECHO_PER_CLIENT:usize = 200;
+
+static gInits:AtomicUsize = AtomicUsize::new(0);
+static gResponses:AtomicUsize = AtomicUsize::new(0);
+static gMismatch:AtomicUsize = AtomicUsize::new(0);
+static gTimeouts:AtomicUsize = AtomicUsize::new(0);
+static gSendErrors:AtomicUsize = AtomicUsize::new(0);
+
+struct client_app_t {
+    //the sequence carried by each pending request
+    pending:HashMap<rsm::rsm_request_id_t,u32>,
+}
+
+struct server_app_t;
+
+fn new_client_app(_cid:&rsm::rsm_component_t)->Box<dyn rsm::Runnable> {
+    return Box::new(client_app_t { pending:HashMap::new() })
+}
+
+fn new_server_app(_cid:&rsm::rsm_component_t)->Box<dyn rsm::Runnable> {
+    return Box::new(server_app_t)
+}
+
+impl client_app_t {
+    fn request(&mut self,cid:&rsm::rsm_component_t,msg_id:u32,seq:u32,timeout_msec:u64) {
+        let inst_id = if msg_id==MSG_FORWARD_REQ { 1 } else { (cid.get_inst_id()%SERVER_INST_NUM)+1 };
+        let dst = rsm::rsm_component_t::new(SERVER_APP_ID, rsm::get_local_node_id(), inst_id);
+        match rsm::send_request(&dst, msg_id, &seq, timeout_msec) {
+            Ok(req_id)=> { self.pending.insert(req_id, seq); },
+            Err(_)=> { gSendErrors.fetch_add(1, Ordering::SeqCst); },
+        }
+    }
+}
+
+impl rsm::Runnable for client_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+        gInits.fetch_add(1, Ordering::SeqCst);
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,_event:rsm::rsm_socket_event_t) {
+    }
+    fn on_message(&mut self,cid:&rsm::rsm_component_t,msg_id:rsm::rsm_message_id_t,msg:&rsm::rsm_message_t) {
+        if msg_id!=MSG_KICK {
+            return
+        }
+        match msg.decode::<u32>() {
+            Some(KICK_ECHO)=> {
+                for i in 0..ECHO_PER_CLIENT {
+                    self.request(cid, MSG_ECHO_REQ, (cid.get_inst_id()*10000+i) as u32, 5000);
+                }
+            },
+            Some(KICK_IGNORED)=>self.request(cid, MSG_IGNORED_REQ, 0, 100),
+            Some(KICK_SLOW)=>self.request(cid, MSG_SLOW_REQ, 0, 50),
+            Some(KICK_FORWARD)=>self.request(cid, MSG_FORWARD_REQ, 0, 300),
+            Some(KICK_PENDING)=>self.request(cid, MSG_IGNORED_REQ, 0, 60000),
+            _=>(),
+        }
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+    fn on_response(&mut self,_cid:&rsm::rsm_component_t,req_id:rsm::rsm_request_id_t,result:errcode::RESULT,msg:Option<&rsm::rsm_message_t>) {
+        let seq = match self.pending.remove(&req_id) {
+            None=> {
+                gMismatch.fetch_add(1, Ordering::SeqCst);
+                return
+            },
+            Some(s)=>s,
+        };
+        match (result,msg) {
+            (errcode::RESULT_SUCCESS,Some(m))=> {
+                if m.get_request_id()!=req_id || !m.is_response() || m.decode::<u32>()!=Some(seq+1) {
+                    gMismatch.fetch_add(1, Ordering::SeqCst);
+                }
+                gResponses.fetch_add(1, Ordering::SeqCst);
+            },
+            (errcode::ERROR_TIME_OUT,None)=> {
+                gTimeouts.fetch_add(1, Ordering::SeqCst);
+            },
+            _=> {
+                gMismatch.fetch_add(1, Ordering::SeqCst);
+            },
+        }
+    }
+}
+
+impl rsm::Runnable for server_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+        gInits.fetch_add(1, Ordering::SeqCst);
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,_event:rsm::rsm_socket_event_t) {
+    }
+    fn on_message(&mut self,cid:&rsm::rsm_component_t,msg_id:rsm::rsm_message_id_t,msg:&rsm::rsm_message_t) {
+        if !msg.is_request() {
+            return
+        }
+        let seq = msg.decode::<u32>().unwrap();
+        match msg_id {
+            MSG_ECHO_REQ=> {
+                rsm::reply_to(msg, MSG_ECHO_RSP, &(seq+1));
+            },
+            MSG_SLOW_REQ=> {
+                thread::sleep(Duration::from_millis(300));
+                rsm::reply_to(msg, MSG_ECHO_RSP, &(seq+1));
+            },
+            MSG_FORWARD_REQ if cid.get_inst_id()==1=> {
+                let other = rsm::rsm_component_t::new(SERVER_APP_ID, rsm::get_local_node_id(), 2);
+                assert_eq!(rsm::send_asyn_msg(&other, msg.clone()), errcode::RESULT_SUCCESS);
+            },
+            MSG_FORWARD_REQ=> {
+                rsm::reply_to(msg, MSG_ECHO_RSP, &(seq+1));
+            },
+            _=>(),
+        }
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+}
+
+fn kick_clients(kind:u32) {
+    for i in 1..=CLIENT_INST_NUM {
+        let dst = rsm::rsm_component_t::new(CLIENT_APP_ID, rsm::get_local_node_id(), i);
+        assert_eq!(rsm::send_asyn_msg_ext(&dst, MSG_KICK, &kind), errcode::RESULT_SUCCESS);
+    }
+}
+
+#[test]
+fn test_request_response() {
+    start_test_rsm(15220, 12220);
+    let policy = rsm::component_policy_t::default();
+    let attrs = rsm::component_attrs_t::new(&SERVER_APP_ID, "rpc_server", SERVER_INST_NUM, 4096, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    assert_eq!(rsm::registry_boxed_component(SERVER_APP_ID, &attrs, &policy, new_server_app), errcode::RESULT_SUCCESS);
+    let attrs = rsm::component_attrs_t::new(&CLIENT_APP_ID, "rpc_client", CLIENT_INST_NUM, 4096, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    assert_eq!(rsm::registry_boxed_component(CLIENT_APP_ID, &attrs, &policy, new_client_app), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gInits.load(Ordering::SeqCst)==CLIENT_INST_NUM+SERVER_INST_NUM));
+
+    //only a rsm task can send a request
+    let server = rsm::rsm_component_t::new(SERVER_APP_ID, rsm::get_local_node_id(), 1);
+    assert_eq!(rsm::send_request(&server, MSG_ECHO_REQ, &0u32, 100), Err(errcode::ERROR_INVALID_STATE));
+
+    //the clients send the requests concurrently, each response is correlated to its request
+    kick_clients(KICK_ECHO);
+    let total = CLIENT_INST_NUM*ECHO_PER_CLIENT;
+    assert!(wait_until(|| gResponses.load(Ordering::SeqCst)==total), "responses={}", gResponses.load(Ordering::SeqCst));
+    let stats = rsm_rpc::get_rpc_stats();
+    assert_eq!((stats.sent_request,stats.recv_response,stats.timeout,stats.pending), (total as u64,total as u64,0,0));
+
+    //no response, the requester is notified by the timeout
+    kick_clients(KICK_IGNORED);
+    assert!(wait_until(|| gTimeouts.load(Ordering::SeqCst)==CLIENT_INST_NUM));
+
+    //the response received after the timeout is dropped and counted as late
+    kick_clients(KICK_SLOW);
+    assert!(wait_until(|| rsm_rpc::get_rpc_stats().late_response==CLIENT_INST_NUM as u64));
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(gTimeouts.load(Ordering::SeqCst), 2*CLIENT_INST_NUM);
+    assert_eq!(gResponses.load(Ordering::SeqCst), total);
+
+    let stats = rsm_rpc::get_rpc_stats();
+    assert_eq!((stats.sent_request,stats.recv_response,stats.timeout,stats.late_response,stats.pending),
+        ((total+2*CLIENT_INST_NUM) as u64,total as u64,2*CLIENT_INST_NUM as u64,CLIENT_INST_NUM as u64,0));
+    assert_eq!(gMismatch.load(Ordering::SeqCst), 0);
+    assert_eq!(gSendErrors.load(Ordering::SeqCst), 0);
+
+    //the response sent by another server instance doesn't complete the request, which times out
+    kick_clients(KICK_FORWARD);
+    assert!(wait_until(|| gTimeouts.load(Ordering::SeqCst)==3*CLIENT_INST_NUM));
+    let stats = rsm_rpc::get_rpc_stats();
+    assert_eq!((stats.recv_response,stats.late_response,stats.pending), (total as u64,2*CLIENT_INST_NUM as u64,0));
+    assert_eq!(gResponses.load(Ordering::SeqCst), total);
+
+    //the pending requests are removed with the stopped requester
+    kick_clients(KICK_PENDING);
+    assert!(wait_until(|| rsm_rpc::get_rpc_stats().pending==CLIENT_INST_NUM));
+    assert_eq!(rsm::stop_component(CLIENT_APP_ID), errcode::RESULT_SUCCESS);
+    assert_eq!(rsm_rpc::get_rpc_stats().pending, 0);
+    assert_eq!(gMismatch.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,94 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+//scale a component registered at runtime from several threads, and stop it while scaling
+use rust_rsm::common::errcode;
+use rust_rsm::rsm;
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::thread;
+
+mod test_common;
+use test_common::{wait_until,start_test_rsm};
+
+const SCALE_APP_ID:u32 = rsm::RSM_USER_CID_START+60;
+const MAX_INST_NUM:usize = 10;
+const MSG_PING:u32 = rsm::RSM_USER_MESSAGE_ID_START+1;
+
+static gAlive:AtomicUsize = AtomicUsize::new(0);
+
+struct scale_app_t;
+
+fn new_scale_app(_cid:&rsm::rsm_component_t)->Box<dyn rsm::Runnable> {
+    return Box::new(scale_app_t)
+}
+
+impl rsm::Runnable for scale_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+        gAlive.fetch_add(1, Ordering::SeqCst);
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,_event:rsm::rsm_socket_event_t) {
+    }
+    fn on_message(&mut self,_cid:&rsm::rsm_component_t,_msg_id:rsm::rsm_message_id_t,_msg:&rsm::rsm_message_t) {
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+        gAlive.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+//the number of the instances accepting messages
+fn reachable_inst_num()->usize {
+    let mut count = 0;
+    for i in 1..=MAX_INST_NUM {
+        let dst = rsm::rsm_component_t::new(SCALE_APP_ID, rsm::get_local_node_id(), i);
+        if rsm::send_asyn_msg_ext(&dst, MSG_PING, &0u32)==errcode::RESULT_SUCCESS {
+            count+=1;
+        }
+    }
+    return count
+}
+
+fn register_scale_app(inst_num:usize) {
+    let attrs = rsm::component_attrs_t::new(&SCALE_APP_ID, "scale_app", inst_num, 64, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    assert_eq!(rsm::registry_boxed_component(SCALE_APP_ID, &attrs, &rsm::component_policy_t::default(), new_scale_app), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gAlive.load(Ordering::SeqCst)==inst_num));
+}
+
+#[test]
+fn test_scale_component() {
+    start_test_rsm(15280, 12280);
+    register_scale_app(2);
+
+    //the concurrent scaling is serialized, the running instances match the last instance number
+    let mut handles = Vec::new();
+    for n in [6,3,MAX_INST_NUM,4,7] {
+        handles.push(thread::spawn(move || rsm::scale_component(SCALE_APP_ID, n)));
+    }
+    for h in handles {
+        let ret = h.join().unwrap();
+        assert!(ret==errcode::RESULT_SUCCESS || ret==errcode::ERROR_NO_OP);
+    }
+    let alive = gAlive.load(Ordering::SeqCst);
+    assert!(alive>=3 && alive<=MAX_INST_NUM);
+    assert_eq!(reachable_inst_num(), alive);
+
+    assert_eq!(rsm::scale_component(SCALE_APP_ID, 1), errcode::RESULT_SUCCESS);
+    assert_eq!(gAlive.load(Ordering::SeqCst), 1);
+    assert_eq!(reachable_inst_num(), 1);
+    assert_eq!(rsm::scale_component(SCALE_APP_ID, 1), errcode::ERROR_NO_OP);
+
+    //the instances added by a scaling in progress are stopped with the component
+    let h = thread::spawn(|| rsm::scale_component(SCALE_APP_ID, 5));
+    let ret = rsm::stop_component(SCALE_APP_ID);
+    assert_eq!(ret, errcode::RESULT_SUCCESS);
+    let ret = h.join().unwrap();
+    assert!(ret==errcode::RESULT_SUCCESS || ret==errcode::ERROR_NOT_FOUND);
+    assert_eq!(gAlive.load(Ordering::SeqCst), 0);
+    assert_eq!(reachable_inst_num(), 0);
+    assert_eq!(rsm::scale_component(SCALE_APP_ID, 2), errcode::ERROR_NOT_FOUND);
+}
@@ -0,0 +1,132 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![cfg(unix)]
+
+//asynchronous tcp connect on the loopback, the result is reported by SOCK_EVENT_CONNECT
+use rust_rsm::common::errcode;
+use rust_rsm::rsm::{self,socket};
+use std::collections::HashMap;
+use std::net::{SocketAddr,TcpListener,TcpStream};
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::time::{Duration,Instant};
+use std::thread;
+
+mod test_common;
+use test_common::{wait_until,start_test_rsm};
+
+const TEST_APP_ID:u32 = rsm::RSM_USER_CID_START+61;
+//the body is the case index, the peer port is PEER_PORT_BASE+case
+const MSG_CONNECT:u32 = rsm::RSM_USER_MESSAGE_ID_START+1;
+const PEER_PORT_BASE:u16 = 16410;
+const CASE_CONNECTED:usize = 0;
+const CASE_REFUSED:usize = 1;
+const CASE_TIMEOUT:usize = 2;
+const CONNECT_TIMEOUT_MSEC:u64 = 300;
+//no result yet
+const NO_RESULT:usize = usize::MAX;
+
+static gResults:[AtomicUsize;3] = [AtomicUsize::new(NO_RESULT),AtomicUsize::new(NO_RESULT),AtomicUsize::new(NO_RESULT)];
+static gEvents:[AtomicUsize;3] = [AtomicUsize::new(0),AtomicUsize::new(0),AtomicUsize::new(0)];
+
+struct connect_app_t {
+    cases:HashMap<i32,usize>,
+}
+
+fn new_connect_app(_cid:&rsm::rsm_component_t)->Box<dyn rsm::Runnable> {
+    return Box::new(connect_app_t { cases:HashMap::new() })
+}
+
+impl rsm::Runnable for connect_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,event:rsm::rsm_socket_event_t) {
+        if (event.event & rsm::SOCK_EVENT_CONNECT)==0 {
+            return
+        }
+        if let Some(case) = self.cases.get(&event.socket_id) {
+            gEvents[*case].fetch_add(1, Ordering::SeqCst);
+            gResults[*case].store(event.result as usize, Ordering::SeqCst);
+        }
+    }
+    fn on_message(&mut self,_cid:&rsm::rsm_component_t,msg_id:rsm::rsm_message_id_t,msg:&rsm::rsm_message_t) {
+        if msg_id!=MSG_CONNECT {
+            return
+        }
+        let case = msg.decode::<u32>().unwrap() as usize;
+        let mut sock = socket::TcpSocket::new(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        assert_eq!(sock.set_connect_timeout(CONNECT_TIMEOUT_MSEC), errcode::RESULT_SUCCESS);
+        let peer = SocketAddr::new("127.0.0.1".parse().unwrap(), PEER_PORT_BASE+case as u16);
+        self.cases.insert(sock.get_socket_id(), case);
+        //the result is known at once or reported by the event later
+        let ret = sock.connect(&peer);
+        if ret!=errcode::ERROR_WOULD_BLOCK {
+            gResults[case].store(ret as usize, Ordering::SeqCst);
+        }
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+}
+
+fn wait_result(case:usize)->usize {
+    wait_until(|| gResults[case].load(Ordering::SeqCst)!=NO_RESULT);
+    return gResults[case].load(Ordering::SeqCst)
+}
+
+fn connect(case:usize)->Instant {
+    let app = rsm::rsm_component_t::new(TEST_APP_ID, rsm::get_local_node_id(), 1);
+    assert_eq!(rsm::send_asyn_msg_ext(&app, MSG_CONNECT, &(case as u32)), errcode::RESULT_SUCCESS);
+    return Instant::now()
+}
+
+//a listener never accepting, its backlog is filled so that the further syn is dropped
+fn new_full_listener(port:u16)->i32 {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert!(fd>=0);
+    let mut sa = unsafe { std::mem::zeroed::<libc::sockaddr_in>() };
+    sa.sin_family = libc::AF_INET as libc::sa_family_t;
+    sa.sin_port = port.to_be();
+    sa.sin_addr.s_addr = u32::from_be_bytes([127,0,0,1]).to_be();
+    let ret = unsafe { libc::bind(fd, std::ptr::addr_of!(sa) as *const libc::sockaddr, std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t) };
+    assert_eq!(ret, 0);
+    assert_eq!(unsafe { libc::listen(fd, 0) }, 0);
+    let addr = SocketAddr::new("127.0.0.1".parse().unwrap(), port);
+    for _ in 0..4 {
+        let _ = TcpStream::connect_timeout(&addr, Duration::from_millis(100)).map(std::mem::forget);
+    }
+    return fd
+}
+
+#[test]
+fn test_async_connect() {
+    start_test_rsm(15240, 12240);
+    let attrs = rsm::component_attrs_t::new(&TEST_APP_ID, "connect_app", 1, 64, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    assert_eq!(rsm::registry_boxed_component(TEST_APP_ID, &attrs, &rsm::component_policy_t::default(), new_connect_app), errcode::RESULT_SUCCESS);
+    thread::sleep(Duration::from_millis(200));
+
+    let _listener = TcpListener::bind(("127.0.0.1", PEER_PORT_BASE+CASE_CONNECTED as u16)).unwrap();
+    connect(CASE_CONNECTED);
+    assert_eq!(wait_result(CASE_CONNECTED), errcode::RESULT_SUCCESS as usize);
+
+    //nothing listens on the port, the connection is reset by the peer
+    connect(CASE_REFUSED);
+    assert_eq!(wait_result(CASE_REFUSED), errcode::ERROR_CONNECTION as usize);
+
+    let fd = new_full_listener(PEER_PORT_BASE+CASE_TIMEOUT as u16);
+    let start = connect(CASE_TIMEOUT);
+    assert_eq!(wait_result(CASE_TIMEOUT), errcode::ERROR_TIME_OUT as usize);
+    //the timer may expire up to one tick earlier
+    let min_elapsed = Duration::from_millis(CONNECT_TIMEOUT_MSEC-rsm::config::RSM_DEF_TIMER_TICK_MSEC);
+    assert!(start.elapsed()>=min_elapsed, "elapsed {:?}", start.elapsed());
+
+    //each connect in progress is reported once
+    thread::sleep(Duration::from_millis(CONNECT_TIMEOUT_MSEC+100));
+    assert!(gEvents.iter().all(|e| e.load(Ordering::SeqCst)<=1));
+    assert_eq!(gEvents[CASE_TIMEOUT].load(Ordering::SeqCst), 1);
+    unsafe { libc::close(fd) };
+}
@@ -0,0 +1,124 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+//a partial send of a tcp socket is followed by exactly one SOCK_EVENT_WRITE once the peer drains the data
+use rust_rsm::common::errcode;
+use rust_rsm::rsm::{self,socket};
+use std::io::Read;
+use std::net::{SocketAddr,TcpListener};
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::time::Duration;
+use std::thread;
+
+mod test_common;
+use test_common::{wait_until,start_test_rsm};
+
+const TEST_APP_ID:u32 = rsm::RSM_USER_CID_START+60;
+const MSG_CONNECT:u32 = rsm::RSM_USER_MESSAGE_ID_START+1;
+const PEER_PORT:u16 = 16400;
+const CHUNK_SIZE:usize = 65536;
+
+static gConnected:AtomicUsize = AtomicUsize::new(0);
+static gPartialSends:AtomicUsize = AtomicUsize::new(0);
+static gWriteEvents:AtomicUsize = AtomicUsize::new(0);
+static gSentBytes:AtomicUsize = AtomicUsize::new(0);
+
+struct write_app_t {
+    sock:Option<socket::TcpSocket>,
+}
+
+fn new_write_app(_cid:&rsm::rsm_component_t)->Box<dyn rsm::Runnable> {
+    return Box::new(write_app_t { sock:None })
+}
+
+impl write_app_t {
+    //send until the socket send buffer is full
+    fn fill_send_buffer(&mut self) {
+        let sock = match &mut self.sock {
+            None=>return,
+            Some(s)=>s,
+        };
+        let buf = vec![0x5au8;CHUNK_SIZE];
+        loop {
+            let len = match sock.send(&buf) {
+                Ok(l)=>l,
+                Err(_)=>return,
+            };
+            gSentBytes.fetch_add(len, Ordering::SeqCst);
+            if len<buf.len() {
+                gPartialSends.fetch_add(1, Ordering::SeqCst);
+                return
+            }
+        }
+    }
+}
+
+impl rsm::Runnable for write_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,event:rsm::rsm_socket_event_t) {
+        if (event.event & rsm::SOCK_EVENT_CONNECT)!=0 && event.result==errcode::RESULT_SUCCESS {
+            gConnected.fetch_add(1, Ordering::SeqCst);
+            self.fill_send_buffer();
+        }
+        //the remaining data is not sent, no more write event is expected
+        if (event.event & rsm::SOCK_EVENT_WRITE)!=0 {
+            gWriteEvents.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+    fn on_message(&mut self,_cid:&rsm::rsm_component_t,msg_id:rsm::rsm_message_id_t,_msg:&rsm::rsm_message_t) {
+        if msg_id!=MSG_CONNECT {
+            return
+        }
+        let mut sock = socket::TcpSocket::new(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let peer = SocketAddr::new("127.0.0.1".parse().unwrap(), PEER_PORT);
+        let ret = sock.connect(&peer);
+        self.sock = Some(sock);
+        if ret==errcode::RESULT_SUCCESS {
+            gConnected.fetch_add(1, Ordering::SeqCst);
+            self.fill_send_buffer();
+        }
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+}
+
+#[test]
+fn test_partial_send_write_event() {
+    start_test_rsm(15230, 12230);
+    let attrs = rsm::component_attrs_t::new(&TEST_APP_ID, "write_app", 1, 64, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    assert_eq!(rsm::registry_boxed_component(TEST_APP_ID, &attrs, &rsm::component_policy_t::default(), new_write_app), errcode::RESULT_SUCCESS);
+
+    //the peer doesn't read until the send buffer of the rsm socket is full
+    let listener = TcpListener::bind(("127.0.0.1", PEER_PORT)).unwrap();
+    let app = rsm::rsm_component_t::new(TEST_APP_ID, rsm::get_local_node_id(), 1);
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(rsm::send_asyn_msg_ext(&app, MSG_CONNECT, &0u32), errcode::RESULT_SUCCESS);
+    let (mut peer,_) = listener.accept().unwrap();
+    assert!(wait_until(|| gPartialSends.load(Ordering::SeqCst)==1));
+    assert_eq!(gConnected.load(Ordering::SeqCst), 1);
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(gWriteEvents.load(Ordering::SeqCst), 0);
+
+    //drain the data, the socket becomes writable and the owner is notified once
+    let mut buf = vec![0u8;CHUNK_SIZE];
+    let mut received = 0;
+    peer.set_read_timeout(Some(Duration::from_millis(500))).unwrap();
+    while received<gSentBytes.load(Ordering::SeqCst) {
+        match peer.read(&mut buf) {
+            Ok(l) if l>0=>received+=l,
+            _=>break,
+        }
+    }
+    assert_eq!(received, gSentBytes.load(Ordering::SeqCst));
+    assert!(wait_until(|| gWriteEvents.load(Ordering::SeqCst)>=1));
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(gWriteEvents.load(Ordering::SeqCst), 1);
+    assert_eq!(gPartialSends.load(Ordering::SeqCst), 1);
+}
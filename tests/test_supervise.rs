@@ -0,0 +1,130 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+//restart of the panicked task instances according to the restart policy
+use rust_rsm::common::errcode;
+use rust_rsm::rsm;
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::time::Duration;
+use std::thread;
+
+mod test_common;
+use test_common::{wait_until,start_test_rsm};
+
+const ALWAYS_APP_ID:u32 = rsm::RSM_USER_CID_START+30;
+const LIMITED_APP_ID:u32 = rsm::RSM_USER_CID_START+31;
+const NEVER_APP_ID:u32 = rsm::RSM_USER_CID_START+32;
+const MSG_PANIC:u32 = rsm::RSM_USER_MESSAGE_ID_START+1;
+const MSG_PING:u32 = rsm::RSM_USER_MESSAGE_ID_START+2;
+
+//index by the application, 0:always,1:limited,2:never
+static gInits:[AtomicUsize;3] = [AtomicUsize::new(0),AtomicUsize::new(0),AtomicUsize::new(0)];
+static gPings:[AtomicUsize;3] = [AtomicUsize::new(0),AtomicUsize::new(0),AtomicUsize::new(0)];
+//boxed instances dropped by rsm
+static gDrops:AtomicUsize = AtomicUsize::new(0);
+
+struct test_app_t {
+    idx:usize,
+}
+
+static mut alwaysApp:test_app_t = test_app_t { idx:0 };
+static mut neverApp:test_app_t = test_app_t { idx:2 };
+
+fn new_always_app(_cid:&rsm::rsm_component_t)->&'static mut dyn rsm::Runnable {
+    return unsafe { &mut alwaysApp }
+}
+
+fn new_never_app(_cid:&rsm::rsm_component_t)->&'static mut dyn rsm::Runnable {
+    return unsafe { &mut neverApp }
+}
+
+fn new_limited_app(_cid:&rsm::rsm_component_t)->Box<dyn rsm::Runnable> {
+    return Box::new(test_app_t { idx:1 })
+}
+
+impl Drop for test_app_t {
+    fn drop(&mut self) {
+        gDrops.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl rsm::Runnable for test_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+        gInits[self.idx].fetch_add(1, Ordering::SeqCst);
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,_event:rsm::rsm_socket_event_t) {
+    }
+    fn on_message(&mut self,_cid:&rsm::rsm_component_t,msg_id:rsm::rsm_message_id_t,_msg:&rsm::rsm_message_t) {
+        match msg_id {
+            MSG_PANIC=>panic!("test panic of app {}",self.idx),
+            MSG_PING=>{ gPings[self.idx].fetch_add(1, Ordering::SeqCst); },
+            _=>(),
+        }
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+}
+
+fn send(cid:u32,msg_id:u32)->errcode::RESULT {
+    let dst = rsm::rsm_component_t::new(cid, rsm::get_local_node_id(), 1);
+    return rsm::send_asyn_msg_ext(&dst, msg_id, &0u32)
+}
+
+#[test]
+fn test_restart_on_panic() {
+    start_test_rsm(15200, 12200);
+
+    let attrs = rsm::component_attrs_t::new(&ALWAYS_APP_ID, "always", 1, 64, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    let mut policy = rsm::component_policy_t::default();
+    policy.set_restart_policy(rsm::E_RSM_RESTART_POLICY::RESTART_ALWAYS, 0, 0);
+    assert_eq!(rsm::registry_component_with_policy(ALWAYS_APP_ID, &attrs, &policy, new_always_app), errcode::RESULT_SUCCESS);
+
+    //at most one restart in one second
+    let attrs = rsm::component_attrs_t::new(&LIMITED_APP_ID, "limited", 1, 64, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    let mut policy = rsm::component_policy_t::default();
+    policy.set_restart_policy(rsm::E_RSM_RESTART_POLICY::RESTART_LIMITED, 1, 1);
+    assert_eq!(rsm::registry_boxed_component(LIMITED_APP_ID, &attrs, &policy, new_limited_app), errcode::RESULT_SUCCESS);
+
+    let attrs = rsm::component_attrs_t::new(&NEVER_APP_ID, "never", 1, 64, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    assert_eq!(rsm::registry_component(NEVER_APP_ID, &attrs, new_never_app), errcode::RESULT_SUCCESS);
+
+    assert!(wait_until(|| gInits.iter().all(|i| i.load(Ordering::SeqCst)==1)));
+
+    //RESTART_ALWAYS, the instance is powered on again after each panic and keeps serving
+    for i in 0..5 {
+        assert_eq!(send(ALWAYS_APP_ID, MSG_PANIC), errcode::RESULT_SUCCESS);
+        assert!(wait_until(|| gInits[0].load(Ordering::SeqCst)==i+2));
+    }
+    assert_eq!(send(ALWAYS_APP_ID, MSG_PING), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gPings[0].load(Ordering::SeqCst)==1));
+
+    //RESTART_LIMITED, the restarts out of the window are not counted
+    assert_eq!(send(LIMITED_APP_ID, MSG_PANIC), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gInits[1].load(Ordering::SeqCst)==2));
+    thread::sleep(Duration::from_millis(1100));
+    assert_eq!(send(LIMITED_APP_ID, MSG_PANIC), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gInits[1].load(Ordering::SeqCst)==3));
+    //the old boxed instances are dropped when restarted
+    assert_eq!(gDrops.load(Ordering::SeqCst), 2);
+    assert_eq!(send(LIMITED_APP_ID, MSG_PING), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gPings[1].load(Ordering::SeqCst)==1));
+
+    //the second restart within the window exceeds max_restart, the task exits
+    assert_eq!(send(LIMITED_APP_ID, MSG_PANIC), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| send(LIMITED_APP_ID, MSG_PING)==errcode::ERROR_INVALID_STATE));
+    assert_eq!(gInits[1].load(Ordering::SeqCst), 3);
+    assert_eq!(gPings[1].load(Ordering::SeqCst), 1);
+
+    //RESTART_NEVER is the default policy
+    assert_eq!(send(NEVER_APP_ID, MSG_PANIC), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| send(NEVER_APP_ID, MSG_PING)==errcode::ERROR_INVALID_STATE));
+    assert_eq!(gInits[2].load(Ordering::SeqCst), 1);
+    assert_eq!(gPings[2].load(Ordering::SeqCst), 0);
+    assert_eq!(gInits[0].load(Ordering::SeqCst), 6);
+}
@@ -0,0 +1,165 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![cfg(feature="tls")]
+
+//TLS handshake on the loopback with the certificates in tests/tls, the certificates are signed by ca.pem, other_ca.pem is unrelated
+use rust_rsm::common::errcode;
+use rust_rsm::rsm::{self,socket};
+use std::collections::HashMap;
+use std::net::{SocketAddr,TcpStream};
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::time::{Duration,Instant};
+use std::thread;
+
+mod test_common;
+use test_common::{wait_until,start_test_rsm};
+
+const TEST_APP_ID:u32 = rsm::RSM_USER_CID_START+62;
+//the body is the case index
+const MSG_CONNECT:u32 = rsm::RSM_USER_MESSAGE_ID_START+1;
+const TLS_PORT:u16 = 16420;
+//the peer never sends the client hello
+const SILENT_PORT:u16 = 16421;
+const HANDSHAKE_TIMEOUT_MSEC:u64 = 300;
+const CASE_TRUSTED:usize = 0;
+const CASE_WRONG_CA:usize = 1;
+const HELLO:&[u8] = b"hello over tls";
+//no result yet
+const NO_RESULT:usize = usize::MAX;
+
+static gClientResults:[AtomicUsize;2] = [AtomicUsize::new(NO_RESULT),AtomicUsize::new(NO_RESULT)];
+static gServerHandshakes:AtomicUsize = AtomicUsize::new(0);
+static gServerData:AtomicUsize = AtomicUsize::new(0);
+static gSilentResult:AtomicUsize = AtomicUsize::new(NO_RESULT);
+static gSilentEvents:AtomicUsize = AtomicUsize::new(0);
+
+struct tls_app_t {
+    listeners:Vec<socket::TcpListener>,
+    clients:HashMap<i32,usize>,
+}
+
+fn new_tls_app(_cid:&rsm::rsm_component_t)->Box<dyn rsm::Runnable> {
+    return Box::new(tls_app_t { listeners:Vec::new(), clients:HashMap::new() })
+}
+
+fn cert_file(name:&str)->String {
+    return format!("{}/tests/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn local_addr(port:u16)->SocketAddr {
+    return SocketAddr::new("127.0.0.1".parse().unwrap(), port)
+}
+
+impl tls_app_t {
+    fn on_server_event(&mut self,event:&rsm::rsm_socket_event_t,port:u16) {
+        if (event.event & rsm::SOCK_EVENT_TLS_HANDSHAKE)!=0 {
+            if port==SILENT_PORT {
+                gSilentEvents.fetch_add(1, Ordering::SeqCst);
+                gSilentResult.store(event.result as usize, Ordering::SeqCst);
+            } else if event.result==errcode::RESULT_SUCCESS {
+                gServerHandshakes.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        if (event.event & rsm::SOCK_EVENT_READ)!=0 {
+            let mut sock = socket::TcpSocket::get_socket_by_id(event.socket_id);
+            let mut buf = [0u8;64];
+            if let Ok(l) = sock.recv(&mut buf) {
+                if &buf[..l]==HELLO {
+                    gServerData.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+}
+
+impl rsm::Runnable for tls_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+        let config = socket::TlsServerConfig::from_pem_files(&cert_file("srv.pem"), &cert_file("srv.key"), None).unwrap();
+        for port in [TLS_PORT,SILENT_PORT] {
+            let mut lis = socket::TcpListener::new(&local_addr(port), 16, socket::SOCKET_LB_POLICY::SOCK_LB_CALLER_INSTANCE).unwrap();
+            assert_eq!(lis.enable_tls(&config), errcode::RESULT_SUCCESS);
+            if port==SILENT_PORT {
+                assert_eq!(lis.set_tls_handshake_timeout(HANDSHAKE_TIMEOUT_MSEC), errcode::RESULT_SUCCESS);
+            }
+            self.listeners.push(lis);
+        }
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,event:rsm::rsm_socket_event_t) {
+        let case = match self.clients.get(&event.socket_id) {
+            None=> {
+                let sock = socket::TcpSocket::get_socket_by_id(event.socket_id);
+                if let Some(addr) = sock.get_local_addr() {
+                    self.on_server_event(&event, addr.port());
+                }
+                return
+            },
+            Some(c)=>*c,
+        };
+        if (event.event & rsm::SOCK_EVENT_TLS_HANDSHAKE)==0 {
+            return
+        }
+        gClientResults[case].store(event.result as usize, Ordering::SeqCst);
+        if event.result==errcode::RESULT_SUCCESS {
+            let mut sock = socket::TcpSocket::get_socket_by_id(event.socket_id);
+            assert!(sock.is_tls_established());
+            assert_eq!(sock.send(HELLO), Ok(HELLO.len()));
+        }
+    }
+    fn on_message(&mut self,_cid:&rsm::rsm_component_t,msg_id:rsm::rsm_message_id_t,msg:&rsm::rsm_message_t) {
+        if msg_id!=MSG_CONNECT {
+            return
+        }
+        let case = msg.decode::<u32>().unwrap() as usize;
+        let ca_file = if case==CASE_TRUSTED { cert_file("ca.pem") } else { cert_file("other_ca.pem") };
+        let config = socket::TlsClientConfig::from_pem_files(&ca_file, None).unwrap();
+        let mut sock = socket::TcpSocket::new(&local_addr(0)).unwrap();
+        self.clients.insert(sock.get_socket_id(), case);
+        let ret = sock.connect_tls(&local_addr(TLS_PORT), "localhost", &config);
+        assert_eq!(ret, errcode::ERROR_WOULD_BLOCK);
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+}
+
+fn wait_result(result:&AtomicUsize)->usize {
+    wait_until(|| result.load(Ordering::SeqCst)!=NO_RESULT);
+    return result.load(Ordering::SeqCst)
+}
+
+fn connect(case:usize) {
+    let app = rsm::rsm_component_t::new(TEST_APP_ID, rsm::get_local_node_id(), 1);
+    assert_eq!(rsm::send_asyn_msg_ext(&app, MSG_CONNECT, &(case as u32)), errcode::RESULT_SUCCESS);
+}
+
+#[test]
+fn test_tls_handshake() {
+    start_test_rsm(15250, 12250);
+    let attrs = rsm::component_attrs_t::new(&TEST_APP_ID, "tls_app", 1, 64, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    assert_eq!(rsm::registry_boxed_component(TEST_APP_ID, &attrs, &rsm::component_policy_t::default(), new_tls_app), errcode::RESULT_SUCCESS);
+    thread::sleep(Duration::from_millis(200));
+
+    //both sides finish the handshake, and the data is decrypted by the server
+    connect(CASE_TRUSTED);
+    assert_eq!(wait_result(&gClientResults[CASE_TRUSTED]), errcode::RESULT_SUCCESS as usize);
+    assert!(wait_until(|| gServerData.load(Ordering::SeqCst)==1));
+    assert_eq!(gServerHandshakes.load(Ordering::SeqCst), 1);
+
+    //the server certificate is not signed by the trusted CA
+    connect(CASE_WRONG_CA);
+    assert_eq!(wait_result(&gClientResults[CASE_WRONG_CA]), errcode::ERROR_AUTH_FAILED as usize);
+    assert_eq!(gServerHandshakes.load(Ordering::SeqCst), 1);
+
+    //the peer is connected but never starts the handshake
+    let start = Instant::now();
+    let _peer = TcpStream::connect(local_addr(SILENT_PORT)).unwrap();
+    assert_eq!(wait_result(&gSilentResult), errcode::ERROR_TIME_OUT as usize);
+    assert!(start.elapsed()>=Duration::from_millis(HANDSHAKE_TIMEOUT_MSEC-rsm::config::RSM_DEF_TIMER_TICK_MSEC));
+    thread::sleep(Duration::from_millis(HANDSHAKE_TIMEOUT_MSEC));
+    assert_eq!(gSilentEvents.load(Ordering::SeqCst), 1);
+}
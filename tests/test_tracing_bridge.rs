@@ -0,0 +1,68 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![cfg(feature="tracing-bridge")]
+
+//the tracing events are sent to the xlog server with the module path as the module name and the event fields as structured fields
+use rust_rsm::rsm::xlog::{self,tracing_bridge};
+use std::net::{IpAddr,UdpSocket};
+use std::time::Duration;
+use tracing_subscriber::layer::SubscriberExt;
+
+//the module path of the events is test_tracing_bridge::net
+mod net {
+    pub fn emit_events() {
+        tracing::error!(peer="1.1.1.1:80", "connect failed");
+        tracing::warn!(retry=3u64, ok=false, "retry");
+        tracing::info!(ratio=0.5, delta=-2i64, "connected");
+        tracing::debug!(len=10u64, "send");
+        tracing::trace!("state changed");
+    }
+}
+
+//the records received by a fake xlog server
+fn recv_records(server:&UdpSocket,count:usize)->Vec<serde_json::Value> {
+    let mut records = Vec::new();
+    let mut buf = [0u8;65536];
+    while records.len()<count {
+        let len = match server.recv(&mut buf) {
+            Err(_)=>break,
+            Ok(l)=>l,
+        };
+        records.push(serde_json::from_slice(&buf[0..len]).unwrap());
+    }
+    return records
+}
+
+#[test]
+fn test_tracing_bridge() {
+    assert_eq!(tracing_bridge::map_tracing_level(&tracing::Level::ERROR), xlog::LOG_LEVEL_ERROR);
+    assert_eq!(tracing_bridge::map_tracing_level(&tracing::Level::WARN), xlog::LOG_LEVEL_WARNING);
+    assert_eq!(tracing_bridge::map_tracing_level(&tracing::Level::INFO), xlog::LOG_LEVEL_INFO);
+    assert_eq!(tracing_bridge::map_tracing_level(&tracing::Level::DEBUG), xlog::LOG_LEVEL_DEBUG);
+    assert_eq!(tracing_bridge::map_tracing_level(&tracing::Level::TRACE), xlog::LOG_LEVEL_DEBUG);
+
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    server.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let local = IpAddr::from([127,0,0,1]);
+    let mut logger = xlog::xlogger::new_xlogger("app", &local, 0, &local, server.local_addr().unwrap().port());
+    logger.set_log_level(xlog::LOG_LEVEL_DEBUG);
+    let subscriber = tracing_subscriber::registry().with(tracing_bridge::tracing_layer_t::new(logger));
+
+    //the trace event is mapped to LOG_LEVEL_DEBUG
+    tracing::subscriber::with_default(subscriber, || net::emit_events());
+    let records = recv_records(&server, 5);
+    assert_eq!(records.len(), 5);
+    let levels:Vec<i64> = records.iter().map(|r| r["LogLevel"].as_i64().unwrap()).collect();
+    assert_eq!(levels, vec![xlog::LOG_LEVEL_ERROR as i64, xlog::LOG_LEVEL_WARNING as i64, xlog::LOG_LEVEL_INFO as i64,
+        xlog::LOG_LEVEL_DEBUG as i64, xlog::LOG_LEVEL_DEBUG as i64]);
+    for r in records.iter() {
+        assert_eq!(r["ModuleName"], "test_tracing_bridge::net");
+    }
+    let descs:Vec<&str> = records.iter().map(|r| r["LogDesc"].as_str().unwrap()).collect();
+    assert_eq!(descs, vec!["connect failed", "retry", "connected", "send", "state changed"]);
+    assert_eq!(records[0]["Fields"], serde_json::json!([["peer",{"LOG_STR":"1.1.1.1:80"}]]));
+    assert_eq!(records[1]["Fields"], serde_json::json!([["retry",{"LOG_UINT":3}],["ok",{"LOG_BOOL":false}]]));
+    assert_eq!(records[2]["Fields"], serde_json::json!([["ratio",{"LOG_FLOAT":0.5}],["delta",{"LOG_INT":-2}]]));
+    assert!(records[4].get("Fields").is_none());
+}
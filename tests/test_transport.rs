@@ -0,0 +1,150 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+//two rsm nodes on the loopback, each node is a child process running this test with RSM_TEST_NODE set
+use rust_rsm::common::errcode;
+use rust_rsm::rsm::{self,config};
+use std::net::SocketAddr;
+use std::process::{Command,Child};
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::time::{Duration,Instant};
+use std::thread;
+
+const TEST_APP_ID:u32 = rsm::RSM_USER_CID_START+20;
+const MSG_ECHO_REQ:u32 = rsm::RSM_USER_MESSAGE_ID_START+1;
+const MSG_ECHO_RSP:u32 = rsm::RSM_USER_MESSAGE_ID_START+2;
+const MSG_LOCAL:u32 = rsm::RSM_USER_MESSAGE_ID_START+3;
+//ask the app of node 1 to send an echo request, so the request has a sender to reply
+const MSG_KICK:u32 = rsm::RSM_USER_MESSAGE_ID_START+4;
+const ECHO_COUNT:usize = 20;
+
+static gEchoReplies:AtomicUsize = AtomicUsize::new(0);
+static gLocalMsgs:AtomicUsize = AtomicUsize::new(0);
+
+struct echo_app_t;
+
+fn new_echo_app(_cid:&rsm::rsm_component_t)->&'static mut dyn rsm::Runnable {
+    return Box::leak(Box::new(echo_app_t))
+}
+
+impl rsm::Runnable for echo_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,_event:rsm::rsm_socket_event_t) {
+    }
+    fn on_message(&mut self,_cid:&rsm::rsm_component_t,msg_id:rsm::rsm_message_id_t,msg:&rsm::rsm_message_t) {
+        match msg_id {
+            MSG_ECHO_REQ=> {
+                let sender = rsm::get_sender_cid().unwrap();
+                let seq = msg.decode::<u32>().unwrap();
+                rsm::send_asyn_msg_ext(&sender, MSG_ECHO_RSP, &seq);
+            },
+            MSG_ECHO_RSP=> {
+                gEchoReplies.fetch_add(1, Ordering::SeqCst);
+            },
+            MSG_LOCAL=> {
+                gLocalMsgs.fetch_add(1, Ordering::SeqCst);
+            },
+            MSG_KICK=> {
+                let peer = rsm::rsm_component_t::new(TEST_APP_ID, 2, 1);
+                let seq = msg.decode::<u32>().unwrap();
+                assert_eq!(rsm::send_asyn_msg_ext(&peer, MSG_ECHO_REQ, &seq), errcode::RESULT_SUCCESS);
+            },
+            _=>(),
+        }
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+}
+
+fn node_addr(base_port:u16,node_id:u32)->SocketAddr {
+    return SocketAddr::new("127.0.0.1".parse().unwrap(), base_port+node_id as u16)
+}
+
+fn run_node(node_id:u32,proto:config::E_RSM_TRANSPORT_PROTO,base_port:u16) {
+    let log_addr = node_addr(base_port+100, node_id);
+    let oam_addr = node_addr(base_port+200, node_id);
+    let mut cfg = config::rsm_init_cfg_t::new(node_id, Some(log_addr), Some(oam_addr), None);
+    cfg.transport = proto;
+    cfg.add_node(1, &node_addr(base_port, 1));
+    cfg.add_node(2, &node_addr(base_port, 2));
+    rsm::rsm_init(&cfg);
+    let attrs = rsm::component_attrs_t::new(&TEST_APP_ID, "echo_app", 1, 1024, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    rsm::registry_component(TEST_APP_ID, &attrs, new_echo_app);
+    rsm::start_rsm();
+    thread::sleep(Duration::from_millis(300));
+    if node_id!=1 {
+        //serve the echo requests until killed by the test
+        thread::sleep(Duration::from_secs(30));
+        return
+    }
+
+    //a node id not in the node table is not found, only the legacy node id 1 is regarded as the local node
+    let unknown = rsm::rsm_component_t::new(TEST_APP_ID, 9, 1);
+    assert_eq!(rsm::send_asyn_msg_ext(&unknown, MSG_LOCAL, &0u32), errcode::ERROR_NOT_FOUND);
+    assert_eq!(rsm::send_asyn_priority_msg(&unknown, rsm::rsm_message_t::new(MSG_LOCAL, &0u32).unwrap()), errcode::ERROR_NOT_FOUND);
+
+    let app = rsm::rsm_component_t::new(TEST_APP_ID, 1, 1);
+    let start = Instant::now();
+    let mut seq = 0u32;
+    //the peer may not be ready, keep sending until enough replies are received
+    while gEchoReplies.load(Ordering::SeqCst)<ECHO_COUNT {
+        assert!(start.elapsed()<Duration::from_secs(15), "echo replies={}", gEchoReplies.load(Ordering::SeqCst));
+        let ret = rsm::send_asyn_msg_ext(&app, MSG_KICK, &seq);
+        assert_eq!(ret, errcode::RESULT_SUCCESS);
+        seq+=1;
+        thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(gLocalMsgs.load(Ordering::SeqCst), 0);
+    let stats = rsm::rsm_transport::get_peer_stats();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].node_id, 2);
+    assert!(stats[0].recv_msg>=ECHO_COUNT as u64);
+}
+
+fn spawn_node(node_id:u32,proto:&str,base_port:u16)->Child {
+    return Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "test_transport_loopback", "--nocapture"])
+        .env("RSM_TEST_NODE", node_id.to_string())
+        .env("RSM_TEST_PROTO", proto)
+        .env("RSM_TEST_PORT", base_port.to_string())
+        .spawn().unwrap()
+}
+
+#[test]
+fn test_transport_loopback() {
+    if let Ok(node) = std::env::var("RSM_TEST_NODE") {
+        let proto = match std::env::var("RSM_TEST_PROTO").unwrap().as_str() {
+            "tcp"=>config::E_RSM_TRANSPORT_PROTO::TRANSPORT_TCP,
+            _=>config::E_RSM_TRANSPORT_PROTO::TRANSPORT_UDP,
+        };
+        let port = std::env::var("RSM_TEST_PORT").unwrap().parse::<u16>().unwrap();
+        run_node(node.parse::<u32>().unwrap(), proto, port);
+        return
+    }
+
+    for (proto,base_port) in [("udp",16300u16),("tcp",16310u16)] {
+        let mut peer = spawn_node(2, proto, base_port);
+        let mut node = spawn_node(1, proto, base_port);
+        let start = Instant::now();
+        let status = loop {
+            if let Some(s) = node.try_wait().unwrap() {
+                break Some(s)
+            }
+            if start.elapsed()>Duration::from_secs(20) {
+                let _ = node.kill();
+                break None
+            }
+            thread::sleep(Duration::from_millis(50));
+        };
+        let _ = peer.kill();
+        let _ = peer.wait();
+        assert!(status.map_or(false, |s| s.success()), "{} transport failed", proto);
+    }
+}
@@ -0,0 +1,163 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![cfg(unix)]
+
+//unix domain stream, listener and datagram sockets, on a file system path and in the abstract namespace
+use rust_rsm::common::errcode;
+use rust_rsm::rsm::{self,socket};
+use rust_rsm::rsm::socket::UnixSocketAddr;
+use std::io::{Read,Write};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize,Ordering};
+use std::time::Duration;
+
+mod test_common;
+use test_common::{wait_until,start_test_rsm};
+
+const TEST_APP_ID:u32 = rsm::RSM_USER_CID_START+64;
+const MSG_CONNECT_ABSTRACT:u32 = rsm::RSM_USER_MESSAGE_ID_START+1;
+const ABSTRACT_HELLO:&[u8] = b"hello abstract";
+
+static gInited:AtomicUsize = AtomicUsize::new(0);
+static gRebindResult:AtomicUsize = AtomicUsize::new(0);
+//the data received by the accepted connections
+static gStreamData:Mutex<Vec<Vec<u8>>> = Mutex::new(Vec::new());
+static gPeerCreds:Mutex<Vec<socket::UnixCredential>> = Mutex::new(Vec::new());
+static gDgramPeers:Mutex<Vec<UnixSocketAddr>> = Mutex::new(Vec::new());
+
+struct unix_app_t {
+    listeners:Vec<socket::UnixListener>,
+    dgram:Option<socket::UnixDatagram>,
+    client:Option<socket::UnixStream>,
+}
+
+fn new_unix_app(_cid:&rsm::rsm_component_t)->Box<dyn rsm::Runnable> {
+    return Box::new(unix_app_t { listeners:Vec::new(), dgram:None, client:None })
+}
+
+fn test_path(name:&str)->String {
+    return std::env::temp_dir().join(format!("rsm_test_{}_{}.sock", name, std::process::id())).to_string_lossy().to_string()
+}
+
+fn abstract_name()->Vec<u8> {
+    return format!("rsm_test_abstract_{}", std::process::id()).into_bytes()
+}
+
+impl rsm::Runnable for unix_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+        let path = UnixSocketAddr::from_path(&test_path("stream"));
+        self.listeners.push(socket::UnixListener::new(&path, 16, socket::SOCKET_LB_POLICY::SOCK_LB_CALLER_INSTANCE).unwrap());
+        //the path is in use
+        if let Err(e) = socket::UnixListener::new(&path, 16, socket::SOCKET_LB_POLICY::SOCK_LB_CALLER_INSTANCE) {
+            gRebindResult.store(e as usize, Ordering::SeqCst);
+        }
+        #[cfg(target_os="linux")]
+        {
+            let name = UnixSocketAddr::from_abstract_name(&abstract_name());
+            let lis = socket::UnixListener::new(&name, 16, socket::SOCKET_LB_POLICY::SOCK_LB_CALLER_INSTANCE).unwrap();
+            assert_eq!(lis.get_local_addr(), Some(name));
+            self.listeners.push(lis);
+        }
+        self.dgram = Some(socket::UnixDatagram::new(&UnixSocketAddr::from_path(&test_path("dgram"))).unwrap());
+        gInited.fetch_add(1, Ordering::SeqCst);
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,event:rsm::rsm_socket_event_t) {
+        let mut buf = [0u8;256];
+        if let Some(dgram) = &mut self.dgram {
+            if dgram.get_socket_id()==event.socket_id {
+                //reply to the sender
+                while let Ok((len,peer)) = dgram.recv_from(&mut buf) {
+                    gDgramPeers.lock().unwrap().push(peer.clone());
+                    let mut reply = b"reply ".to_vec();
+                    reply.extend_from_slice(&buf[0..len]);
+                    assert_eq!(dgram.send_to(&peer, &reply), Ok(reply.len()));
+                }
+                return
+            }
+        }
+        if self.client.as_ref().map_or(false, |c| c.get_socket_id()==event.socket_id) {
+            return
+        }
+        let mut stream = socket::UnixStream::get_socket_by_id(event.socket_id);
+        if (event.event & rsm::SOCK_EVENT_NEW)!=0 {
+            gPeerCreds.lock().unwrap().push(stream.get_peer_cred().unwrap());
+        }
+        if (event.event & rsm::SOCK_EVENT_READ)!=0 {
+            //echo the data
+            while let Ok(len) = stream.recv(&mut buf) {
+                if len==0 {
+                    break;
+                }
+                gStreamData.lock().unwrap().push(buf[0..len].to_vec());
+                assert_eq!(stream.send(&buf[0..len]), Ok(len));
+            }
+        }
+    }
+    fn on_message(&mut self,_cid:&rsm::rsm_component_t,msg_id:rsm::rsm_message_id_t,_msg:&rsm::rsm_message_t) {
+        if msg_id!=MSG_CONNECT_ABSTRACT {
+            return
+        }
+        let mut client = socket::UnixStream::connect(&UnixSocketAddr::from_abstract_name(&abstract_name())).unwrap();
+        assert_eq!(client.send(ABSTRACT_HELLO), Ok(ABSTRACT_HELLO.len()));
+        self.client = Some(client);
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+}
+
+fn own_cred()->socket::UnixCredential {
+    return socket::UnixCredential {
+        pid:std::process::id() as i32,
+        uid:unsafe { libc::getuid() },
+        gid:unsafe { libc::getgid() },
+    }
+}
+
+#[test]
+fn test_unix_socket() {
+    start_test_rsm(15270, 12270);
+    let attrs = rsm::component_attrs_t::new(&TEST_APP_ID, "unix_app", 1, 64, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    assert_eq!(rsm::registry_boxed_component(TEST_APP_ID, &attrs, &rsm::component_policy_t::default(), new_unix_app), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gInited.load(Ordering::SeqCst)==1));
+    assert_eq!(gRebindResult.load(Ordering::SeqCst), errcode::ERROR_ALREADY_EXIST as usize);
+
+    //a stream connection on the path, the data is echoed by the accepted connection
+    let mut peer = std::os::unix::net::UnixStream::connect(test_path("stream")).unwrap();
+    peer.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    peer.write_all(b"hello path").unwrap();
+    let mut buf = [0u8;10];
+    peer.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello path");
+    assert!(wait_until(|| gPeerCreds.lock().unwrap().len()==1));
+    assert_eq!(gPeerCreds.lock().unwrap()[0], own_cred());
+
+    //a connection in the abstract namespace between two rsm sockets
+    #[cfg(target_os="linux")]
+    {
+        let app = rsm::rsm_component_t::new(TEST_APP_ID, rsm::get_local_node_id(), 1);
+        assert_eq!(rsm::send_asyn_msg_ext(&app, MSG_CONNECT_ABSTRACT, &0u32), errcode::RESULT_SUCCESS);
+        assert!(wait_until(|| gStreamData.lock().unwrap().iter().any(|d| d==ABSTRACT_HELLO)));
+        assert_eq!(gPeerCreds.lock().unwrap().len(), 2);
+        assert_eq!(gPeerCreds.lock().unwrap()[1], own_cred());
+    }
+
+    //the datagram is replied to the bound path of the sender
+    let peer_path = test_path("dgram_peer");
+    let _ = std::fs::remove_file(&peer_path);
+    let dgram = std::os::unix::net::UnixDatagram::bind(&peer_path).unwrap();
+    dgram.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    assert_eq!(dgram.send_to(b"ping", test_path("dgram")).unwrap(), 4);
+    let mut buf = [0u8;64];
+    let len = dgram.recv(&mut buf).unwrap();
+    assert_eq!(&buf[0..len], b"reply ping");
+    assert_eq!(gDgramPeers.lock().unwrap()[0], UnixSocketAddr::from_path(&peer_path));
+    let _ = std::fs::remove_file(&peer_path);
+    let _ = std::fs::remove_file(test_path("stream"));
+    let _ = std::fs::remove_file(test_path("dgram"));
+}
@@ -0,0 +1,92 @@
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+
+//the deadline alarm is raised for a message handler finished between two watchdog checks
+use rust_rsm::common::errcode;
+use rust_rsm::rsm;
+use std::sync::atomic::{AtomicUsize,AtomicU64,Ordering};
+use std::time::Duration;
+use std::thread;
+
+mod test_common;
+use test_common::{wait_until,start_test_rsm};
+
+const WD_APP_ID:u32 = rsm::RSM_USER_CID_START+70;
+const MSG_FAST:u32 = rsm::RSM_USER_MESSAGE_ID_START+1;
+const MSG_SLOW:u32 = rsm::RSM_USER_MESSAGE_ID_START+2;
+const DEADLINE_USEC:u64 = 5000;
+//shorter than the watchdog check period
+const SLOW_RUN_MSEC:u64 = 30;
+
+static gInits:AtomicUsize = AtomicUsize::new(0);
+static gProcessed:AtomicUsize = AtomicUsize::new(0);
+static gRaised:AtomicUsize = AtomicUsize::new(0);
+static gCleared:AtomicUsize = AtomicUsize::new(0);
+static gAlarmMsgId:AtomicUsize = AtomicUsize::new(0);
+static gAlarmValue:AtomicU64 = AtomicU64::new(0);
+
+struct wd_app_t;
+
+fn new_wd_app(_cid:&rsm::rsm_component_t)->Box<dyn rsm::Runnable> {
+    return Box::new(wd_app_t)
+}
+
+impl rsm::Runnable for wd_app_t {
+    fn on_init(&mut self,_cid:&rsm::rsm_component_t) {
+        gInits.fetch_add(1, Ordering::SeqCst);
+    }
+    fn on_timer(&mut self,_cid:&rsm::rsm_component_t,_timer_id:rsm::rsm_timer_id_t,_timer_data:usize) {
+    }
+    fn on_socket_event(&mut self,_cid:&rsm::rsm_component_t,_event:rsm::rsm_socket_event_t) {
+    }
+    fn on_message(&mut self,_cid:&rsm::rsm_component_t,msg_id:rsm::rsm_message_id_t,_msg:&rsm::rsm_message_t) {
+        if msg_id==MSG_SLOW {
+            thread::sleep(Duration::from_millis(SLOW_RUN_MSEC));
+        }
+        gProcessed.fetch_add(1, Ordering::SeqCst);
+    }
+    fn is_inited(&self)->bool {
+        true
+    }
+    fn on_close(&mut self,_cid:&rsm::rsm_component_t) {
+    }
+}
+
+fn on_alarm(alarm:&rsm::rsm_alarm_t) {
+    if alarm.tid.get_cid()!=WD_APP_ID || alarm.alarm_type!=rsm::E_RSM_ALARM_TYPE::ALARM_DEADLINE_EXCEED {
+        return
+    }
+    if alarm.cleared_at==0 {
+        gAlarmMsgId.store(alarm.msg_id as usize, Ordering::SeqCst);
+        gAlarmValue.store(alarm.value, Ordering::SeqCst);
+        gRaised.fetch_add(1, Ordering::SeqCst);
+    } else {
+        gCleared.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_deadline_overrun() {
+    start_test_rsm(15285, 12285);
+    let attrs = rsm::component_attrs_t::new(&WD_APP_ID, "wd_app", 1, 64, rsm::E_RSM_TASK_PRIORITY::THREAD_PRI_NORMAL, false);
+    let mut policy = rsm::component_policy_t::default();
+    policy.set_deadline(DEADLINE_USEC, 0);
+    assert_eq!(rsm::registry_boxed_component(WD_APP_ID, &attrs, &policy, new_wd_app), errcode::RESULT_SUCCESS);
+    assert_eq!(rsm::register_alarm_callback(on_alarm), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gInits.load(Ordering::SeqCst)==1));
+
+    let dst = rsm::rsm_component_t::new(WD_APP_ID, rsm::get_local_node_id(), 1);
+    assert_eq!(rsm::send_asyn_msg_ext(&dst, MSG_FAST, &0u32), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gProcessed.load(Ordering::SeqCst)==1));
+    thread::sleep(Duration::from_millis(300));
+    assert_eq!(gRaised.load(Ordering::SeqCst), 0);
+
+    //the alarm carries the overrun message and its run time, and is cleared when no more overrun
+    assert_eq!(rsm::send_asyn_msg_ext(&dst, MSG_SLOW, &0u32), errcode::RESULT_SUCCESS);
+    assert!(wait_until(|| gRaised.load(Ordering::SeqCst)==1));
+    assert_eq!(gAlarmMsgId.load(Ordering::SeqCst), MSG_SLOW as usize);
+    assert!(gAlarmValue.load(Ordering::SeqCst)>DEADLINE_USEC);
+    assert!(wait_until(|| gCleared.load(Ordering::SeqCst)==1));
+    assert_eq!(gRaised.load(Ordering::SeqCst), 1);
+}